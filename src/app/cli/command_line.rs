@@ -1,13 +1,21 @@
 use std::{fs::create_dir_all, process::exit, rc::Rc};
 
 use clap::{Parser, Subcommand};
-use miette::{Context, Error, IntoDiagnostic, Result};
-use tabled::{builder::Builder, settings::Style};
+use miette::{bail, Context, Error, IntoDiagnostic, Result};
 
 use crate::{
-    app::{ConfigManager, SessionManager},
-    common::{cmd::ShellRunner, path::to_absolute_path, session_info::SessionInfo},
-    muxer::{create_muxer, Muxer},
+    app::{
+        cli::{format_sessions, group_table, print_dry_run, session_table},
+        manager::session::recent::SessionSort,
+        ConfigManager, SessionManager,
+    },
+    common::{
+        cmd::{RecordingRunner, ShellRunner},
+        config::secrets::{self, Secrets},
+        path::{config_search_path, to_absolute_path},
+        session_info::SessionInfo,
+    },
+    muxer::{create_muxer, create_muxer_with_runner, Muxer},
 };
 
 #[derive(Subcommand, Debug)]
@@ -29,6 +37,14 @@ enum Commands {
         #[clap(short = 'p', long)]
         show_picker: bool,
 
+        /// Start the sessions of a group instead
+        #[clap(short, long, conflicts_with_all = ["name", "file", "show_picker"])]
+        group: Option<String>,
+
+        /// Order of the sessions in the picker
+        #[clap(long, value_enum, default_value_t)]
+        sort: SessionSort,
+
         /// Skip the startup commands
         #[clap(long)]
         skip_cmds: bool,
@@ -41,6 +57,19 @@ enum Commands {
         /// Example: --var name=myproject --var path=/home/user/dev
         #[clap(long = "var")]
         variables: Vec<String>,
+
+        /// Secret variables in key=value format, never stored in the session environment
+        /// Example: --secret-var api_token=abc123
+        #[clap(long = "secret-var")]
+        secret_variables: Vec<String>,
+
+        /// Print the commands that would run, without running them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Print the dry run as JSON
+        #[clap(long, requires = "dry_run")]
+        json: bool,
     },
 
     /// Stop session.
@@ -64,10 +93,80 @@ enum Commands {
         #[clap(short, long)]
         others: bool,
 
+        /// Save a snapshot of the session first, see `laio session restore` (tmux only)
+        #[clap(long, conflicts_with_all = ["all", "others", "dry_run"])]
+        snapshot: bool,
+
+        /// Stop the sessions of a group instead
+        #[clap(short, long, conflicts_with_all = ["name", "all", "others", "snapshot", "variables"])]
+        group: Option<String>,
+
         /// Template variables in key=value format (can be specified multiple times)
         /// Example: --var name=myproject --var path=/home/user/dev
         #[clap(long = "var")]
         variables: Vec<String>,
+
+        /// Secret variables in key=value format, never stored in the session environment
+        /// Example: --secret-var api_token=abc123
+        #[clap(long = "secret-var")]
+        secret_variables: Vec<String>,
+
+        /// Print the commands that would run, without running them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Print the dry run as JSON
+        #[clap(long, requires = "dry_run")]
+        json: bool,
+    },
+
+    /// Restart session with the config and variables it was started with.
+    Restart {
+        /// Name of the session to restart, defaults to the current session.
+        name: Option<String>,
+
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
+
+        /// Skip the shutdown and startup commands
+        #[clap(long)]
+        skip_cmds: bool,
+
+        /// Skip attaching to session
+        #[clap(long)]
+        skip_attach: bool,
+
+        /// Template variables in key=value format, replacing the ones the session was
+        /// started with. Example: --var path=/home/user/dev
+        #[clap(long = "var")]
+        variables: Vec<String>,
+
+        /// Secret variables in key=value format, never stored in the session environment
+        /// Example: --secret-var api_token=abc123
+        #[clap(long = "secret-var")]
+        secret_variables: Vec<String>,
+    },
+
+    /// Start the laio sessions that were running, e.g. after a reboot.
+    Resurrect {
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
+
+        /// Skip the startup commands
+        #[clap(long)]
+        skip_cmds: bool,
+    },
+
+    /// Switch to a running session, or with '-' to the one used before the current one
+    Switch {
+        /// Name of the session, or '-' for the previous one
+        name: String,
+
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
     },
 
     /// List active (*) and available sessions
@@ -80,10 +179,27 @@ enum Commands {
         /// Output as JSON.
         #[clap(short, long)]
         json: bool,
+
+        /// List the sessions of a group, or the groups if no name is given
+        #[clap(short, long)]
+        group: Option<Option<String>>,
+
+        /// Only list configs with this tag
+        #[clap(short, long, conflicts_with = "group")]
+        tag: Option<String>,
+
+        /// Print every entry with a template, such as '{{ name }} {{ windows }}'
+        #[clap(short, long, conflicts_with_all = ["json", "group"])]
+        format: Option<String>,
+
+        /// Order of the entries
+        #[clap(short, long, value_enum, default_value_t, conflicts_with = "group")]
+        sort: SessionSort,
     },
 
     Config(super::config::cli::Cli),
     Session(super::session::cli::Cli),
+    Window(super::window::cli::Cli),
     Completion(super::completion::Cli),
 }
 
@@ -96,23 +212,31 @@ pub struct Cli {
     #[command(subcommand)]
     commands: Commands,
 
-    #[arg[long, default_value = "~/.config/laio", global=true]]
-    pub config_dir: String,
+    /// Config directory, or colon-separated list of directories searched in order.
+    /// Defaults to LAIO_CONFIG_PATH, then $XDG_CONFIG_HOME/laio or ~/.config/laio.
+    #[arg[long, global=true]]
+    pub config_dir: Option<String>,
 
     #[clap(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// Secrets known to this invocation.
+    #[clap(skip)]
+    secrets: Secrets,
 }
 
 impl Cli {
     pub fn run(&self) -> Result<()> {
-        let config_path = to_absolute_path(&self.config_dir)?;
+        let config_dir = self.config_path();
+        let primary_dir = config_dir
+            .split(':')
+            .find(|dir| !dir.is_empty())
+            .unwrap_or(".");
+        let config_path = to_absolute_path(primary_dir)?;
         if !config_path.exists() {
             create_dir_all(config_path)
                 .into_diagnostic()
-                .wrap_err(format!(
-                    "Could not access config path '{}'",
-                    &self.config_dir
-                ))?;
+                .wrap_err(format!("Could not access config path '{}'", primary_dir))?;
         }
         let res = match &self.commands {
             Commands::Start {
@@ -120,19 +244,32 @@ impl Cli {
                 file,
                 muxer,
                 show_picker,
+                group,
+                sort,
                 skip_cmds,
                 skip_attach,
                 variables,
+                secret_variables,
+                dry_run,
+                json,
             } => self
-                .session(muxer)?
-                .start(
-                    name,
-                    file,
-                    variables,
-                    *show_picker,
-                    *skip_cmds,
-                    *skip_attach,
-                )
+                .with_session(muxer, *dry_run, *json, |session| {
+                    let session = session.with_picker_sort(*sort);
+                    let variables = secrets::with_secret_variables(variables, secret_variables)?;
+                    match group {
+                        Some(group) => {
+                            session.start_group(group, &variables, *skip_cmds, *skip_attach)
+                        }
+                        None => session.start(
+                            name,
+                            file,
+                            &variables,
+                            *show_picker,
+                            *skip_cmds,
+                            *skip_attach,
+                        ),
+                    }
+                })
                 .wrap_err("Could not start session!".to_string()),
             Commands::Stop {
                 name,
@@ -140,51 +277,130 @@ impl Cli {
                 skip_cmds: skip_shutdown_cmds,
                 all: stop_all,
                 others: stop_other,
+                snapshot,
+                group,
                 variables,
+                secret_variables,
+                dry_run,
+                json,
             } => self
-                .session(muxer)?
-                .stop(name, variables, *skip_shutdown_cmds, *stop_all, *stop_other)
+                .secrets
+                .provide(secret_variables)
+                .and_then(|_| {
+                    self.with_session(muxer, *dry_run, *json, |session| {
+                        if let Some(group) = group {
+                            return session.stop_group(group, *skip_shutdown_cmds);
+                        }
+                        if *snapshot {
+                            let path = session.snapshot(name)?;
+                            println!("Saved snapshot to {}", path.display());
+                        }
+                        session.stop(name, variables, *skip_shutdown_cmds, *stop_all, *stop_other)
+                    })
+                })
                 .wrap_err("Unable to stop session(s)!"),
-            Commands::List { muxer, json } => {
-                let session_info = self
-                    .session(muxer)?
+            Commands::Restart {
+                name,
+                muxer,
+                skip_cmds,
+                skip_attach,
+                variables,
+                secret_variables,
+            } => self
+                .session(muxer)
+                .and_then(|session| {
+                    session.restart(
+                        name,
+                        &secrets::with_secret_variables(variables, secret_variables)?,
+                        *skip_cmds,
+                        *skip_attach,
+                    )
+                })
+                .wrap_err("Could not restart session!"),
+            Commands::Resurrect { muxer, skip_cmds } => self
+                .session(muxer)
+                .and_then(|session| session.resurrect(*skip_cmds))
+                .and_then(|results| {
+                    if results.is_empty() {
+                        println!("No sessions to resurrect");
+                    }
+                    for (name, result) in &results {
+                        match result {
+                            Ok(()) => println!("Started '{name}'"),
+                            Err(e) => eprintln!("Could not start '{name}': {e:?}"),
+                        }
+                    }
+                    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+                    if failed > 0 {
+                        bail!(
+                            "{failed} of {} session(s) could not be started",
+                            results.len()
+                        );
+                    }
+                    Ok(())
+                })
+                .wrap_err("Could not resurrect sessions!"),
+            Commands::List {
+                muxer,
+                json,
+                group: Some(group),
+                ..
+            } => {
+                let session = self.session(muxer)?;
+                let output = match (group, json) {
+                    (Some(group), true) => {
+                        serde_json::to_string_pretty(&session.list_group(group)?)
+                            .into_diagnostic()?
+                    }
+                    (Some(group), false) => session_table(&session.list_group(group)?).to_string(),
+                    (None, true) => {
+                        serde_json::to_string_pretty(&session.list_groups()?).into_diagnostic()?
+                    }
+                    (None, false) => group_table(&session.list_groups()?).to_string(),
+                };
+                println!("{output}");
+                Ok(())
+            }
+            Commands::Switch { name, muxer } => self
+                .session(muxer)
+                .and_then(|session| session.switch(name))
+                .wrap_err(format!("Unable to switch to '{name}'")),
+            Commands::List {
+                muxer,
+                json,
+                group: None,
+                tag,
+                format,
+                sort,
+            } => {
+                let session = self.session(muxer)?;
+                let session_info = session
                     .list()
                     .wrap_err("Could not retrieve active sessions.".to_string())?;
-                let config: Vec<String> = self
+                let configs = self
                     .config()
                     .list()
                     .wrap_err("Could not retrieve configurations.".to_string())?;
 
-                let session_names: Vec<String> =
-                    session_info.iter().map(|s| s.name.clone()).collect();
-
-                let mut merged: Vec<SessionInfo> = session_info;
-                merged.extend(
-                    config
-                        .iter()
-                        .filter(|c| !session_names.contains(c))
-                        .map(|s| SessionInfo::inactive(s.to_string())),
-                );
-                merged.sort_by(|a, b| a.name.cmp(&b.name));
-                merged.dedup_by(|a, b| a.name == b.name);
+                let mut merged = SessionInfo::merge(session_info, configs);
+                if let Some(tag) = tag {
+                    merged.retain(|info| info.has_tag(tag));
+                }
+                session.sort(&mut merged, *sort);
 
-                if *json {
+                if let Some(format) = format {
+                    print!("{}", format_sessions(&merged, format)?);
+                } else if *json {
                     let json_output = serde_json::to_string_pretty(&merged).into_diagnostic()?;
                     println!("{}", json_output);
                 } else {
-                    let records: Vec<_> = merged
-                        .iter()
-                        .map(|item| [item.status.icon(), item.name.as_str()])
-                        .collect();
-                    let builder = Builder::from_iter(records);
-                    let mut table = builder.build();
-                    table.with(Style::rounded().remove_horizontals());
-                    println!("{}", table);
+                    println!("{}", session_table(&merged));
                 }
                 Ok(())
             }
-            Commands::Config(cli) => cli.run(&self.config_dir),
-            Commands::Session(cli) => cli.run(&self.config_dir),
+            Commands::Config(cli) => cli.run(&config_dir, &self.secrets),
+            Commands::Session(cli) => cli.run(&config_dir, &self.secrets),
+            Commands::Window(cli) => cli.run(&config_dir, &self.secrets),
             Commands::Completion(cli) => cli.run(),
         };
 
@@ -196,13 +412,46 @@ impl Cli {
         res
     }
 
+    /// Colon-separated config search path.
+    fn config_path(&self) -> String {
+        config_search_path(self.config_dir.as_deref())
+    }
+
     fn session(&self, muxer: &Option<Muxer>) -> Result<SessionManager> {
-        let muxer = create_muxer(muxer).wrap_err("Could not create desired multiplexer")?;
-        Ok(SessionManager::new(&self.config_dir, muxer))
+        let muxer =
+            create_muxer(muxer, &self.secrets).wrap_err("Could not create desired multiplexer")?;
+        Ok(SessionManager::new(&self.config_path(), muxer)
+            .with_secrets(self.secrets.clone())
+            .with_session_record())
+    }
+
+    /// Runs `f` with the session manager. With `dry_run`, the commands it would run
+    /// are printed instead.
+    fn with_session(
+        &self,
+        muxer: &Option<Muxer>,
+        dry_run: bool,
+        json: bool,
+        f: impl FnOnce(SessionManager) -> Result<()>,
+    ) -> Result<()> {
+        if !dry_run {
+            return f(self.session(muxer)?);
+        }
+
+        let runner = RecordingRunner::new(self.secrets.clone());
+        let multiplexer = create_muxer_with_runner(muxer, runner.clone(), &self.secrets)
+            .wrap_err("Could not create desired multiplexer")?;
+        f(SessionManager::new(&self.config_path(), multiplexer)
+            .with_secrets(self.secrets.clone()))?;
+        print_dry_run(&runner.recorded(), json)
     }
 
     fn config(&self) -> ConfigManager<ShellRunner> {
-        ConfigManager::new(&self.config_dir, Rc::new(ShellRunner::new()))
+        ConfigManager::new(
+            &self.config_path(),
+            Rc::new(ShellRunner::new(self.secrets.clone())),
+        )
+        .with_secrets(self.secrets.clone())
     }
 
     fn handle_error(&self, error: &Error) {
@@ -217,7 +466,14 @@ impl Cli {
         println!();
         println!("{error:?}");
         println!();
-        if let Commands::Start { name, muxer, .. } = &self.commands {
+        // A dry run has nothing to clean up
+        if let Commands::Start {
+            name,
+            muxer,
+            dry_run: false,
+            ..
+        } = &self.commands
+        {
             if let Some(n) = name {
                 log::warn!("Shutting down session: {n}");
                 let _ = self
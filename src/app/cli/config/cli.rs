@@ -2,11 +2,18 @@ use std::rc::Rc;
 
 use clap::{Args, Subcommand};
 use miette::{Context, IntoDiagnostic, Result};
-use tabled::{builder::Builder, settings::Style};
 
 use crate::{
-    app::{ConfigManager, SessionManager},
-    common::{cmd::ShellRunner, session_info::SessionInfo},
+    app::{
+        cli::session_table,
+        manager::config::{export::ExportFormat, import::ImportFormat},
+        ConfigManager, SessionManager,
+    },
+    common::{
+        cmd::ShellRunner,
+        config::secrets::{self, Secrets},
+        session_info::SessionInfo,
+    },
     muxer::create_muxer,
 };
 
@@ -24,6 +31,51 @@ pub enum Commands {
         /// Template variable (repeatable, e.g., --var name=value)
         #[clap(long = "var")]
         variables: Vec<String>,
+
+        /// Generate the config from project files in the current directory.
+        #[clap(short, long, conflicts_with_all = ["copy", "variables"])]
+        detect: bool,
+    },
+
+    /// Import a tmuxinator, tmuxp or Zellij configuration.
+    Import {
+        /// The configuration file to import.
+        file: String,
+
+        /// Format of the configuration file.
+        #[clap(long = "from")]
+        from: ImportFormat,
+
+        /// Name of the new configuration, defaults to the session name.
+        #[clap(short, long)]
+        name: Option<String>,
+
+        /// Overwrite an existing configuration.
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Export laio configuration as a Zellij layout, tmux script or tmuxinator project.
+    Export {
+        /// Name of the configuration to export.
+        name: String,
+
+        /// Format to export to.
+        #[clap(long)]
+        format: ExportFormat,
+
+        /// File to write to, omit to print to stdout.
+        #[clap(short, long)]
+        output: Option<String>,
+
+        /// Template variable (repeatable, e.g., --var name=value)
+        #[clap(long = "var")]
+        variables: Vec<String>,
+
+        /// Secret template variable (repeatable, e.g., --secret-var token=value),
+        /// masked in the export
+        #[clap(long = "secret-var")]
+        secret_variables: Vec<String>,
     },
 
     /// Edit laio configuration.
@@ -54,6 +106,10 @@ pub enum Commands {
         /// Template variable (repeatable, e.g., --var name=value)
         #[clap(long = "var")]
         variables: Vec<String>,
+
+        /// Secret template variable (repeatable, e.g., --secret-var token=value)
+        #[clap(long = "secret-var")]
+        secret_variables: Vec<String>,
     },
 
     /// Delete laio configuration.
@@ -89,55 +145,87 @@ pub struct Cli {
 }
 
 impl Cli {
-    pub fn run(&self, config_path: &str) -> Result<()> {
-        let cfg = ConfigManager::new(config_path, Rc::new(ShellRunner::new()));
+    pub fn run(&self, config_path: &str, secrets: &Secrets) -> Result<()> {
+        let cfg = ConfigManager::new(config_path, Rc::new(ShellRunner::new(secrets.clone())))
+            .with_secrets(secrets.clone());
 
         match &self.commands {
             Commands::Create {
                 name,
                 copy,
                 variables,
-            } => cfg.create(name, copy, variables),
+                detect,
+            } => {
+                if *detect {
+                    cfg.create_detected(name)
+                } else {
+                    cfg.create(name, copy, variables)
+                }
+            }
+            Commands::Import {
+                file,
+                from,
+                name,
+                force,
+            } => {
+                let (config_file, warnings) = cfg.import(from, file, name, *force)?;
+                for warning in warnings {
+                    eprintln!("Warning: {warning}");
+                }
+                println!("Imported '{file}' to '{}'", config_file.display());
+                Ok(())
+            }
+            Commands::Export {
+                name,
+                format,
+                output,
+                variables,
+                secret_variables,
+            } => {
+                let export = cfg.export(
+                    name,
+                    format,
+                    output,
+                    &secrets::with_secret_variables(variables, secret_variables)?,
+                )?;
+                for warning in export.warnings {
+                    eprintln!("Warning: {warning}");
+                }
+                match output {
+                    Some(output) => println!("Exported '{name}' to '{output}'"),
+                    None => print!("{}", export.content),
+                }
+                Ok(())
+            }
             Commands::Edit { name } => cfg.edit(name),
             Commands::Link { name, file } => cfg.link(name, file),
             Commands::Validate {
                 name,
                 file,
                 variables,
-            } => cfg.validate(name, Some(file), variables),
+                secret_variables,
+            } => cfg.validate(
+                name,
+                Some(file),
+                &secrets::with_secret_variables(variables, secret_variables)?,
+            ),
             Commands::Delete { name, force } => cfg.delete(name, *force),
             Commands::List { muxer, json } => {
-                let muxer =
-                    create_muxer(muxer).wrap_err("Could not create desired multiplexer.")?;
-                let session_manager = SessionManager::new(config_path, muxer);
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let session_manager =
+                    SessionManager::new(config_path, muxer).with_secrets(secrets.clone());
 
                 let sessions = session_manager.list()?;
                 let configs = cfg.list()?;
 
-                let session_names: Vec<String> = sessions.iter().map(|s| s.name.clone()).collect();
-
-                let mut merged: Vec<SessionInfo> = sessions;
-                merged.extend(
-                    configs
-                        .iter()
-                        .filter(|c| !session_names.contains(c))
-                        .map(|s| SessionInfo::inactive(s.to_string())),
-                );
-                merged.sort_by(|a, b| a.name.cmp(&b.name));
-                merged.dedup_by(|a, b| a.name == b.name);
+                let merged = SessionInfo::merge(sessions, configs);
 
                 if *json {
                     let json_output = serde_json::to_string_pretty(&merged).into_diagnostic()?;
                     println!("{}", json_output);
                 } else {
-                    let records: Vec<_> = merged
-                        .iter()
-                        .map(|item| [item.status.icon(), item.name.as_str()])
-                        .collect();
-                    let builder = Builder::from_iter(records);
-                    let mut table = builder.build();
-                    table.with(Style::rounded().remove_horizontals());
-                    println!("{}", table);
+                    println!("{}", session_table(&merged));
                 }
                 Ok(())
             }
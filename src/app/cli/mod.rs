@@ -2,4 +2,128 @@ mod command_line;
 mod completion;
 mod config;
 mod session;
+mod window;
 pub use command_line::Cli;
+
+use std::env;
+
+use miette::{miette, IntoDiagnostic, Result};
+use serde_json::Value;
+use tabled::{builder::Builder, settings::Style, Table};
+use tera::{Context, Tera};
+
+use crate::{
+    app::manager::session::group::Group,
+    common::{
+        cmd::{quote, RecordedCommand},
+        session_info::SessionInfo,
+    },
+};
+
+/// Renders sessions as a table, adding columns for descriptions, tags and the config
+/// directory when known.
+fn session_table(sessions: &[SessionInfo]) -> Table {
+    let show_descriptions = sessions.iter().any(|s| s.description.is_some());
+    let show_tags = sessions.iter().any(|s| !s.tags.is_empty());
+    let show_dirs = sessions.iter().any(|s| s.config_dir.is_some());
+    let records: Vec<Vec<String>> = sessions
+        .iter()
+        .map(|item| {
+            let mut record = vec![item.status.icon().to_string(), item.name.clone()];
+            if show_descriptions {
+                record.push(item.description.clone().unwrap_or_default());
+            }
+            if show_tags {
+                let tags: Vec<String> = item.tags.iter().map(|tag| format!("#{tag}")).collect();
+                record.push(tags.join(" "));
+            }
+            if show_dirs {
+                record.push(item.config_dir.clone().unwrap_or_default());
+            }
+            record
+        })
+        .collect();
+    let mut table = Builder::from_iter(records).build();
+    table.with(Style::rounded().remove_horizontals());
+    table
+}
+
+/// Renders groups as a table of their names and the configs of their sessions.
+fn group_table(groups: &[Group]) -> Table {
+    let records = groups.iter().map(|group| {
+        let configs: Vec<&str> = group.sessions.iter().map(|m| m.config.as_str()).collect();
+        vec![group.name.clone(), configs.join(", ")]
+    });
+    let mut table = Builder::from_iter(records).build();
+    table.with(Style::rounded().remove_horizontals());
+    table
+}
+
+/// Fields of [`SessionInfo`] that `--format` can use even where they aren't known,
+/// with their value in that case.
+const FORMAT_FIELDS: [(&str, Value); 14] = [
+    ("session", Value::Null),
+    ("description", Value::Null),
+    ("tags", Value::Array(vec![])),
+    ("config_dir", Value::Null),
+    ("config_path", Value::Null),
+    ("config_source", Value::Null),
+    ("muxer", Value::Null),
+    ("windows", Value::Null),
+    ("panes", Value::Null),
+    ("clients", Value::Null),
+    ("created", Value::Null),
+    ("last_activity", Value::Null),
+    ("variables", Value::Array(vec![])),
+    ("is_laio_session", Value::Null),
+];
+
+/// Renders every session with the Tera template `format`, one per line, skipping
+/// sessions it renders nothing for. Besides the fields of the JSON output, `icon` is
+/// the symbol of the status.
+fn format_sessions(sessions: &[SessionInfo], format: &str) -> Result<String> {
+    let mut tera = Tera::default();
+    tera.autoescape_on(Vec::<&str>::new());
+
+    let mut lines = sessions
+        .iter()
+        .map(|info| {
+            let mut value = serde_json::to_value(info).into_diagnostic()?;
+            if let Value::Object(fields) = &mut value {
+                for (field, default) in &FORMAT_FIELDS {
+                    fields.entry(*field).or_insert(default.clone());
+                }
+                fields.insert("icon".to_string(), info.status.icon().into());
+            }
+            let context = Context::from_serialize(&value)
+                .map_err(|e| miette!("Template context error: {}", e))?;
+            tera.render_str(format, &context, false)
+                .map_err(|e| miette!("Invalid format: {}", e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    lines.retain(|line| !line.is_empty());
+    Ok(lines.iter().map(|line| format!("{line}\n")).collect())
+}
+
+/// Prints the commands of a dry run, with a `cd` wherever the directory changes.
+fn print_dry_run(commands: &[RecordedCommand], json: bool) -> Result<()> {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(commands).into_diagnostic()?
+        );
+        return Ok(());
+    }
+
+    let mut cwd = env::current_dir()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    for command in commands {
+        if command.cwd != cwd {
+            cwd = command.cwd.clone();
+            println!("cd {}", quote(&cwd));
+        }
+        println!("{command}");
+    }
+    Ok(())
+}
@@ -1,11 +1,15 @@
 use crate::{
-    app::SessionManager,
+    app::{
+        cli::{format_sessions, session_table},
+        SessionManager,
+    },
+    common::config::secrets::Secrets,
     muxer::{create_muxer, Muxer},
 };
 
 use clap::{Args, Subcommand};
 use miette::{Context, IntoDiagnostic, Result};
-use tabled::{builder::Builder, settings::Style};
+use std::process::exit;
 
 #[derive(Debug, Subcommand, Clone)]
 pub(crate) enum Commands {
@@ -19,11 +23,101 @@ pub(crate) enum Commands {
         /// Output as JSON.
         #[clap(short, long)]
         json: bool,
+
+        /// Print every session with a template, such as '{{ name }} {{ windows }}'
+        #[clap(short, long, conflicts_with = "json")]
+        format: Option<String>,
     },
 
     /// Shows current session layout as yaml.
     #[clap()]
     Yaml {
+        /// Overlay changes onto the config the session was started from.
+        #[clap(short, long)]
+        overlay: bool,
+
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
+    },
+
+    /// Save current session layout as a configuration.
+    Save {
+        /// Name of the configuration, defaults to the session name.
+        name: Option<String>,
+
+        /// Save as .laio.yaml in the current directory.
+        #[clap(short, long, conflicts_with = "name")]
+        local: bool,
+
+        /// Overwrite an existing configuration.
+        #[clap(short, long)]
+        force: bool,
+
+        /// Overlay changes onto the config the session was started from.
+        #[clap(short, long)]
+        overlay: bool,
+
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
+    },
+
+    /// Add windows and panes missing from a session and re-apply its layouts.
+    Apply {
+        /// Name of the session, defaults to the current session.
+        name: Option<String>,
+
+        /// Close windows and panes that are not in the config.
+        #[clap(long)]
+        prune: bool,
+
+        /// Skip the commands of new panes.
+        #[clap(long)]
+        skip_cmds: bool,
+
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
+    },
+
+    /// Save the layout and pane content of a session, to restore it later (tmux only).
+    Snapshot {
+        /// Name of the session, defaults to the current session.
+        name: Option<String>,
+
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
+    },
+
+    /// Start a session from its snapshot, printing the saved pane content.
+    Restore {
+        /// Name of the snapshot, the name of the session it was taken of.
+        snapshot: String,
+
+        /// Skip the pane commands.
+        #[clap(long)]
+        skip_cmds: bool,
+
+        /// Skip attaching to session
+        #[clap(long)]
+        skip_attach: bool,
+
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
+    },
+
+    /// Show how a session differs from its config, exits with 1 if it does.
+    Diff {
+        /// Name of the session, defaults to the current session.
+        name: Option<String>,
+
+        /// Output as JSON.
+        #[clap(short, long)]
+        json: bool,
+
         /// Specify the multiplexer to use.
         #[clap(short, long)]
         muxer: Option<Muxer>,
@@ -38,39 +132,108 @@ pub struct Cli {
 }
 
 impl Cli {
-    pub fn run(&self, config_path: &str) -> Result<()> {
+    pub fn run(&self, config_path: &str, secrets: &Secrets) -> Result<()> {
         match &self.commands {
-            Commands::List { muxer, json } => {
-                let muxer =
-                    create_muxer(muxer).wrap_err("Could not create desired multiplexer.")?;
-                let session = SessionManager::new(config_path, muxer);
+            Commands::List {
+                muxer,
+                json,
+                format,
+            } => {
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let session = SessionManager::new(config_path, muxer).with_secrets(secrets.clone());
 
                 let list = session.list()?;
 
-                if *json {
+                if let Some(format) = format {
+                    print!("{}", format_sessions(&list, format)?);
+                } else if *json {
                     let json_output = serde_json::to_string_pretty(&list).into_diagnostic()?;
                     println!("{}", json_output);
                 } else {
-                    let records: Vec<_> = list
-                        .iter()
-                        .map(|item| [item.status.icon(), item.name.as_str()])
-                        .collect();
-                    let builder = Builder::from_iter(records);
-                    let mut table = builder.build();
-                    table.with(Style::rounded().remove_horizontals());
-                    println!("{}", table);
+                    println!("{}", session_table(&list));
                 }
                 Ok(())
             }
-            Commands::Yaml { muxer } => {
-                let muxer =
-                    create_muxer(muxer).wrap_err("Could not create desired multiplexer.")?;
-                let session = SessionManager::new(config_path, muxer);
+            Commands::Yaml { overlay, muxer } => {
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let session = SessionManager::new(config_path, muxer).with_secrets(secrets.clone());
 
-                let yaml = session.to_yaml()?;
+                let yaml = session.to_yaml(*overlay)?;
                 println!("{yaml}");
                 Ok(())
             }
+            Commands::Save {
+                name,
+                local,
+                force,
+                overlay,
+                muxer,
+            } => {
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let session = SessionManager::new(config_path, muxer).with_secrets(secrets.clone());
+
+                let path = session.save(name, *local, *force, *overlay)?;
+                println!("Saved session to {}", path.display());
+                Ok(())
+            }
+            Commands::Apply {
+                name,
+                prune,
+                skip_cmds,
+                muxer,
+            } => {
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let session = SessionManager::new(config_path, muxer).with_secrets(secrets.clone());
+
+                for warning in session.apply(name, *prune, *skip_cmds)? {
+                    eprintln!("Warning: {warning}");
+                }
+                Ok(())
+            }
+            Commands::Snapshot { name, muxer } => {
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let session = SessionManager::new(config_path, muxer).with_secrets(secrets.clone());
+
+                let path = session.snapshot(name)?;
+                println!("Saved snapshot to {}", path.display());
+                Ok(())
+            }
+            Commands::Restore {
+                snapshot,
+                skip_cmds,
+                skip_attach,
+                muxer,
+            } => {
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let session = SessionManager::new(config_path, muxer)
+                    .with_secrets(secrets.clone())
+                    .with_session_record();
+
+                session.restore(snapshot, *skip_attach, *skip_cmds)
+            }
+            Commands::Diff { name, json, muxer } => {
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let session = SessionManager::new(config_path, muxer).with_secrets(secrets.clone());
+
+                let diff = session.diff(name)?;
+                if *json {
+                    let json_output = serde_json::to_string_pretty(&diff).into_diagnostic()?;
+                    println!("{}", json_output);
+                } else {
+                    println!("{diff}");
+                }
+                if diff.differs {
+                    exit(1);
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -0,0 +1,60 @@
+use crate::{
+    app::SessionManager,
+    common::config::secrets::Secrets,
+    muxer::{create_muxer, Muxer},
+};
+
+use clap::{Args, Subcommand};
+use miette::{Context, Result};
+
+#[derive(Debug, Subcommand, Clone)]
+pub(crate) enum Commands {
+    /// Kill a window and recreate it from the session's config.
+    Respawn {
+        /// Name of the window.
+        window: String,
+
+        /// Only restart the pane with this name.
+        #[clap(short, long)]
+        pane: Option<String>,
+
+        /// Name of the session, defaults to the current session.
+        #[clap(short, long)]
+        session: Option<String>,
+
+        /// Skip the pane commands.
+        #[clap(long)]
+        skip_cmds: bool,
+
+        /// Specify the multiplexer to use.
+        #[clap(short, long)]
+        muxer: Option<Muxer>,
+    },
+}
+
+/// Manage Windows
+#[derive(Args, Debug)]
+pub struct Cli {
+    #[clap(subcommand)]
+    commands: Commands,
+}
+
+impl Cli {
+    pub fn run(&self, config_path: &str, secrets: &Secrets) -> Result<()> {
+        match &self.commands {
+            Commands::Respawn {
+                window,
+                pane,
+                session,
+                skip_cmds,
+                muxer,
+            } => {
+                let muxer = create_muxer(muxer, secrets)
+                    .wrap_err("Could not create desired multiplexer.")?;
+                let manager = SessionManager::new(config_path, muxer).with_secrets(secrets.clone());
+
+                manager.respawn(session, window, pane, *skip_cmds)
+            }
+        }
+    }
+}
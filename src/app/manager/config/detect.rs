@@ -0,0 +1,382 @@
+//! Project-type detection for `laio config create --detect`.
+//!
+//! Inspects a project directory for well-known files and builds a starting
+//! [`Session`] from them, e.g. a cargo watch pane for Rust projects or a compose
+//! logs window when a compose file is present.
+
+use noyalib::compat::serde_yaml::Value;
+use std::{collections::HashMap, fs, path::Path};
+use toml::Table;
+
+use crate::common::config::{Command, FlexDirection, Pane, Session, Window};
+
+/// Files checked for Docker Compose projects, in order of preference.
+const COMPOSE_FILES: [&str; 4] = [
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+/// Makefile targets worth running in a pane, in order of preference.
+const MAKE_TARGETS: [&str; 5] = ["dev", "run", "serve", "watch", "start"];
+
+/// Maximum number of workspace members given their own pane.
+const MAX_MEMBER_PANES: usize = 4;
+
+/// Builds a session for the project in `dir` from the project files it contains.
+///
+/// `path` is used as the session path. An editor window is always added; the
+/// other windows depend on what was detected.
+pub(crate) fn detect_session(name: &str, path: &str, dir: &Path) -> Session {
+    let mut windows = vec![window(
+        "code",
+        FlexDirection::Column,
+        vec![Pane {
+            focus: true,
+            ..pane("editor", vec![cmd("$EDITOR", &[])])
+        }],
+    )];
+    windows.extend(detect_cargo(dir));
+    windows.extend(detect_node(dir));
+    windows.extend(detect_python(dir));
+    windows.extend(detect_make(dir));
+
+    let mut startup = vec![];
+    let mut shutdown = vec![];
+    if let Some(compose_file) = COMPOSE_FILES.iter().find(|f| dir.join(f).is_file()) {
+        log::info!("Detected {compose_file}");
+        startup.push(cmd("docker", &["compose", "up", "-d"]));
+        shutdown.push(cmd("docker", &["compose", "down"]));
+        windows.push(window(
+            "compose",
+            FlexDirection::Row,
+            vec![pane(
+                "logs",
+                vec![cmd("docker", &["compose", "logs", "-f"])],
+            )],
+        ));
+    }
+
+    // Nix dev shells are entered in every pane, unless direnv already does it.
+    if dir.join("flake.nix").is_file() && !uses_direnv_flake(dir) {
+        log::info!("Detected flake.nix");
+        for window in &mut windows {
+            window.leaf_panes_mut().for_each(nix_develop);
+        }
+    }
+
+    Session {
+        name: name.to_string(),
+        description: None,
+        tags: vec![],
+        path: path.to_string(),
+        startup,
+        startup_script: None,
+        shutdown,
+        shutdown_script: None,
+        env: HashMap::new(),
+        shell: None,
+        pane_cmd_delay: None,
+        windows,
+    }
+}
+
+fn detect_cargo(dir: &Path) -> Vec<Window> {
+    let Some(manifest) = read_toml(&dir.join("Cargo.toml")) else {
+        return vec![];
+    };
+    log::info!("Detected Cargo.toml");
+
+    let members = workspace_members(dir, &manifest);
+    let watch = if members.is_empty() {
+        cmd("cargo", &["watch", "-x", "check", "-x", "test"])
+    } else {
+        cmd(
+            "cargo",
+            &["watch", "-x", "check --workspace", "-x", "test --workspace"],
+        )
+    };
+
+    let mut windows = vec![window(
+        "cargo",
+        FlexDirection::Row,
+        vec![pane("watch", vec![watch]), pane("shell", vec![])],
+    )];
+
+    // A shell per workspace member, opened in the member's directory
+    if !members.is_empty() {
+        let panes = members
+            .iter()
+            .take(MAX_MEMBER_PANES)
+            .map(|member| Pane {
+                path: member.clone(),
+                ..pane(member.rsplit('/').next().unwrap_or(member), vec![])
+            })
+            .collect();
+        windows.push(window("crates", FlexDirection::Row, panes));
+    }
+
+    windows
+}
+
+/// Workspace members listed in a `[workspace]` table, with `dir/*` globs expanded
+/// to the subdirectories containing a `Cargo.toml`.
+fn workspace_members(dir: &Path, manifest: &Table) -> Vec<String> {
+    let members = toml_strings(manifest, &["workspace", "members"]);
+
+    let mut expanded = vec![];
+    for member in members {
+        match member.strip_suffix("/*") {
+            Some(parent) => {
+                let mut found: Vec<String> = fs::read_dir(dir.join(parent))
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.join("Cargo.toml").is_file())
+                    .filter_map(|path| {
+                        path.file_name()
+                            .map(|n| format!("{parent}/{}", n.to_string_lossy()))
+                    })
+                    .collect();
+                found.sort();
+                expanded.extend(found);
+            }
+            None => expanded.push(member.to_string()),
+        }
+    }
+    expanded
+}
+
+fn detect_node(dir: &Path) -> Option<Window> {
+    let manifest = fs::read_to_string(dir.join("package.json")).ok()?;
+    log::info!("Detected package.json");
+
+    let package: serde_json::Value = serde_json::from_str(&manifest).ok()?;
+    let scripts = package.get("scripts").and_then(|s| s.as_object());
+    let has_script = |name: &str| scripts.is_some_and(|s| s.contains_key(name));
+
+    let manager = node_package_manager(dir);
+    let mut panes = vec![];
+    if let Some(script) = ["dev", "start", "serve"]
+        .into_iter()
+        .find(|s| has_script(s))
+    {
+        panes.push(pane(script, vec![cmd(manager, &["run", script])]));
+    }
+    if has_script("test") {
+        panes.push(pane("test", vec![cmd(manager, &["test"])]));
+    }
+    panes.push(pane("shell", vec![]));
+
+    Some(window("node", FlexDirection::Row, panes))
+}
+
+fn node_package_manager(dir: &Path) -> &'static str {
+    [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+    ]
+    .into_iter()
+    .find(|(lockfile, _)| dir.join(lockfile).is_file())
+    .map(|(_, manager)| manager)
+    .unwrap_or("npm")
+}
+
+fn detect_python(dir: &Path) -> Option<Window> {
+    let manifest = read_toml(&dir.join("pyproject.toml"))?;
+    log::info!("Detected pyproject.toml");
+
+    let run = |program: &str| {
+        if dir.join("uv.lock").is_file() {
+            cmd("uv", &["run", program])
+        } else if toml_get(&manifest, &["tool", "poetry"]).is_some() {
+            cmd("poetry", &["run", program])
+        } else {
+            cmd(program, &[])
+        }
+    };
+
+    let mut panes = vec![pane("repl", vec![run("python")])];
+    if uses_pytest(&manifest) {
+        panes.push(pane("test", vec![run("pytest")]));
+    }
+    panes.push(pane("shell", vec![]));
+
+    Some(window("python", FlexDirection::Row, panes))
+}
+
+fn detect_make(dir: &Path) -> Option<Window> {
+    let makefile = ["Makefile", "makefile", "GNUmakefile"]
+        .into_iter()
+        .find_map(|f| fs::read_to_string(dir.join(f)).ok())?;
+    log::info!("Detected Makefile");
+
+    let targets = make_targets(&makefile);
+    let target = MAKE_TARGETS
+        .into_iter()
+        .find(|t| targets.iter().any(|x| x == t))?;
+
+    Some(window(
+        "make",
+        FlexDirection::Row,
+        vec![pane(target, vec![cmd("make", &[target])])],
+    ))
+}
+
+/// Rule names defined in a Makefile, excluding special and pattern targets.
+fn make_targets(makefile: &str) -> Vec<String> {
+    makefile
+        .lines()
+        .filter(|line| !line.starts_with(['\t', ' ', '#', '.']))
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(_, rest)| !rest.starts_with('='))
+        .flat_map(|(targets, _)| targets.split_whitespace())
+        .filter(|target| !target.contains(['%', '$', '=']))
+        .map(str::to_string)
+        .collect()
+}
+
+fn uses_direnv_flake(dir: &Path) -> bool {
+    fs::read_to_string(dir.join(".envrc")).is_ok_and(|envrc| envrc.contains("use flake"))
+}
+
+/// Runs a pane's commands inside `nix develop`, or opens a dev shell if it has none.
+fn nix_develop(pane: &mut Pane) {
+    pane.commands = match pane.commands.as_slice() {
+        [] => vec![cmd("nix", &["develop"])],
+        commands => commands
+            .iter()
+            .map(|command| {
+                let mut args = vec![
+                    Value::String("develop".to_string()),
+                    Value::String("--command".to_string()),
+                    Value::String(command.command.clone()),
+                ];
+                args.extend(command.args.iter().cloned());
+                Command {
+                    command: "nix".to_string(),
+                    args,
+                }
+            })
+            .collect(),
+    };
+}
+
+fn cmd(command: &str, args: &[&str]) -> Command {
+    Command {
+        command: command.to_string(),
+        args: args.iter().map(|a| Value::String(a.to_string())).collect(),
+    }
+}
+
+fn window(name: &str, flex_direction: FlexDirection, panes: Vec<Pane>) -> Window {
+    Window {
+        name: name.to_string(),
+        path: None,
+        flex_direction,
+        panes,
+        focus: false,
+    }
+}
+
+fn pane(name: &str, commands: Vec<Command>) -> Pane {
+    Pane {
+        flex_direction: FlexDirection::default(),
+        flex: 1,
+        name: Some(name.to_string()),
+        path: ".".to_string(),
+        style: None,
+        commands,
+        script: None,
+        panes: vec![],
+        zoom: false,
+        focus: false,
+    }
+}
+
+/// A TOML file parsed into a table, `None` if it's missing. A file that doesn't
+/// parse is still detected, only its contents are ignored.
+fn read_toml(path: &Path) -> Option<Table> {
+    let content = fs::read_to_string(path).ok()?;
+    Some(content.parse().unwrap_or_else(|e| {
+        log::warn!("Ignoring the contents of {}: {e}", path.display());
+        Table::new()
+    }))
+}
+
+/// The value at `keys`, following nested tables.
+fn toml_get<'a>(table: &'a Table, keys: &[&str]) -> Option<&'a toml::Value> {
+    let (last, tables) = keys.split_last()?;
+    tables
+        .iter()
+        .try_fold(table, |table, key| table.get(*key)?.as_table())?
+        .get(*last)
+}
+
+/// Strings of the array at `keys`, empty if there's none.
+fn toml_strings<'a>(table: &'a Table, keys: &[&str]) -> Vec<&'a str> {
+    toml_get(table, keys)
+        .and_then(toml::Value::as_array)
+        .map(|array| array.iter().filter_map(toml::Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Name of a PEP 508 requirement, such as `pytest` in `pytest[toml]>=8; python_version>'3'`.
+fn requirement_name(requirement: &str) -> &str {
+    requirement
+        .split(['<', '>', '=', '!', '~', ';', '[', '(', '@', ' '])
+        .next()
+        .unwrap_or_default()
+}
+
+/// Whether pytest is configured in `[tool.pytest]` or is a dependency, be it of the
+/// project, of a dependency group or of Poetry or uv.
+fn uses_pytest(manifest: &Table) -> bool {
+    if toml_get(manifest, &["tool", "pytest"]).is_some() {
+        return true;
+    }
+
+    let mut requirements = toml_strings(manifest, &["project", "dependencies"]);
+    requirements.extend(toml_strings(manifest, &["tool", "uv", "dev-dependencies"]));
+    let groups = [
+        toml_get(manifest, &["dependency-groups"]),
+        toml_get(manifest, &["project", "optional-dependencies"]),
+    ];
+    requirements.extend(
+        groups
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_table)
+            .flat_map(Table::values)
+            .filter_map(toml::Value::as_array)
+            .flatten()
+            .filter_map(toml::Value::as_str),
+    );
+    let is_pytest = |name: &str| name.trim().eq_ignore_ascii_case("pytest");
+    if requirements
+        .iter()
+        .any(|requirement| is_pytest(requirement_name(requirement)))
+    {
+        return true;
+    }
+
+    // Poetry lists dependencies as the keys of its tables
+    let mut poetry_tables = vec![
+        toml_get(manifest, &["tool", "poetry", "dependencies"]),
+        toml_get(manifest, &["tool", "poetry", "dev-dependencies"]),
+    ];
+    if let Some(groups) =
+        toml_get(manifest, &["tool", "poetry", "group"]).and_then(toml::Value::as_table)
+    {
+        poetry_tables.extend(groups.values().map(|group| group.get("dependencies")));
+    }
+    poetry_tables
+        .into_iter()
+        .flatten()
+        .filter_map(toml::Value::as_table)
+        .any(|dependencies| dependencies.keys().any(|name| is_pytest(name)))
+}
@@ -0,0 +1,84 @@
+//! Conversion of laio sessions into configs the muxers, or their session managers,
+//! read without laio.
+//!
+//! Settings the target format has no counterpart for are reported as warnings.
+
+use clap::ValueEnum;
+use miette::Result;
+
+use crate::{
+    common::config::{Pane, Session},
+    muxer::{tmux, zellij},
+};
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum ExportFormat {
+    ZellijKdl,
+    TmuxScript,
+    Tmuxinator,
+}
+
+/// An exported session, along with what could not be exported.
+#[derive(Debug)]
+pub(crate) struct Export {
+    pub(crate) content: String,
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Converts `session` into `format`.
+pub(crate) fn export(format: &ExportFormat, session: &Session) -> Result<Export> {
+    let mut warnings = vec![];
+    let content = match format {
+        ExportFormat::ZellijKdl => {
+            zellij_warnings(session, &mut warnings);
+            zellij::session_layout(session)?
+        }
+        ExportFormat::TmuxScript => tmux::export::script(session, &mut warnings)?,
+        ExportFormat::Tmuxinator => tmux::export::tmuxinator(session, &mut warnings)?,
+    };
+    warnings.dedup();
+    Ok(Export { content, warnings })
+}
+
+/// A Zellij layout only holds the tabs and panes, everything `start` does around
+/// them is lost.
+fn zellij_warnings(session: &Session, warnings: &mut Vec<String>) {
+    if !session.startup.is_empty() || session.startup_script.is_some() {
+        warnings.push("Startup commands are not exported".to_string());
+    }
+    if !session.shutdown.is_empty() || session.shutdown_script.is_some() {
+        warnings.push("Shutdown commands are not exported".to_string());
+    }
+    if !session.env.is_empty() {
+        warnings.push("Environment variables are not exported".to_string());
+    }
+    if let Some(shell) = &session.shell {
+        warnings.push(format!("Shell '{shell}' is not exported"));
+    }
+    for window in &session.windows {
+        pane_warnings(&window.panes, &window.name, warnings);
+    }
+}
+
+fn pane_warnings(panes: &[Pane], window: &str, warnings: &mut Vec<String>) {
+    for pane in panes {
+        if pane.zoom || pane.style.is_some() {
+            warnings.push(format!(
+                "Pane zoom and style in '{window}' are not exported"
+            ));
+        }
+        if pane.script.is_some() {
+            warnings.push(format!("Pane script in '{window}' is not exported"));
+        }
+        if !pane.panes.is_empty() && !pane.commands.is_empty() {
+            warnings.push(format!(
+                "Commands of split panes in '{window}' are not exported"
+            ));
+        }
+        // A Zellij pane runs a single command, the last one it's given
+        if pane.commands.len() > 1 {
+            warnings.push(format!("Panes in '{window}' only keep their last command"));
+        }
+        pane_warnings(&pane.panes, window, warnings);
+    }
+}
@@ -0,0 +1,125 @@
+//! tmux window layouts as laio flex trees.
+
+use std::collections::HashMap;
+
+use crate::{
+    common::config::{leaf_panes, leaf_panes_mut, FlexDirection, Pane, Session},
+    muxer::tmux::{client::WindowInfo, parser::parse},
+};
+
+use super::pane;
+
+/// Arranges `panes` in the named tmux `layout`, or in a custom layout string as
+/// printed by `list-windows -F '#{window_layout}'`.
+///
+/// Returns the direction of the window and its panes. Layouts that can't be
+/// converted fall back to stacking the panes, as tmux does for new splits.
+pub(super) fn arrange(
+    layout: Option<&str>,
+    panes: Vec<Pane>,
+    warnings: &mut Vec<String>,
+) -> (FlexDirection, Vec<Pane>) {
+    if panes.len() < 2 {
+        return (FlexDirection::Column, panes);
+    }
+
+    match layout.map(str::trim) {
+        None => (FlexDirection::Column, panes),
+        Some("even-horizontal") => (FlexDirection::Row, panes),
+        Some("even-vertical") => (FlexDirection::Column, panes),
+        Some("main-horizontal") => main(FlexDirection::Column, panes, false),
+        Some("main-horizontal-mirrored") => main(FlexDirection::Column, panes, true),
+        Some("main-vertical") => main(FlexDirection::Row, panes, false),
+        Some("main-vertical-mirrored") => main(FlexDirection::Row, panes, true),
+        Some("tiled") => tiled(panes),
+        Some(layout) => custom(layout, panes).unwrap_or_else(|panes| {
+            warnings.push(format!(
+                "Layout '{layout}' is not supported, panes are stacked instead"
+            ));
+            (FlexDirection::Column, panes)
+        }),
+    }
+}
+
+/// One main pane, with the others split the other way next to it.
+fn main(
+    direction: FlexDirection,
+    mut panes: Vec<Pane>,
+    mirrored: bool,
+) -> (FlexDirection, Vec<Pane>) {
+    let main_pane = panes.remove(0);
+    let others = match panes.len() {
+        1 => panes.remove(0),
+        _ => split(opposite(&direction), panes),
+    };
+
+    let panes = if mirrored {
+        vec![others, main_pane]
+    } else {
+        vec![main_pane, others]
+    };
+    (direction, panes)
+}
+
+/// Rows of equal panes, with as many columns as rows or one more.
+fn tiled(panes: Vec<Pane>) -> (FlexDirection, Vec<Pane>) {
+    let columns = (panes.len() as f64).sqrt().ceil() as usize;
+
+    let mut panes = panes.into_iter().peekable();
+    let mut rows = vec![];
+    while panes.peek().is_some() {
+        let mut row: Vec<Pane> = panes.by_ref().take(columns).collect();
+        rows.push(match row.len() {
+            1 => row.remove(0),
+            _ => split(FlexDirection::Row, row),
+        });
+    }
+    (FlexDirection::Column, rows)
+}
+
+/// Fills the leaves of a tmux layout string with `panes`, in order. Gives the panes
+/// back if the layout can't be parsed or has a different number of panes.
+fn custom(layout: &str, panes: Vec<Pane>) -> Result<(FlexDirection, Vec<Pane>), Vec<Pane>> {
+    let window = WindowInfo {
+        name: String::new(),
+        layout: layout.to_string(),
+        active: false,
+    };
+    let tokens = parse(
+        &[window],
+        &HashMap::new(),
+        "",
+        &HashMap::new(),
+        &HashMap::new(),
+    );
+    let Some(window) = Session::from_tokens("", "", &tokens).windows.pop() else {
+        return Err(panes);
+    };
+
+    let mut tree = window.panes;
+    if leaf_panes(&tree).count() != panes.len() {
+        return Err(panes);
+    }
+    for (node, pane) in leaf_panes_mut(&mut tree).zip(panes) {
+        *node = Pane {
+            flex: node.flex,
+            ..pane
+        };
+    }
+    Ok((window.flex_direction, tree))
+}
+
+fn split(flex_direction: FlexDirection, panes: Vec<Pane>) -> Pane {
+    Pane {
+        flex_direction,
+        panes,
+        ..pane(vec![])
+    }
+}
+
+fn opposite(direction: &FlexDirection) -> FlexDirection {
+    match direction {
+        FlexDirection::Row => FlexDirection::Column,
+        FlexDirection::Column => FlexDirection::Row,
+    }
+}
@@ -0,0 +1,125 @@
+//! Conversion of other session managers' configs into laio sessions.
+//!
+//! Settings without a laio counterpart are reported as warnings instead of being
+//! dropped silently.
+
+mod layout;
+mod tmuxinator;
+mod tmuxp;
+mod zellij;
+
+use clap::ValueEnum;
+use miette::Result;
+use noyalib::compat::serde_yaml::Value;
+
+use crate::common::config::{Command, FlexDirection, Pane, Session};
+
+#[derive(Debug, Clone, ValueEnum)]
+pub(crate) enum ImportFormat {
+    Tmuxinator,
+    Tmuxp,
+    Zellij,
+}
+
+/// A converted session, along with what could not be converted.
+#[derive(Debug)]
+pub(crate) struct Import {
+    pub(crate) session: Session,
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Converts the config in `content` from `format` into a laio session. `name` is
+/// the session name for formats that have none, such as Zellij layouts.
+pub(crate) fn import(format: &ImportFormat, content: &str, name: &str) -> Result<Import> {
+    match format {
+        ImportFormat::Tmuxinator => tmuxinator::import(content),
+        ImportFormat::Tmuxp => tmuxp::import(content),
+        ImportFormat::Zellij => zellij::import(content, name),
+    }
+}
+
+/// Splits a shell command line into a command and its arguments, honouring quotes.
+///
+/// Lines using shell syntax such as pipes or redirects are kept whole, so they are
+/// typed into the pane as written.
+fn command(line: &str) -> Command {
+    let line = line.trim();
+    let has_shell_syntax = line.contains(['|', '&', ';', '<', '>', '$', '`', '(', '*']);
+
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in line.chars() {
+        match (quote, c) {
+            _ if escaped => {
+                word.get_or_insert_default().push(c);
+                escaped = false;
+            }
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('"'), '\\') | (None, '\\') => escaped = true,
+            (Some(_), c) => word.get_or_insert_default().push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_default();
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+
+    if has_shell_syntax || quote.is_some() || words.is_empty() {
+        return Command {
+            command: line.to_string(),
+            args: vec![],
+        };
+    }
+
+    let mut words = words.into_iter();
+    Command {
+        command: words.next().unwrap_or_default(),
+        args: words.map(Value::String).collect(),
+    }
+}
+
+/// Strings, numbers and booleans as a string, e.g. a port given as a pane command.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Commands of a value that is either a single command or a list of them.
+fn commands(value: Option<&Value>) -> Vec<Command> {
+    match value {
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(scalar)
+            .map(|line| command(&line))
+            .collect(),
+        Some(value) => scalar(value)
+            .map(|line| command(&line))
+            .into_iter()
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn pane(commands: Vec<Command>) -> Pane {
+    Pane {
+        flex_direction: FlexDirection::default(),
+        flex: 1,
+        name: None,
+        path: ".".to_string(),
+        style: None,
+        commands,
+        script: None,
+        panes: vec![],
+        zoom: false,
+        focus: false,
+    }
+}
@@ -0,0 +1,204 @@
+//! Import of [tmuxinator](https://github.com/tmuxinator/tmuxinator) projects.
+
+use std::collections::HashMap;
+
+use miette::{bail, IntoDiagnostic, Result};
+use noyalib::compat::serde_yaml::{Mapping, Value};
+
+use crate::common::config::{Command, Pane, Session, Window};
+
+use super::{command, commands, layout, pane, scalar, Import};
+
+/// Project settings that are converted.
+const KNOWN: [&str; 13] = [
+    "name",
+    "project_name",
+    "root",
+    "project_root",
+    "pre",
+    "on_project_start",
+    "on_project_first_start",
+    "on_project_stop",
+    "pre_window",
+    "startup_window",
+    "startup_pane",
+    "windows",
+    "tabs",
+];
+
+pub(super) fn import(content: &str) -> Result<Import> {
+    let mut warnings = vec![];
+    if content.contains("<%") {
+        warnings.push(
+            "ERB tags are not evaluated, consider replacing them with template variables"
+                .to_string(),
+        );
+    }
+
+    let config: Value = noyalib::compat::serde_yaml::from_str(content).into_diagnostic()?;
+    let Some(config) = config.as_mapping() else {
+        bail!("Expected a tmuxinator project, got: {content}");
+    };
+
+    let Some(name) = ["name", "project_name"]
+        .iter()
+        .find_map(|key| config.get(key).and_then(scalar))
+    else {
+        bail!("tmuxinator project has no name");
+    };
+
+    for key in config.keys().filter(|key| !KNOWN.contains(&key.as_str())) {
+        warnings.push(format!("Project setting '{key}' is not supported"));
+    }
+
+    let pre_window = commands(config.get("pre_window"));
+    let mut windows = match config.get("windows").or_else(|| config.get("tabs")) {
+        Some(Value::Sequence(windows)) => windows
+            .iter()
+            .enumerate()
+            .filter_map(|(index, window)| convert_window(index, window, &pre_window, &mut warnings))
+            .collect(),
+        _ => vec![],
+    };
+
+    // startup_window may be a name or an index, startup_pane is an index
+    let startup_window = config.get("startup_window").and_then(scalar);
+    let focused = startup_window.as_deref().and_then(|startup| {
+        windows
+            .iter()
+            .position(|w: &Window| w.name == startup)
+            .or_else(|| startup.parse().ok().filter(|&i| i < windows.len()))
+    });
+    if let Some(index) = focused {
+        windows[index].focus = true;
+    }
+    if let Some(pane_index) = config.get("startup_pane").and_then(Value::as_u64)
+        && let Some(window) = windows.get_mut(focused.unwrap_or(0))
+        && !(window.panes.is_empty() && pane_index == 0)
+    {
+        match window.leaf_panes_mut().nth(pane_index as usize) {
+            Some(pane) => pane.focus = true,
+            None => warnings.push(format!("Startup pane {pane_index} does not exist")),
+        }
+    }
+
+    let mut startup: Vec<Command> = ["on_project_first_start", "pre", "on_project_start"]
+        .iter()
+        .flat_map(|key| commands(config.get(key)))
+        .collect();
+    startup.dedup();
+
+    let session = Session {
+        name,
+        description: None,
+        tags: vec![],
+        path: ["root", "project_root"]
+            .iter()
+            .find_map(|key| config.get(key).and_then(scalar))
+            .unwrap_or(".".to_string()),
+        startup,
+        startup_script: None,
+        shutdown: commands(config.get("on_project_stop")),
+        shutdown_script: None,
+        env: HashMap::new(),
+        shell: None,
+        pane_cmd_delay: None,
+        windows,
+    };
+
+    Ok(Import { session, warnings })
+}
+
+/// Windows are single-key mappings of the name to a command, a list of commands
+/// or the window settings.
+fn convert_window(
+    index: usize,
+    window: &Value,
+    pre_window: &[Command],
+    warnings: &mut Vec<String>,
+) -> Option<Window> {
+    let Some((name, value)) = window.as_mapping().and_then(|w| w.iter().next()) else {
+        warnings.push(format!("Window {index} is not a mapping and is skipped"));
+        return None;
+    };
+
+    let (path, layout, panes) = match value {
+        Value::Mapping(settings) => {
+            for key in settings.keys() {
+                if !["root", "layout", "panes", "pre"].contains(&key.as_str()) {
+                    warnings.push(format!(
+                        "Window setting '{key}' of '{name}' is not supported"
+                    ));
+                }
+            }
+            let pre: Vec<Command> = pre_window
+                .iter()
+                .cloned()
+                .chain(commands(settings.get("pre")))
+                .collect();
+            let panes = match settings.get("panes") {
+                Some(Value::Sequence(panes)) => {
+                    panes.iter().map(|p| convert_pane(p, &pre)).collect()
+                }
+                _ => vec![pane(pre)],
+            };
+            (
+                settings.get("root").and_then(scalar),
+                settings.get("layout").and_then(scalar),
+                panes,
+            )
+        }
+        value => {
+            let mut window_commands = pre_window.to_vec();
+            window_commands.extend(commands(Some(value)));
+            (None, None, vec![pane(window_commands)])
+        }
+    };
+
+    let mut layout_warnings = vec![];
+    let (flex_direction, panes) = layout::arrange(layout.as_deref(), panes, &mut layout_warnings);
+    warnings.extend(
+        layout_warnings
+            .into_iter()
+            .map(|w| format!("{w} in '{name}'")),
+    );
+
+    Some(Window {
+        name: name.clone(),
+        path,
+        flex_direction,
+        // A single plain pane is what laio opens for a window without panes
+        panes: match panes.as_slice() {
+            [pane] if pane.commands.is_empty() && pane.name.is_none() => vec![],
+            _ => panes,
+        },
+        focus: false,
+    })
+}
+
+/// Panes are a command, a list of commands or a mapping of a title to those.
+fn convert_pane(value: &Value, pre: &[Command]) -> Pane {
+    let (name, value) = match value.as_mapping().and_then(single_entry) {
+        Some((name, value)) => (Some(name.clone()), value),
+        None => (None, value),
+    };
+
+    let mut pane_commands = pre.to_vec();
+    match value {
+        Value::Null => {}
+        Value::Sequence(_) => pane_commands.extend(commands(Some(value))),
+        value => pane_commands.extend(scalar(value).map(|line| command(&line))),
+    }
+
+    Pane {
+        name,
+        ..pane(pane_commands)
+    }
+}
+
+fn single_entry(mapping: &Mapping) -> Option<(&String, &Value)> {
+    match mapping.len() {
+        1 => mapping.iter().next(),
+        _ => None,
+    }
+}
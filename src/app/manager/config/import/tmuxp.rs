@@ -0,0 +1,227 @@
+//! Import of [tmuxp](https://github.com/tmux-python/tmuxp) workspaces.
+
+use std::collections::HashMap;
+
+use miette::{bail, IntoDiagnostic, Result};
+use noyalib::compat::serde_yaml::{Mapping, Value};
+
+use crate::common::config::{Command, Pane, Session, Window};
+
+use super::{command, layout, pane, scalar, Import};
+
+/// Workspace settings that are converted.
+const KNOWN: [&str; 6] = [
+    "session_name",
+    "start_directory",
+    "before_script",
+    "shell_command_before",
+    "environment",
+    "windows",
+];
+
+/// Window settings that are converted.
+const KNOWN_WINDOW: [&str; 7] = [
+    "window_name",
+    "start_directory",
+    "layout",
+    "focus",
+    "panes",
+    "shell_command_before",
+    "environment",
+];
+
+/// Pane settings that are converted.
+const KNOWN_PANE: [&str; 4] = ["shell_command", "cmd", "start_directory", "focus"];
+
+pub(super) fn import(content: &str) -> Result<Import> {
+    let config: Value = noyalib::compat::serde_yaml::from_str(content).into_diagnostic()?;
+    let Some(config) = config.as_mapping() else {
+        bail!("Expected a tmuxp workspace, got: {content}");
+    };
+    let Some(name) = config.get("session_name").and_then(scalar) else {
+        bail!("tmuxp workspace has no session_name");
+    };
+
+    let mut warnings = vec![];
+    for key in config.keys().filter(|key| !KNOWN.contains(&key.as_str())) {
+        warnings.push(format!("Workspace setting '{key}' is not supported"));
+    }
+
+    let before = shell_commands(config.get("shell_command_before"));
+    let mut env = environment(config.get("environment"));
+    let windows = match config.get("windows") {
+        Some(Value::Sequence(windows)) => windows
+            .iter()
+            .enumerate()
+            .filter_map(|(index, window)| {
+                convert_window(index, window, &before, &mut env, &mut warnings)
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    let session = Session {
+        name,
+        description: None,
+        tags: vec![],
+        path: config
+            .get("start_directory")
+            .and_then(scalar)
+            .unwrap_or(".".to_string()),
+        startup: config
+            .get("before_script")
+            .and_then(scalar)
+            .map(|script| command(&script))
+            .into_iter()
+            .collect(),
+        startup_script: None,
+        shutdown: vec![],
+        shutdown_script: None,
+        env,
+        shell: None,
+        pane_cmd_delay: None,
+        windows,
+    };
+
+    Ok(Import { session, warnings })
+}
+
+fn convert_window(
+    index: usize,
+    window: &Value,
+    before: &[Command],
+    env: &mut HashMap<String, String>,
+    warnings: &mut Vec<String>,
+) -> Option<Window> {
+    let Some(settings) = window.as_mapping() else {
+        warnings.push(format!("Window {index} is not a mapping and is skipped"));
+        return None;
+    };
+    let name = settings
+        .get("window_name")
+        .and_then(scalar)
+        .unwrap_or_else(|| format!("window-{}", index + 1));
+
+    for key in settings
+        .keys()
+        .filter(|k| !KNOWN_WINDOW.contains(&k.as_str()))
+    {
+        warnings.push(format!(
+            "Window setting '{key}' of '{name}' is not supported"
+        ));
+    }
+
+    // laio sets the environment for the whole session
+    for (key, value) in environment(settings.get("environment")) {
+        if env.get(&key).is_some_and(|v| *v != value) {
+            warnings.push(format!(
+                "Variable '{key}' of window '{name}' differs from the session and is skipped"
+            ));
+        } else {
+            env.insert(key, value);
+        }
+    }
+
+    let mut before = before.to_vec();
+    before.extend(shell_commands(settings.get("shell_command_before")));
+
+    let panes = match settings.get("panes") {
+        Some(Value::Sequence(panes)) => panes
+            .iter()
+            .map(|p| convert_pane(p, &before, &name, warnings))
+            .collect(),
+        _ => vec![pane(before)],
+    };
+
+    let mut layout_warnings = vec![];
+    let layout = settings.get("layout").and_then(scalar);
+    let (flex_direction, panes) = layout::arrange(layout.as_deref(), panes, &mut layout_warnings);
+    warnings.extend(
+        layout_warnings
+            .into_iter()
+            .map(|w| format!("{w} in '{name}'")),
+    );
+
+    Some(Window {
+        name,
+        path: settings.get("start_directory").and_then(scalar),
+        flex_direction,
+        // A single plain pane is what laio opens for a window without panes
+        panes: match panes.as_slice() {
+            [pane] if pane.commands.is_empty() && pane.path == "." && !pane.focus => vec![],
+            _ => panes,
+        },
+        focus: settings.get("focus").and_then(truthy).unwrap_or(false),
+    })
+}
+
+/// Panes are a command, a list of commands, or the pane settings.
+fn convert_pane(
+    value: &Value,
+    before: &[Command],
+    window: &str,
+    warnings: &mut Vec<String>,
+) -> Pane {
+    let mut commands = before.to_vec();
+    let Some(settings) = value.as_mapping() else {
+        commands.extend(shell_commands(Some(value)));
+        return pane(commands);
+    };
+
+    for key in settings
+        .keys()
+        .filter(|k| !KNOWN_PANE.contains(&k.as_str()))
+    {
+        warnings.push(format!(
+            "Pane setting '{key}' in '{window}' is not supported"
+        ));
+    }
+
+    commands.extend(shell_commands(
+        settings
+            .get("shell_command")
+            .or_else(|| settings.get("cmd")),
+    ));
+    Pane {
+        path: settings
+            .get("start_directory")
+            .and_then(scalar)
+            .unwrap_or(".".to_string()),
+        focus: settings.get("focus").and_then(truthy).unwrap_or(false),
+        ..pane(commands)
+    }
+}
+
+/// A command, or a list of commands given as strings or `cmd` mappings.
+fn shell_commands(value: Option<&Value>) -> Vec<Command> {
+    let line = |value: &Value| match value {
+        Value::Mapping(m) => m.get("cmd").and_then(scalar),
+        value => scalar(value),
+    };
+    match value {
+        Some(Value::Sequence(items)) => {
+            items.iter().filter_map(line).map(|l| command(&l)).collect()
+        }
+        Some(value) => line(value).map(|l| command(&l)).into_iter().collect(),
+        None => vec![],
+    }
+}
+
+fn environment(value: Option<&Value>) -> HashMap<String, String> {
+    value
+        .and_then(Value::as_mapping)
+        .map(Mapping::iter)
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| scalar(value).map(|v| (key.clone(), v)))
+        .collect()
+}
+
+/// tmuxp accepts `true` as well as the string "true" for flags.
+fn truthy(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::String(s) => Some(s.eq_ignore_ascii_case("true")),
+        _ => None,
+    }
+}
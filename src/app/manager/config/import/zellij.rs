@@ -0,0 +1,356 @@
+//! Import of native [Zellij](https://zellij.dev/documentation/layouts) layouts.
+//!
+//! The layout is expanded into the shape `zellij dump-layout` prints: templates are
+//! applied, `cwd`s are resolved against their parents and sizes in cells become
+//! percentages. The result is then read like a live session.
+
+use std::collections::HashMap;
+
+use kdl::{KdlDocument, KdlNode, KdlValue};
+use miette::{bail, Result};
+
+use crate::{
+    common::{config::Session, path::sanitize_path},
+    muxer::zellij::model::{extract_child_nodes, find_entry_value},
+};
+
+use super::Import;
+
+/// Templates may use other templates, but not themselves.
+const MAX_TEMPLATE_DEPTH: usize = 16;
+
+/// Plugins of the default layouts, which laio has no use for.
+const UI_PLUGINS: [&str; 3] = ["tab-bar", "status-bar", "compact-bar"];
+
+pub(super) fn import(content: &str, name: &str) -> Result<Import> {
+    let document = KdlDocument::parse_v1(content)?;
+    let Some(layout) = document.get("layout") else {
+        bail!("Expected a Zellij layout, but there is no layout node");
+    };
+
+    let mut converter = Converter::new(layout);
+    let layout = converter.layout(layout);
+    if extract_child_nodes(&layout, "tab").is_empty() {
+        bail!("Zellij layout has no tabs or panes");
+    }
+
+    let mut session = Session::from_kdl(name, &layout);
+    for window in &mut session.windows {
+        // A single plain pane is what laio opens for a window without panes
+        if let [pane] = window.panes.as_slice()
+            && pane.panes.is_empty()
+            && pane.commands.is_empty()
+            && pane.name.is_none()
+            && pane.path == "."
+        {
+            window.panes.clear();
+        }
+    }
+
+    Ok(Import {
+        session,
+        warnings: converter.warnings,
+    })
+}
+
+struct Converter<'a> {
+    pane_templates: HashMap<&'a str, &'a KdlNode>,
+    tab_templates: HashMap<&'a str, &'a KdlNode>,
+    default_tab_template: Option<&'a KdlNode>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(layout: &'a KdlNode) -> Self {
+        let templates = |kind: &str| -> HashMap<&'a str, &'a KdlNode> {
+            extract_child_nodes(layout, kind)
+                .into_iter()
+                .filter_map(|node| find_entry_value(node, "name").map(|name| (name, node)))
+                .collect()
+        };
+
+        Self {
+            pane_templates: templates("pane_template"),
+            tab_templates: templates("tab_template"),
+            default_tab_template: extract_child_nodes(layout, "default_tab_template")
+                .into_iter()
+                .next(),
+            warnings: vec![],
+        }
+    }
+
+    fn layout(&mut self, layout: &'a KdlNode) -> KdlNode {
+        // The cwd is a property or, as in `dump-layout`, a child node
+        let cwd = find_entry_value(layout, "cwd").or_else(|| {
+            layout
+                .children()
+                .and_then(|children| children.get_arg("cwd"))
+                .and_then(KdlValue::as_string)
+        });
+        let base = cwd.unwrap_or(".").to_string();
+
+        let mut tabs = vec![];
+        let mut panes = vec![];
+        for node in layout.iter_children() {
+            match node.name().value() {
+                "cwd" | "pane_template" | "tab_template" | "default_tab_template" => {}
+                "new_tab_template" => {}
+                "tab" => tabs.push((node, self.default_tab_template)),
+                "swap_tiled_layout" | "swap_floating_layout" => self
+                    .warnings
+                    .push("Swap layouts are not supported".to_string()),
+                name => match self.tab_templates.get(name) {
+                    Some(&template) => tabs.push((node, Some(template))),
+                    None => panes.push(node),
+                },
+            }
+        }
+
+        let mut layout_node = KdlNode::new("layout");
+        if let Some(cwd) = cwd {
+            layout_node.insert("cwd", cwd);
+        }
+        let mut tab_nodes: Vec<KdlNode> = tabs
+            .into_iter()
+            .enumerate()
+            .map(|(index, (node, template))| self.tab(index, node, template, &base))
+            .collect();
+
+        // Panes outside of tabs make up the only tab
+        if !panes.is_empty() {
+            if tab_nodes.is_empty() {
+                let mut tab = KdlNode::new("tab");
+                tab.set_children(document(panes.into_iter().cloned().collect()));
+                tab_nodes.push(self.tab(0, &tab, None, &base));
+            } else {
+                self.warnings
+                    .push("Panes outside of tabs are not supported".to_string());
+            }
+        }
+
+        layout_node.set_children(document(tab_nodes));
+        layout_node
+    }
+
+    fn tab(
+        &mut self,
+        index: usize,
+        node: &KdlNode,
+        template: Option<&'a KdlNode>,
+        base: &str,
+    ) -> KdlNode {
+        let name = find_entry_value(node, "name")
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("tab-{}", index + 1));
+
+        for entry in node.entries() {
+            match entry.name().map(|n| n.value()) {
+                Some("name" | "cwd" | "focus" | "split_direction" | "hide_floating_panes") => {}
+                Some(key) => self
+                    .warnings
+                    .push(format!("Tab setting '{key}' of '{name}' is not supported")),
+                None => {}
+            }
+        }
+
+        let mut tab = KdlNode::new("tab");
+        tab.insert("name", name.as_str());
+        let cwd = find_entry_value(node, "cwd")
+            .or_else(|| template.and_then(|t| find_entry_value(t, "cwd")))
+            .map(|cwd| sanitize_path(&cwd.to_string(), &base.to_string()));
+        if let Some(cwd) = &cwd {
+            tab.insert("cwd", cwd.as_str());
+        }
+        if node.get("focus").and_then(KdlValue::as_bool) == Some(true) {
+            tab.insert("focus", true);
+        }
+        let base = cwd.as_deref().unwrap_or(base);
+
+        let panes = match template {
+            None => {
+                copy_entry(node, &mut tab, "split_direction");
+                self.children(node, base, &name, &[], 0)
+            }
+            // The panes of the tab go where the template has `children`, split
+            // like the tab itself
+            Some(template) => {
+                copy_entry(template, &mut tab, "split_direction");
+                let mut panes = KdlNode::new("pane");
+                copy_entry(node, &mut panes, "split_direction");
+                let children = self.children(node, base, &name, &[], 0);
+                panes.set_children(document(children));
+                self.children(template, base, &name, &[panes], 0)
+            }
+        };
+        tab.set_children(document(panes));
+        tab
+    }
+
+    /// Converts the children of `parent`, putting `placeholder` in the place of a
+    /// `children` node.
+    fn children(
+        &mut self,
+        parent: &KdlNode,
+        base: &str,
+        tab: &str,
+        placeholder: &[KdlNode],
+        depth: usize,
+    ) -> Vec<KdlNode> {
+        let mut nodes = vec![];
+        for node in parent.iter_children() {
+            match node.name().value() {
+                "pane" => nodes.push(self.pane(node, None, base, tab, placeholder, depth)),
+                "children" => nodes.extend(placeholder.iter().cloned()),
+                "args" | "plugin" => nodes.push(node.clone()),
+                "floating_panes" => self
+                    .warnings
+                    .push(format!("Floating panes of '{tab}' are not supported")),
+                name => match self.pane_templates.get(name) {
+                    Some(&template) => {
+                        nodes.push(self.pane(node, Some(template), base, tab, &[], depth))
+                    }
+                    None => self
+                        .warnings
+                        .push(format!("Setting '{name}' in '{tab}' is not supported")),
+                },
+            }
+        }
+
+        self.cells_to_percentages(&mut nodes, tab);
+        nodes
+    }
+
+    fn pane(
+        &mut self,
+        node: &KdlNode,
+        template: Option<&KdlNode>,
+        base: &str,
+        tab: &str,
+        placeholder: &[KdlNode],
+        depth: usize,
+    ) -> KdlNode {
+        let mut pane = KdlNode::new("pane");
+        if depth > MAX_TEMPLATE_DEPTH {
+            self.warnings.push(format!(
+                "Pane template '{}' in '{tab}' is recursive",
+                node.name().value()
+            ));
+            return pane;
+        }
+
+        // Properties of the pane override those of its template, whose name is
+        // not the name of the pane
+        let template_entries = template
+            .map(|t| t.entries())
+            .unwrap_or_default()
+            .iter()
+            .filter(|entry| entry.name().map(|n| n.value()) != Some("name"));
+        for entry in template_entries.chain(node.entries()) {
+            if let Some(key) = entry.name() {
+                pane.insert(key.value(), entry.clone());
+            }
+        }
+
+        let plugin = template.is_some_and(has_ui_plugin) || has_ui_plugin(node);
+        for entry in pane.entries() {
+            match entry.name().map(|n| n.value()) {
+                Some("size" | "name" | "cwd" | "command" | "focus" | "split_direction") => {}
+                Some(key) if !plugin => self
+                    .warnings
+                    .push(format!("Pane setting '{key}' in '{tab}' is not supported")),
+                _ => {}
+            }
+        }
+        if let Some(location) = extract_child_nodes(node, "plugin")
+            .first()
+            .and_then(|plugin| find_entry_value(plugin, "location"))
+            .filter(|_| !plugin)
+        {
+            self.warnings
+                .push(format!("Plugin '{location}' in '{tab}' is not supported"));
+        }
+
+        let cwd = find_entry_value(&pane, "cwd")
+            .map(|cwd| sanitize_path(&cwd.to_string(), &base.to_string()));
+        if let Some(cwd) = &cwd {
+            pane.insert("cwd", cwd.as_str());
+        }
+        let base = cwd.as_deref().unwrap_or(base);
+
+        let children = match template {
+            Some(template) if has_children_placeholder(template) => {
+                let own = self.children(node, base, tab, &[], depth + 1);
+                self.children(template, base, tab, &own, depth + 1)
+            }
+            // The children of the pane replace those of the same kind in the template
+            Some(template) => {
+                let own = self.children(node, base, tab, &[], depth + 1);
+                let mut children: Vec<KdlNode> = self
+                    .children(template, base, tab, &[], depth + 1)
+                    .into_iter()
+                    .filter(|t| !own.iter().any(|o| o.name().value() == t.name().value()))
+                    .collect();
+                children.extend(own);
+                children
+            }
+            None => self.children(node, base, tab, placeholder, depth + 1),
+        };
+        if !children.is_empty() {
+            pane.set_children(document(children));
+        }
+        pane
+    }
+
+    /// laio has no fixed sizes. Panes that are all sized in cells keep their ratio,
+    /// mixed with percentages they share what is left.
+    fn cells_to_percentages(&mut self, nodes: &mut [KdlNode], tab: &str) {
+        let mut panes: Vec<&mut KdlNode> = nodes
+            .iter_mut()
+            .filter(|node| node.name().value() == "pane" && !has_ui_plugin(node))
+            .collect();
+        let cells: Vec<Option<i128>> = panes
+            .iter()
+            .map(|pane| pane.get("size").and_then(KdlValue::as_integer))
+            .collect();
+
+        if cells.iter().all(Option::is_some) {
+            let total: i128 = cells.iter().flatten().sum();
+            if total <= 0 {
+                return;
+            }
+            for (pane, size) in panes.iter_mut().zip(cells.iter().flatten()) {
+                let percentage = (size * 100 / total).max(1);
+                pane.insert("size", format!("{percentage}%"));
+            }
+        } else if cells.iter().any(Option::is_some) {
+            self.warnings
+                .push(format!("Pane sizes in cells in '{tab}' are approximated"));
+        }
+    }
+}
+
+fn document(nodes: Vec<KdlNode>) -> KdlDocument {
+    let mut document = KdlDocument::new();
+    document.nodes_mut().extend(nodes);
+    document
+}
+
+fn copy_entry(from: &KdlNode, to: &mut KdlNode, key: &str) {
+    if let Some(entry) = from.entry(key) {
+        to.insert(key, entry.clone());
+    }
+}
+
+fn has_children_placeholder(node: &KdlNode) -> bool {
+    node.iter_children()
+        .any(|child| child.name().value() == "children" || has_children_placeholder(child))
+}
+
+fn has_ui_plugin(node: &KdlNode) -> bool {
+    extract_child_nodes(node, "plugin").iter().any(|plugin| {
+        find_entry_value(plugin, "location").is_some_and(|location| {
+            let location = location.strip_prefix("zellij:").unwrap_or(location);
+            UI_PLUGINS.contains(&location)
+        })
+    })
+}
@@ -1,38 +1,195 @@
 use crate::common::{
     cmd::Type,
-    config::{template, variables::parse_variables, Session},
+    config::{
+        secrets::Secrets,
+        template,
+        variables::{parse_variables, session_variables, variable_name},
+        Session,
+    },
 };
-use miette::{miette, Context, Error, IntoDiagnostic, Result};
+use miette::{bail, miette, Context, Error, IntoDiagnostic, Result};
 use std::{
     env::{self, var},
     fs::{self},
     io::{stdin, Write},
-    path::PathBuf,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
 use crate::{
     cmd_forget,
-    common::{cmd::Runner, path::to_absolute_path},
+    common::{
+        cmd::Runner,
+        path::{find_in_search_path, home_dir, split_search_path, to_absolute_path},
+        session_info::{ConfigSource, SessionInfo},
+    },
+};
+
+use super::{
+    detect,
+    export::{self, Export, ExportFormat},
+    import::{self, Import, ImportFormat},
 };
 
 pub(crate) const TEMPLATE: &str = include_str!("tmpl.yaml");
 const DEFAULT_EDITOR: &str = "vim";
 
+/// Lists the configs found in `search_path`, excluding `_default.yaml`.
+///
+/// Configs in subdirectories are listed by their path, e.g. `work/api.yaml` as
+/// `work/api`, the name to start them with. Folders starting with `_` or `.` (such as
+/// `_templates`) are skipped. A config in an earlier directory shadows one with the
+/// same relative path in a later one. The source directory is recorded when more
+/// than one directory is searched.
+pub(crate) fn list_configs(search_path: &[String]) -> Result<Vec<SessionInfo>> {
+    let mut seen: Vec<PathBuf> = Vec::new();
+    let mut configs = Vec::new();
+    // Only metadata is read, secrets are never resolved
+    let secrets = Secrets::default();
+
+    for dir in search_path {
+        let root = PathBuf::from(dir);
+        if !root.is_dir() {
+            log::debug!("Skipping missing config directory '{dir}'");
+            continue;
+        }
+
+        let mut paths = Vec::new();
+        collect_configs(&root, &root, &mut paths)?;
+        paths.sort();
+
+        for relative in paths {
+            if seen.contains(&relative) {
+                log::debug!(
+                    "Config '{}' in '{}' is shadowed by an earlier directory",
+                    relative.display(),
+                    dir
+                );
+                continue;
+            }
+
+            let path = root.join(&relative);
+            let session = secrets
+                .without_resolving(|| Session::from_config(&path, None, search_path, &secrets))
+                .wrap_err(format!("Warning: Failed to parse '{}'", path.display()))?;
+            let name = match relative.parent().filter(|p| !p.as_os_str().is_empty()) {
+                Some(_) => relative.with_extension("").to_string_lossy().to_string(),
+                None => session.name,
+            };
+            seen.push(relative);
+
+            let config_dir = (search_path.len() > 1).then(|| display_dir(dir));
+            let source = if path.is_symlink() {
+                ConfigSource::Symlink
+            } else {
+                ConfigSource::Local
+            };
+            configs.push(
+                SessionInfo::inactive(name)
+                    .with_description(session.description, session.tags)
+                    .with_config_dir(config_dir)
+                    .with_config_path(path.to_string_lossy().to_string(), source),
+            );
+        }
+    }
+
+    configs.sort_by(SessionInfo::cmp_grouped);
+    Ok(configs)
+}
+
+fn collect_configs(root: &Path, dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).into_diagnostic().wrap_err(format!(
+        "Failed to list config entries in '{}'",
+        dir.display()
+    ))?;
+
+    for path in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if path.is_dir() {
+            if !file_name.starts_with('_') && !file_name.starts_with('.') {
+                collect_configs(root, &path, paths)?;
+            }
+            continue;
+        }
+
+        // Filter out _default.yaml and only include .yaml files
+        if path.extension().and_then(|ext| ext.to_str()) == Some("yaml")
+            && file_name != "_default.yaml"
+            && let Ok(relative) = path.strip_prefix(root)
+        {
+            paths.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// Creates the namespace folder of a config file if needed.
+fn ensure_parent_dir(config_file: &Path) -> Result<()> {
+    match config_file.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) => fs::create_dir_all(parent)
+            .into_diagnostic()
+            .wrap_err(format!("Could not create '{}'", parent.display())),
+        None => Ok(()),
+    }
+}
+
+/// Shortens a config directory for display by replacing the home directory with `~`.
+pub(crate) fn display_dir(dir: &str) -> String {
+    let Ok(home) = home_dir() else {
+        return dir.to_string();
+    };
+    match Path::new(dir).strip_prefix(&home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~/{}", rest.display()),
+        Err(_) => dir.to_string(),
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ConfigManager<R: Runner> {
+    /// Primary config directory, new configs are written here.
     pub config_path: String,
+    /// All config directories, in order of precedence.
+    pub search_path: Vec<String>,
+    /// Secrets of this invocation, used to render configs.
+    secrets: Secrets,
     cmd_runner: Rc<R>,
 }
 
 impl<R: Runner> ConfigManager<R> {
+    /// Creates a manager for a colon-separated list of config directories.
     pub(crate) fn new(config_path: &str, cmd_runner: Rc<R>) -> Self {
+        let search_path = split_search_path(config_path);
         Self {
-            config_path: config_path.replace('~', env::var("HOME").unwrap().as_str()),
+            config_path: search_path.first().cloned().unwrap_or_default(),
+            search_path,
+            secrets: Secrets::default(),
             cmd_runner,
         }
     }
 
+    /// Renders configs with `secrets`, shared with `cmd_runner` so they are masked
+    /// in the commands it prints.
+    pub(crate) fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Path of config `name`, from the first directory that has it, or in the
+    /// primary directory if none does.
+    fn config_file(&self, name: &str) -> PathBuf {
+        let file_name = format!("{}.yaml", name.sanitize());
+        find_in_search_path(&self.search_path, &file_name)
+            .unwrap_or_else(|| PathBuf::from(&self.config_path).join(file_name))
+    }
+
     pub(crate) fn create(
         &self,
         name: &Option<String>,
@@ -48,9 +205,10 @@ impl<R: Runner> ConfigManager<R> {
             }
             None => PathBuf::from(".laio.yaml"),
         };
+        ensure_parent_dir(&config_file)?;
 
         if let Some(copy_name) = copy {
-            let source = PathBuf::from(&self.config_path).join(format!("{copy_name}.yaml"));
+            let source = self.config_file(copy_name);
 
             fs::copy(&source, &config_file).map_err(|e| {
                 miette!(
@@ -63,7 +221,8 @@ impl<R: Runner> ConfigManager<R> {
         } else {
             // Always use _default.yaml as the template
             // Generate it if it doesn't exist
-            let default_config = PathBuf::from(&self.config_path).join("_default.yaml");
+            let default_config = find_in_search_path(&self.search_path, "_default.yaml")
+                .unwrap_or_else(|| PathBuf::from(&self.config_path).join("_default.yaml"));
             if !default_config.exists() {
                 // Create _default.yaml from built-in template
                 fs::write(&default_config, TEMPLATE).map_err(|e| {
@@ -87,7 +246,7 @@ impl<R: Runner> ConfigManager<R> {
             // Filter out session_name from user variables (always use CLI parameter)
             let user_vars: Vec<String> = variables
                 .iter()
-                .filter(|v| !v.starts_with("session_name="))
+                .filter(|v| variable_name(v) != Some("session_name"))
                 .cloned()
                 .collect();
 
@@ -98,7 +257,7 @@ impl<R: Runner> ConfigManager<R> {
             )];
 
             // Add path if not user-provided
-            if !user_vars.iter().any(|v| v.starts_with("path=")) {
+            if !user_vars.iter().any(|v| variable_name(v) == Some("path")) {
                 var_strings.push(format!("path={}", current_path.to_str().unwrap_or(".")));
             }
 
@@ -106,10 +265,16 @@ impl<R: Runner> ConfigManager<R> {
             var_strings.extend_from_slice(&user_vars);
 
             // Parse and render template using Tera
-            let variables = parse_variables(&var_strings)
+            let variables = parse_variables(&var_strings, &self.secrets)
                 .map_err(|e| miette!("Failed to parse variables: {}", e))?;
-            let rendered = template::render(&template_content, &variables)
-                .map_err(|e| miette!("Failed to render template: {}", e))?;
+            let partials_dir = PathBuf::from(&self.config_path).join(template::TEMPLATES_DIR);
+            let rendered = template::render(
+                &template_content,
+                &variables,
+                std::slice::from_ref(&partials_dir),
+                &self.secrets,
+            )
+            .map_err(|e| miette!("Failed to render template: {}", e))?;
 
             // Write to the file
             let mut file = fs::File::create(&config_file)
@@ -118,6 +283,134 @@ impl<R: Runner> ConfigManager<R> {
                 .map_err(|e| miette!("Could not write to '{}': {}", config_file.display(), e))?;
         }
 
+        self.open_in_editor(&config_file)
+    }
+
+    /// Creates a config for the project in the current directory, generated from
+    /// the project files found there (Cargo.toml, package.json, ...).
+    pub(crate) fn create_detected(&self, name: &Option<String>) -> Result<()> {
+        let current_path =
+            env::current_dir().map_err(|e| miette!("Failed to get current directory: {}", e))?;
+
+        let (config_file, session_path) = match name {
+            Some(name) => (
+                PathBuf::from(&self.config_path).join(format!("{}.yaml", name.sanitize())),
+                current_path.to_string_lossy().into_owned(),
+            ),
+            None => (PathBuf::from(".laio.yaml"), ".".to_string()),
+        };
+        ensure_parent_dir(&config_file)?;
+
+        let session_name = match name {
+            Some(name) => name.rsplit('/').next().unwrap_or(name).to_string(),
+            None => current_path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "changeme".to_string()),
+        };
+
+        let session = detect::detect_session(&session_name, &session_path, &current_path);
+        let yaml = noyalib::compat::serde_yaml::to_string(&session)
+            .into_diagnostic()
+            .wrap_err("Failed to generate config")?;
+
+        fs::write(&config_file, yaml)
+            .map_err(|e| miette!("Could not write to '{}': {}", config_file.display(), e))?;
+
+        self.open_in_editor(&config_file)
+    }
+
+    /// Converts the tmuxinator, tmuxp or Zellij config in `file` into config `name`,
+    /// which defaults to the session name. Zellij layouts name the session after
+    /// the file. Returns the path of the new config, and the
+    /// settings that could not be converted.
+    pub(crate) fn import(
+        &self,
+        format: &ImportFormat,
+        file: &str,
+        name: &Option<String>,
+        force: bool,
+    ) -> Result<(PathBuf, Vec<String>)> {
+        let content = fs::read_to_string(file)
+            .into_diagnostic()
+            .wrap_err(format!("Could not read '{file}'"))?;
+        let file_name = Path::new(file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let Import { session, warnings } = import::import(format, &content, &file_name)
+            .wrap_err(format!("Could not import '{file}'"))?;
+
+        let name = name.as_deref().unwrap_or(&session.name).sanitize();
+        if name.is_empty() {
+            bail!("Invalid config name for session '{}'", session.name);
+        }
+        let config_file = PathBuf::from(&self.config_path).join(format!("{name}.yaml"));
+        if config_file.exists() && !force {
+            bail!(
+                "'{}' already exists, use --force to overwrite it",
+                config_file.display()
+            );
+        }
+        ensure_parent_dir(&config_file)?;
+
+        let yaml = noyalib::compat::serde_yaml::to_string(&session)
+            .into_diagnostic()
+            .wrap_err("Failed to generate config")?;
+        fs::write(&config_file, yaml)
+            .into_diagnostic()
+            .wrap_err(format!("Could not write '{}'", config_file.display()))?;
+
+        Ok((config_file, warnings))
+    }
+
+    /// Converts config `name` into `format`, writing it to `output` if given. tmux
+    /// scripts are made executable.
+    ///
+    /// The config is rendered with the variables `laio start` would use. Secrets
+    /// aren't resolved and known ones are masked, so they never end up in the export.
+    pub(crate) fn export(
+        &self,
+        name: &str,
+        format: &ExportFormat,
+        output: &Option<String>,
+        variables: &[String],
+    ) -> Result<Export> {
+        let config_file = self.config_file(name);
+        let variables = session_variables(name, variables)?;
+        let session = self
+            .secrets
+            .without_resolving(|| {
+                Session::from_config(
+                    &config_file,
+                    Some(&variables),
+                    &self.search_path,
+                    &self.secrets,
+                )
+            })
+            .wrap_err(format!("Could not load '{}'", config_file.display()))?;
+        let mut export =
+            export::export(format, &session).wrap_err(format!("Could not export '{name}'"))?;
+        export.content = self.secrets.mask(&export.content);
+        for warning in &mut export.warnings {
+            *warning = self.secrets.mask(warning);
+        }
+
+        if let Some(output) = output {
+            fs::write(output, &export.content)
+                .into_diagnostic()
+                .wrap_err(format!("Could not write '{output}'"))?;
+            if matches!(format, ExportFormat::TmuxScript) {
+                fs::set_permissions(output, fs::Permissions::from_mode(0o755))
+                    .into_diagnostic()
+                    .wrap_err(format!("Could not make '{output}' executable"))?;
+            }
+        }
+
+        Ok(export)
+    }
+
+    fn open_in_editor(&self, config_file: &Path) -> Result<()> {
         let editor = env::var("EDITOR").unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
 
         self.cmd_runner
@@ -128,20 +421,22 @@ impl<R: Runner> ConfigManager<R> {
     pub(crate) fn edit(&self, name: &str) -> Result<()> {
         self.cmd_runner.run(&cmd_forget!(
             var("EDITOR").unwrap_or_else(|_| "vim".to_string()),
-            args = [format!("{}/{}.yaml", self.config_path, name.sanitize())]
+            args = [self.config_file(name)]
         ))
     }
 
     pub(crate) fn link(&self, name: &str, file: &str) -> Result<()> {
         let source =
             to_absolute_path(file).wrap_err(format!("Failed to get absolute path for '{file}'"))?;
-        let destination = format!("{}/{}.yaml", self.config_path, name);
+        let destination =
+            PathBuf::from(&self.config_path).join(format!("{}.yaml", name.sanitize()));
+        ensure_parent_dir(&destination)?;
         self.cmd_runner
             .run(&cmd_forget!("ln", args = ["-s", &source, &destination]))
             .wrap_err(format!(
                 "Failed to link '{}' to '{}'",
                 &source.to_string_lossy(),
-                destination
+                destination.display()
             ))
     }
 
@@ -152,7 +447,7 @@ impl<R: Runner> ConfigManager<R> {
         variables: &[String],
     ) -> Result<()> {
         let config = match name {
-            Some(name) => format!("{}/{}.yaml", &self.config_path, name),
+            Some(name) => self.config_file(name).to_string_lossy().into_owned(),
             None => {
                 let file_path = file.unwrap_or(".laio.yaml");
                 PathBuf::from(&file_path)
@@ -162,8 +457,13 @@ impl<R: Runner> ConfigManager<R> {
                     .into_owned()
             }
         };
-        let _ = Session::from_config(&PathBuf::from(&config), Some(variables))
-            .wrap_err("Validation error!")?;
+        let _ = Session::from_config(
+            &PathBuf::from(&config),
+            Some(variables),
+            &self.search_path,
+            &self.secrets,
+        )
+        .wrap_err("Validation error!")?;
         Ok(())
     }
 
@@ -177,36 +477,15 @@ impl<R: Runner> ConfigManager<R> {
                 return Ok(());
             }
         }
-        let file = format!("{}/{}.yaml", &self.config_path, name.sanitize());
+        let file = self.config_file(name);
         fs::remove_file(&file)
             .into_diagnostic()
-            .wrap_err(format!("Failed to delete '{}'", &file))?;
+            .wrap_err(format!("Failed to delete '{}'", file.display()))?;
         Ok(())
     }
 
-    pub(crate) fn list(&self) -> Result<Vec<String>> {
-        let mut entries = fs::read_dir(&self.config_path)
-            .into_diagnostic()
-            .wrap_err(format!(
-                "Failed to list config entries in '{}'",
-                &self.config_path
-            ))?
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .filter(|path| {
-                // Filter out _default.yaml and only include .yaml files
-                path.extension().and_then(|ext| ext.to_str()) == Some("yaml")
-                    && path.file_name().and_then(|n| n.to_str()) != Some("_default.yaml")
-            })
-            .map(|path| {
-                Session::from_config(&path, None)
-                    .map(|session| session.name)
-                    .wrap_err(format!("Warning: Failed to parse '{}'", path.display()))
-            })
-            .collect::<Result<Vec<String>, _>>()?;
-
-        entries.sort();
-        Ok(entries)
+    pub(crate) fn list(&self) -> Result<Vec<SessionInfo>> {
+        list_configs(&self.search_path)
     }
 }
 
@@ -215,13 +494,22 @@ pub trait ConfigNameExt {
 }
 
 impl ConfigNameExt for str {
+    /// Normalises a config name. `/` separates namespaces (subdirectories); empty,
+    /// `.` and `..` segments are dropped so a name can't escape the config directory.
     fn sanitize(&self) -> String {
-        self.replace(" ", "-")
-            .to_lowercase()
-            .chars()
-            .filter(|c| c.is_alphanumeric() || *c == '-')
-            .collect::<String>()
-            .trim_matches('-')
-            .to_string()
+        self.split('/')
+            .map(|segment| {
+                segment
+                    .replace(" ", "-")
+                    .to_lowercase()
+                    .chars()
+                    .filter(|c| c.is_alphanumeric() || *c == '-')
+                    .collect::<String>()
+                    .trim_matches('-')
+                    .to_string()
+            })
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("/")
     }
 }
@@ -1,3 +1,6 @@
+mod detect;
+pub(crate) mod export;
+pub(crate) mod import;
 pub(crate) mod manager;
 
 pub(crate) use manager::ConfigManager;
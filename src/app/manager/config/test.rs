@@ -1,8 +1,11 @@
 use crate::{
     app::ConfigManager,
-    common::cmd::{
-        test::{MockCmdBoolMock, MockCmdStringMock, MockCmdUnitMock, RunnerMock},
-        Type,
+    common::{
+        cmd::{
+            test::{MockCmdBoolMock, MockCmdStringMock, MockCmdUnitMock, RunnerMock},
+            Type,
+        },
+        config::secrets::Secrets,
     },
 };
 
@@ -474,3 +477,824 @@ windows:
     // Cleanup
     let _ = fs::remove_dir_all(&test_dir);
 }
+
+#[test]
+fn config_list_search_path() {
+    use std::fs;
+
+    let temp_dir = std::env::temp_dir();
+    let personal_dir = temp_dir.join("laio_test_list_personal");
+    let team_dir = temp_dir.join("laio_test_list_team");
+
+    for dir in [&personal_dir, &team_dir] {
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir).unwrap();
+    }
+
+    let config = |name: &str| format!("name: {name}\nwindows: []\n");
+    fs::write(personal_dir.join("shared.yaml"), config("personal")).unwrap();
+    fs::write(team_dir.join("shared.yaml"), config("team")).unwrap();
+    fs::write(team_dir.join("api.yaml"), config("api")).unwrap();
+
+    let cmd_runner = Rc::new(RunnerMock {
+        cmd_unit: MockCmdUnitMock::new(),
+        cmd_string: MockCmdStringMock::new(),
+        cmd_bool: MockCmdBoolMock::new(),
+    });
+    let search_path = format!(
+        "{}:{}:/nonexistent/laio",
+        personal_dir.display(),
+        team_dir.display()
+    );
+    let cfg = ConfigManager::new(&search_path, cmd_runner);
+
+    let configs: Vec<(String, String)> = cfg
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|c| (c.name, c.config_dir.unwrap()))
+        .collect();
+
+    assert_eq!(
+        configs,
+        vec![
+            ("api".to_string(), team_dir.display().to_string()),
+            ("personal".to_string(), personal_dir.display().to_string()),
+        ]
+    );
+
+    for dir in [&personal_dir, &team_dir] {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+#[test]
+fn config_search_path_expands_leading_tilde() {
+    use crate::app::manager::config::manager::display_dir;
+    use crate::common::path::{home_dir, split_search_path};
+
+    let home = home_dir().unwrap();
+
+    assert_eq!(
+        split_search_path("~/laio:/srv/~shared/laio:~:~other/laio"),
+        vec![
+            format!("{home}/laio"),
+            "/srv/~shared/laio".to_string(),
+            home.clone(),
+            "~other/laio".to_string(),
+        ]
+    );
+
+    assert_eq!(display_dir(&format!("{home}/laio")), "~/laio");
+    assert_eq!(display_dir(&home), "~");
+    assert_eq!(
+        display_dir(&format!("{home}2/laio")),
+        format!("{home}2/laio")
+    );
+}
+
+#[test]
+fn config_name_sanitize_namespaces() {
+    use crate::app::manager::config::manager::ConfigNameExt;
+
+    assert_eq!("My Project".sanitize(), "my-project");
+    assert_eq!("work/api".sanitize(), "work/api");
+    assert_eq!("Work/My API/".sanitize(), "work/my-api");
+    assert_eq!("../../etc/passwd".sanitize(), "etc/passwd");
+    assert_eq!("/work//./api".sanitize(), "work/api");
+}
+
+#[test]
+fn config_list_namespaced() {
+    use std::fs;
+
+    let config_dir = std::env::temp_dir().join("laio_test_list_namespaced");
+    let _ = fs::remove_dir_all(&config_dir);
+    fs::create_dir_all(config_dir.join("work/clients")).unwrap();
+    fs::create_dir_all(config_dir.join("_templates")).unwrap();
+
+    let config = |name: &str| format!("name: {name}\nwindows: []\n");
+    fs::write(config_dir.join("zeta.yaml"), config("zeta")).unwrap();
+    fs::write(config_dir.join("work/api.yaml"), config("api")).unwrap();
+    fs::write(config_dir.join("work/clients/acme.yaml"), config("acme")).unwrap();
+    // Listed by path, not by the session name in the file
+    fs::write(config_dir.join("work/web.yaml"), config("frontend")).unwrap();
+    fs::write(config_dir.join("_templates/pane.yaml"), "not: a-config").unwrap();
+
+    let cmd_runner = Rc::new(RunnerMock {
+        cmd_unit: MockCmdUnitMock::new(),
+        cmd_string: MockCmdStringMock::new(),
+        cmd_bool: MockCmdBoolMock::new(),
+    });
+    let cfg = ConfigManager::new(config_dir.to_str().unwrap(), cmd_runner);
+
+    let names: Vec<String> = cfg.list().unwrap().into_iter().map(|c| c.name).collect();
+    assert_eq!(
+        names,
+        vec!["zeta", "work/api", "work/web", "work/clients/acme"]
+    );
+
+    cfg.validate(&Some("work/api".to_string()), None, &[])
+        .expect("namespaced config should validate");
+    cfg.validate(&Some("work/web".to_string()), None, &[])
+        .expect("config should validate by its listed name");
+
+    let _ = fs::remove_dir_all(&config_dir);
+}
+
+#[test]
+fn config_list_details() {
+    use crate::common::session_info::{ConfigSource, SessionInfo, SessionStatus};
+    use std::{fs, os::unix::fs::symlink};
+
+    let config_dir = std::env::temp_dir().join("laio_test_list_details");
+    let _ = fs::remove_dir_all(&config_dir);
+    fs::create_dir_all(config_dir.join("project")).unwrap();
+
+    fs::write(
+        config_dir.join("api.yaml"),
+        "name: api\ndescription: REST backend\ntags: [work, rust]\nwindows: []\n",
+    )
+    .unwrap();
+    fs::write(
+        config_dir.join("project/.laio.yaml"),
+        "name: app\ntags: [work]\nwindows: []\n",
+    )
+    .unwrap();
+    symlink(
+        config_dir.join("project/.laio.yaml"),
+        config_dir.join("app.yaml"),
+    )
+    .unwrap();
+
+    let cmd_runner = Rc::new(RunnerMock {
+        cmd_unit: MockCmdUnitMock::new(),
+        cmd_string: MockCmdStringMock::new(),
+        cmd_bool: MockCmdBoolMock::new(),
+    });
+    let cfg = ConfigManager::new(config_dir.to_str().unwrap(), cmd_runner);
+    let configs = cfg.list().unwrap();
+
+    let api = &configs[0];
+    assert_eq!(api.name, "api");
+    assert_eq!(api.description.as_deref(), Some("REST backend"));
+    assert_eq!(api.tags, vec!["work", "rust"]);
+    assert_eq!(
+        api.config_path.as_deref(),
+        config_dir.join("api.yaml").to_str()
+    );
+    assert_eq!(api.config_source, Some(ConfigSource::Local));
+    assert_eq!(configs[1].name, "app");
+    assert_eq!(configs[1].config_source, Some(ConfigSource::Symlink));
+
+    // A running session keeps the details of its config
+    let merged = SessionInfo::merge(vec![SessionInfo::active("api".to_string(), true)], configs);
+    assert_eq!(merged[0].status, SessionStatus::Attached);
+    assert_eq!(merged[0].description.as_deref(), Some("REST backend"));
+    assert!(merged[0].has_tag("rust"));
+    assert_eq!(merged[0].to_string(), "● api - REST backend #work #rust");
+
+    let _ = fs::remove_dir_all(&config_dir);
+}
+
+#[test]
+fn config_detect_project() {
+    use super::detect::detect_session;
+    use crate::common::config::Session;
+    use std::fs;
+
+    let project_dir = std::env::temp_dir().join("laio_test_detect_project");
+    let _ = fs::remove_dir_all(&project_dir);
+    fs::create_dir_all(project_dir.join("crates/core")).unwrap();
+    fs::create_dir_all(project_dir.join("crates/cli")).unwrap();
+    fs::create_dir_all(project_dir.join("crates/docs")).unwrap();
+
+    fs::write(
+        project_dir.join("Cargo.toml"),
+        "[workspace]\nmembers = [\n  \"crates/*\", # all crates\n]\n\n[profile.release]\nlto = true\n",
+    )
+    .unwrap();
+    fs::write(project_dir.join("crates/core/Cargo.toml"), "").unwrap();
+    fs::write(project_dir.join("crates/cli/Cargo.toml"), "").unwrap();
+    fs::write(
+        project_dir.join("package.json"),
+        r#"{"scripts": {"dev": "vite", "test": "vitest"}}"#,
+    )
+    .unwrap();
+    fs::write(project_dir.join("pnpm-lock.yaml"), "").unwrap();
+    fs::write(project_dir.join("compose.yaml"), "services: {}\n").unwrap();
+    fs::write(
+        project_dir.join("Makefile"),
+        ".PHONY: build serve\nVERSION := 1\nbuild: deps\n\tcargo build\nserve:\n\t./serve.sh\n%.o: %.c\n",
+    )
+    .unwrap();
+
+    let session = detect_session("myproject", ".", &project_dir);
+
+    let windows: Vec<&str> = session.windows.iter().map(|w| w.name.as_str()).collect();
+    assert_eq!(
+        windows,
+        vec!["code", "cargo", "crates", "node", "make", "compose"]
+    );
+
+    let commands = |window: usize| -> Vec<String> {
+        session.windows[window]
+            .panes
+            .iter()
+            .flat_map(|p| p.commands.iter().map(|c| c.to_string()))
+            .collect()
+    };
+    assert_eq!(
+        commands(1),
+        vec!["cargo watch -x \"check --workspace\" -x \"test --workspace\""]
+    );
+    let member_paths: Vec<&str> = session.windows[2]
+        .panes
+        .iter()
+        .map(|p| p.path.as_str())
+        .collect();
+    assert_eq!(member_paths, vec!["crates/cli", "crates/core"]);
+    assert_eq!(commands(3), vec!["pnpm run dev", "pnpm test"]);
+    assert_eq!(commands(4), vec!["make serve"]);
+    assert_eq!(commands(5), vec!["docker compose logs -f"]);
+    assert_eq!(session.startup[0].to_string(), "docker compose up -d");
+    assert_eq!(session.shutdown[0].to_string(), "docker compose down");
+
+    // The generated config must load like any other
+    let config = project_dir.join(".laio.yaml");
+    fs::write(
+        &config,
+        noyalib::compat::serde_yaml::to_string(&session).unwrap(),
+    )
+    .unwrap();
+    let loaded = Session::from_config(&config, None, &[], &Secrets::default()).unwrap();
+    assert_eq!(loaded.name, "myproject");
+    assert_eq!(loaded.windows.len(), 6);
+
+    let _ = fs::remove_dir_all(&project_dir);
+}
+
+#[test]
+fn config_detect_toml_manifests() {
+    use super::detect::detect_session;
+    use std::fs;
+
+    let project_dir = std::env::temp_dir().join("laio_test_detect_toml");
+    let commands = |manifest: &str, content: &str| -> Vec<Vec<String>> {
+        let _ = fs::remove_dir_all(&project_dir);
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join(manifest), content).unwrap();
+        detect_session("myproject", ".", &project_dir)
+            .windows
+            .iter()
+            .skip(1)
+            .map(|w| {
+                w.panes
+                    .iter()
+                    .map(|p| match p.commands.first() {
+                        Some(c) => c.to_string(),
+                        None => p.path.clone(),
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    let workspace = vec![
+        vec![
+            "cargo watch -x \"check --workspace\" -x \"test --workspace\"".to_string(),
+            ".".to_string(),
+        ],
+        vec!["api".to_string(), "cli".to_string()],
+    ];
+
+    // Inline tables, quoted keys and arrays with comments
+    assert_eq!(
+        commands("Cargo.toml", "workspace = { members = [\"api\", 'cli'] }\n"),
+        workspace
+    );
+    assert_eq!(
+        commands(
+            "Cargo.toml",
+            "[workspace]\n\"members\" = [\n  \"api\", # the server\n  \"cli\",\n]\n[workspace.package]\nversion = \"1.0.0\"\n"
+        ),
+        workspace
+    );
+
+    let python = |content: &str| commands("pyproject.toml", content);
+    let repl_and_test = |run: &str| {
+        vec![vec![
+            format!("{run}python"),
+            format!("{run}pytest"),
+            ".".to_string(),
+        ]]
+    };
+    let repl = vec![vec!["python".to_string(), ".".to_string()]];
+
+    // Mentioning pytest isn't enough
+    assert_eq!(
+        python(
+            "[project]\ndescription = \"Works with pytest\"\ndependencies = [\"pytest-timeout\"]\n"
+        ),
+        repl
+    );
+    assert_eq!(
+        python("[project]\ndependencies = [\"requests\"]\n[project.optional-dependencies]\ntest = [\"pytest>=8; python_version > '3.8'\"]\n"),
+        repl_and_test("")
+    );
+    assert_eq!(
+        python("[dependency-groups]\ndev = [\"PyTest[toml]\", { include-group = \"lint\" }]\nlint = [\"ruff\"]\n"),
+        repl_and_test("")
+    );
+    assert_eq!(
+        python("[tool.pytest.ini_options]\naddopts = \"-q\"\n"),
+        repl_and_test("")
+    );
+    assert_eq!(
+        python("[tool.poetry]\nname = \"app\"\n[tool.poetry.group.test.dependencies]\npytest = \"^8\"\n"),
+        repl_and_test("poetry run ")
+    );
+    // A broken manifest is still detected
+    assert_eq!(python("[project\n"), repl);
+
+    let _ = fs::remove_dir_all(&project_dir);
+}
+
+#[test]
+fn config_detect_nix_flake() {
+    use super::detect::detect_session;
+    use std::fs;
+
+    let project_dir = std::env::temp_dir().join("laio_test_detect_flake");
+    let _ = fs::remove_dir_all(&project_dir);
+    fs::create_dir_all(&project_dir).unwrap();
+    fs::write(project_dir.join("flake.nix"), "{}").unwrap();
+    fs::write(
+        project_dir.join("pyproject.toml"),
+        "[tool.poetry]\nname = \"app\"\n\n[tool.poetry.dev-dependencies]\npytest = \"*\"\n",
+    )
+    .unwrap();
+
+    let session = detect_session("app", ".", &project_dir);
+    let python = &session.windows[1];
+    let commands: Vec<String> = python
+        .panes
+        .iter()
+        .flat_map(|p| p.commands.iter().map(|c| c.to_string()))
+        .collect();
+
+    assert_eq!(python.name, "python");
+    assert_eq!(
+        commands,
+        vec![
+            "nix develop --command poetry run python",
+            "nix develop --command poetry run pytest",
+            "nix develop",
+        ]
+    );
+
+    // direnv already enters the flake
+    fs::write(project_dir.join(".envrc"), "use flake\n").unwrap();
+    let session = detect_session("app", ".", &project_dir);
+    assert_eq!(
+        session.windows[1].panes[0].commands[0].to_string(),
+        "poetry run python"
+    );
+
+    let _ = fs::remove_dir_all(&project_dir);
+}
+
+#[test]
+fn config_import_tmuxinator() {
+    use super::import::{import, ImportFormat};
+    use crate::common::config::FlexDirection;
+
+    let project = r#"
+name: blog
+root: ~/code/blog
+on_project_first_start: docker compose up -d
+on_project_start: bundle install
+on_project_stop: docker compose down
+pre_window: rbenv shell 2.7
+startup_window: logs
+startup_pane: 1
+tmux_options: -f ~/.tmux.mac.conf
+windows:
+  - editor:
+      layout: main-vertical
+      panes:
+        - vim
+        - guard
+        - server: bundle exec rails s -p 3000
+  - logs:
+      root: log
+      layout: tiled
+      synchronize: after
+      panes:
+        - tail -f development.log
+        - tail -f test.log | grep ERROR
+        -
+  - shell:
+"#;
+
+    let imported = import(&ImportFormat::Tmuxinator, project, "").unwrap();
+    let session = imported.session;
+    assert_eq!(session.name, "blog");
+    assert_eq!(session.path, "~/code/blog");
+    let startup: Vec<String> = session.startup.iter().map(|c| c.to_string()).collect();
+    assert_eq!(startup, vec!["docker compose up -d", "bundle install"]);
+    assert_eq!(session.shutdown[0].to_string(), "docker compose down");
+
+    // main-vertical: the main pane left, the others stacked on the right
+    let editor = &session.windows[0];
+    assert!(matches!(editor.flex_direction, FlexDirection::Row));
+    assert_eq!(editor.panes[0].commands[1].to_string(), "vim");
+    assert!(matches!(
+        editor.panes[1].flex_direction,
+        FlexDirection::Column
+    ));
+    let server = &editor.panes[1].panes[1];
+    assert_eq!(server.name.as_deref(), Some("server"));
+    assert_eq!(server.commands[0].to_string(), "rbenv shell 2.7");
+    assert_eq!(server.commands[1].command, "bundle");
+    assert_eq!(server.commands[1].args.len(), 5);
+
+    // tiled: two rows of two and one pane
+    let logs = &session.windows[1];
+    assert_eq!(logs.path.as_deref(), Some("log"));
+    assert!(logs.focus);
+    assert_eq!(logs.panes.len(), 2);
+    assert_eq!(logs.panes[0].panes.len(), 2);
+    assert!(logs.panes[0].panes[1].focus);
+    // Shell syntax is kept as a single command line
+    let grep = &logs.panes[0].panes[1].commands[1];
+    assert_eq!(grep.command, "tail -f test.log | grep ERROR");
+    assert!(grep.args.is_empty());
+
+    assert_eq!(session.windows[2].name, "shell");
+
+    assert_eq!(
+        imported.warnings,
+        vec![
+            "Project setting 'tmux_options' is not supported",
+            "Window setting 'synchronize' of 'logs' is not supported",
+        ]
+    );
+}
+
+#[test]
+fn config_import_tmuxp() {
+    use super::import::{import, ImportFormat};
+    use crate::common::config::FlexDirection;
+
+    let workspace = r#"
+session_name: api
+start_directory: ~/code/api
+before_script: ./bootstrap.sh
+shell_command_before:
+  - source .venv/bin/activate
+environment:
+  DEBUG: 1
+options:
+  automatic-rename: on
+windows:
+  - window_name: code
+    focus: true
+    layout: main-horizontal
+    panes:
+      - shell_command:
+          - cmd: nvim
+      - focus: true
+      - pytest -x
+  - window_name: db
+    start_directory: db
+    layout: 5e79,80x24,0,0{40x24,0,0,1,39x24,41,0,2}
+    panes:
+      - pgcli
+      - null
+  - window_name: odd
+    layout: a4a2,80x24,0,0,3
+    panes:
+      - htop
+      - top
+"#;
+
+    let imported = import(&ImportFormat::Tmuxp, workspace, "").unwrap();
+    let session = imported.session;
+    assert_eq!(session.name, "api");
+    assert_eq!(session.path, "~/code/api");
+    assert_eq!(session.startup[0].to_string(), "./bootstrap.sh");
+    assert_eq!(session.env.get("DEBUG").map(String::as_str), Some("1"));
+
+    // main-horizontal: the main pane on top, the others side by side below
+    let code = &session.windows[0];
+    assert!(code.focus);
+    assert!(matches!(code.flex_direction, FlexDirection::Column));
+    let commands: Vec<String> = code.panes[0]
+        .commands
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(commands, vec!["source .venv/bin/activate", "nvim"]);
+    assert!(matches!(code.panes[1].flex_direction, FlexDirection::Row));
+    assert!(code.panes[1].panes[0].focus);
+    assert_eq!(code.panes[1].panes[1].commands[1].to_string(), "pytest -x");
+
+    // Custom layouts keep their split
+    let db = &session.windows[1];
+    assert_eq!(db.path.as_deref(), Some("db"));
+    assert!(matches!(db.flex_direction, FlexDirection::Row));
+    assert_eq!(db.panes.len(), 2);
+    assert_eq!(db.panes[0].commands[1].to_string(), "pgcli");
+
+    assert!(matches!(
+        session.windows[2].flex_direction,
+        FlexDirection::Column
+    ));
+
+    assert_eq!(
+        imported.warnings,
+        vec![
+            "Workspace setting 'options' is not supported",
+            "Layout 'a4a2,80x24,0,0,3' is not supported, panes are stacked instead in 'odd'",
+        ]
+    );
+}
+
+#[test]
+fn config_import_writes_config() {
+    use super::import::ImportFormat;
+    use crate::common::config::Session;
+    use std::fs;
+
+    let dir = std::env::temp_dir().join("laio_test_config_import");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let project = dir.join("project.yml");
+    fs::write(
+        &project,
+        "name: My Project\nroot: .\nwindows:\n  - editor: vim\n  - shell:\n",
+    )
+    .unwrap();
+
+    let cmd_runner = Rc::new(RunnerMock {
+        cmd_unit: MockCmdUnitMock::new(),
+        cmd_string: MockCmdStringMock::new(),
+        cmd_bool: MockCmdBoolMock::new(),
+    });
+    let cfg = ConfigManager::new(dir.to_str().unwrap(), cmd_runner);
+    let file = project.to_str().unwrap();
+    let (config_file, warnings) = cfg
+        .import(&ImportFormat::Tmuxinator, file, &None, false)
+        .unwrap();
+    assert_eq!(config_file, dir.join("my-project.yaml"));
+    assert!(warnings.is_empty());
+
+    let session = Session::from_config(&config_file, None, &[], &Secrets::default()).unwrap();
+    assert_eq!(session.name, "My Project");
+    assert_eq!(session.windows[0].panes[0].commands[0].to_string(), "vim");
+    assert!(session.windows[1].panes.is_empty());
+
+    assert!(cfg
+        .import(&ImportFormat::Tmuxinator, file, &None, false)
+        .is_err());
+    cfg.import(
+        &ImportFormat::Tmuxinator,
+        file,
+        &Some("work/blog".to_string()),
+        false,
+    )
+    .unwrap();
+    assert!(dir.join("work/blog.yaml").exists());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn config_import_zellij() {
+    use super::import::{import, ImportFormat};
+    use crate::common::config::FlexDirection;
+
+    let layout = r#"
+layout {
+    cwd "/work/app"
+    default_tab_template {
+        pane size=1 borderless=true {
+            plugin location="zellij:tab-bar"
+        }
+        children
+        pane size=2 borderless=true {
+            plugin location="zellij:status-bar"
+        }
+    }
+    pane_template name="editor" command="nvim" {
+        args "."
+    }
+    pane_template name="sidebar" split_direction="vertical" {
+        pane size="25%"
+        children
+    }
+    tab_template name="plain" {
+        children
+    }
+    tab name="code" focus=true split_direction="vertical" {
+        editor size="70%"
+        pane cwd="docs" size="30%"
+    }
+    tab name="server" cwd="api" {
+        pane size=10 command="cargo" {
+            args "run" "--release"
+            start_suspended true
+        }
+        pane size=30 cwd="logs" command="tail" {
+            args "-f" "app.log"
+        }
+    }
+    plain name="files" {
+        sidebar {
+            editor cwd="src" {
+                args "main.rs"
+            }
+        }
+    }
+    tab name="shell"
+    swap_tiled_layout name="wide" {
+        tab max_panes=4 {
+            pane
+        }
+    }
+}
+"#;
+
+    let imported = import(&ImportFormat::Zellij, layout, "app").unwrap();
+    let session = imported.session;
+    assert_eq!(session.name, "app");
+    assert_eq!(session.path, "/work/app");
+    let names: Vec<&str> = session.windows.iter().map(|w| w.name.as_str()).collect();
+    assert_eq!(names, vec!["code", "server", "files", "shell"]);
+
+    // Tab bar and status bar of the default tab template are dropped
+    let code = &session.windows[0];
+    assert!(code.focus);
+    assert!(matches!(code.flex_direction, FlexDirection::Row));
+    assert_eq!(code.panes.len(), 2);
+    assert_eq!(code.panes[0].commands[0].to_string(), "nvim .");
+    assert_eq!(code.panes[1].path, "docs");
+    assert_eq!(
+        code.panes.iter().map(|p| p.flex).collect::<Vec<_>>(),
+        vec![2, 1]
+    );
+
+    // Sizes in cells keep their ratio, cwds are relative to the tab
+    let server = &session.windows[1];
+    assert_eq!(server.path.as_deref(), Some("api"));
+    assert!(matches!(server.flex_direction, FlexDirection::Column));
+    assert_eq!(
+        server.panes.iter().map(|p| p.flex).collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    assert_eq!(
+        server.panes[0].commands[0].to_string(),
+        "cargo run --release"
+    );
+    assert_eq!(server.panes[1].path, "logs");
+    assert_eq!(server.panes[1].commands[0].to_string(), "tail -f app.log");
+
+    // Nested templates, with the args of the pane replacing those of the template
+    let files = &session.windows[2];
+    assert_eq!(files.panes.len(), 1);
+    let sidebar = &files.panes[0];
+    assert!(matches!(sidebar.flex_direction, FlexDirection::Row));
+    assert_eq!(
+        sidebar.panes.iter().map(|p| p.flex).collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    assert_eq!(sidebar.panes[1].path, "src");
+    assert_eq!(sidebar.panes[1].commands[0].to_string(), "nvim main.rs");
+
+    assert!(session.windows[3].panes.is_empty());
+
+    assert_eq!(
+        imported.warnings,
+        vec![
+            "Swap layouts are not supported",
+            "Setting 'start_suspended' in 'server' is not supported",
+        ]
+    );
+
+    // Panes without tabs make up a single tab
+    let imported = import(
+        &ImportFormat::Zellij,
+        "layout {\n    pane split_direction=\"vertical\" {\n        pane\n        pane command=\"htop\"\n    }\n}\n",
+        "top",
+    )
+    .unwrap();
+    let window = &imported.session.windows[0];
+    assert_eq!(window.name, "tab-1");
+    assert!(matches!(window.flex_direction, FlexDirection::Row));
+    assert_eq!(window.panes[1].commands[0].to_string(), "htop");
+}
+
+#[test]
+fn config_export() {
+    use super::export::ExportFormat;
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    let dir = std::env::temp_dir().join("laio_test_config_export");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy("src/common/config/test/valid.yaml", dir.join("valid.yaml")).unwrap();
+
+    let cmd_runner = Rc::new(RunnerMock {
+        cmd_unit: MockCmdUnitMock::new(),
+        cmd_string: MockCmdStringMock::new(),
+        cmd_bool: MockCmdBoolMock::new(),
+    });
+    let cfg = ConfigManager::new(dir.to_str().unwrap(), cmd_runner);
+
+    let export = cfg
+        .export("valid", &ExportFormat::ZellijKdl, &None, &[])
+        .unwrap();
+    assert!(export.content.starts_with("layout cwd=\"/tmp\""));
+    assert!(export
+        .content
+        .contains("pane size=\"50%\" cwd=\"two\" command=\"echo\""));
+    assert!(export
+        .warnings
+        .contains(&"Environment variables are not exported".to_string()));
+    assert!(export
+        .warnings
+        .contains(&"Panes in 'infrastructure' only keep their last command".to_string()));
+
+    let output = dir.join("valid.sh");
+    let export = cfg
+        .export(
+            "valid",
+            &ExportFormat::TmuxScript,
+            &Some(output.to_string_lossy().into_owned()),
+            &[],
+        )
+        .unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), export.content);
+    let mode = fs::metadata(&output).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+
+    assert!(cfg
+        .export("missing", &ExportFormat::Tmuxinator, &None, &[])
+        .is_err());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn config_export_renders_like_start_without_secrets() {
+    use super::export::ExportFormat;
+    use std::fs;
+
+    let dir = std::env::temp_dir().join("laio_test_config_export_secrets");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("work")).unwrap();
+    let ran = dir.join("secret-command-ran");
+    fs::write(
+        dir.join("work/api.yaml"),
+        format!(
+            r#"name: {{{{ session_name }}}}
+path: {{{{ path }}}}
+env:
+  TOKEN: "{{{{ secret(name='token', command='touch {} && echo from-command') }}}}"
+  API_KEY: "{{{{ api_key }}}}"
+windows:
+  - name: main
+    panes:
+      - commands:
+          - command: echo
+            args: ["{{{{ api_key }}}}"]
+"#,
+            ran.display()
+        ),
+    )
+    .unwrap();
+
+    let cmd_runner = Rc::new(RunnerMock {
+        cmd_unit: MockCmdUnitMock::new(),
+        cmd_string: MockCmdStringMock::new(),
+        cmd_bool: MockCmdBoolMock::new(),
+    });
+    let cfg = ConfigManager::new(dir.to_str().unwrap(), cmd_runner);
+
+    let export = cfg
+        .export(
+            "work/api",
+            &ExportFormat::TmuxScript,
+            &None,
+            &["api_key:secret=hunter2-key".to_string()],
+        )
+        .unwrap();
+    assert!(export.content.contains("session=api"));
+    assert!(!export.content.contains("hunter2-key"));
+    assert!(!export.content.contains("from-command"));
+    assert!(export.content.contains("TOKEN=********"));
+    assert!(export.content.contains("API_KEY=********"));
+    assert!(!ran.exists());
+
+    let _ = fs::remove_dir_all(&dir);
+}
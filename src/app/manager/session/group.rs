@@ -0,0 +1,89 @@
+//! Groups of sessions that are started and stopped together, defined in
+//! `_groups/<name>.yaml` in a config directory.
+
+use std::{fs, path::PathBuf};
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::common::path::find_in_search_path;
+
+const GROUPS_DIR: &str = "_groups";
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Group {
+    #[serde(skip_deserializing)]
+    pub(crate) name: String,
+    pub(crate) sessions: Vec<Member>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Member {
+    /// Name of the config, as taken by `laio start`.
+    pub(crate) config: String,
+    /// Template variables in `key=value` format.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) variables: Vec<String>,
+    /// Attach to this session instead of the last one.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) attach: bool,
+}
+
+impl Group {
+    /// Loads group `name` from the first directory of the search path that has it.
+    pub(crate) fn load(search_path: &[String], name: &str) -> Result<Self> {
+        let file_name = format!("{GROUPS_DIR}/{name}.yaml");
+        let Some(file) = find_in_search_path(search_path, &file_name) else {
+            bail!("Group '{name}' not found, expected '{file_name}' in a config directory");
+        };
+        let yaml = fs::read_to_string(&file)
+            .into_diagnostic()
+            .wrap_err(format!("Could not read '{}'", file.display()))?;
+        let mut group: Group = noyalib::compat::serde_yaml::from_str(&yaml)
+            .into_diagnostic()
+            .wrap_err(format!("Could not parse '{}'", file.display()))?;
+
+        if group.sessions.is_empty() {
+            bail!("Group '{name}' has no sessions");
+        }
+        if group.sessions.iter().filter(|m| m.attach).count() > 1 {
+            bail!("Group '{name}' has more than one session to attach to");
+        }
+        group.name = name.to_string();
+        Ok(group)
+    }
+
+    /// Groups defined in the search path, an earlier directory shadowing a group of
+    /// the same name in a later one.
+    pub(crate) fn list(search_path: &[String]) -> Result<Vec<Self>> {
+        let mut names: Vec<String> = vec![];
+        for dir in search_path {
+            let Ok(entries) = fs::read_dir(PathBuf::from(dir).join(GROUPS_DIR)) else {
+                continue;
+            };
+            for path in entries.filter_map(|entry| entry.ok()).map(|e| e.path()) {
+                if path.extension().is_some_and(|ext| ext == "yaml")
+                    && let Some(name) = path.file_stem().and_then(|n| n.to_str())
+                    && !names.iter().any(|n| n == name)
+                {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        names
+            .iter()
+            .map(|name| Self::load(search_path, name))
+            .collect()
+    }
+
+    /// Members in the order they are started, the one to attach to last.
+    pub(crate) fn start_order(&self) -> Vec<&Member> {
+        let (attach, mut members): (Vec<&Member>, Vec<&Member>) =
+            self.sessions.iter().partition(|m| m.attach);
+        members.extend(attach);
+        members
+    }
+}
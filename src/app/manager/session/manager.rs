@@ -1,15 +1,35 @@
 use crate::{
-    app::manager::config::manager::ConfigNameExt,
+    app::manager::config::manager::{list_configs, ConfigNameExt},
     common::{muxer::Multiplexer, session_info::SessionInfo},
 };
-use inquire::Select;
 use miette::{bail, miette, Context, IntoDiagnostic, Result};
-use std::{env, fs, io::Write, path::PathBuf};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use crate::{
     app::manager::config::manager::TEMPLATE,
-    common::config::Session,
-    common::path::{find_config, resolve_symlink, to_absolute_path},
+    common::config::{
+        diff::{diff, SessionDiff},
+        overlay::overlay,
+        secrets::{self, Secrets},
+        variables::{is_secret_variable, session_variables, variable_name},
+        Session,
+    },
+    common::path::{
+        default_state_dir, find_config, find_in_search_path, resolve_symlink, same_file,
+        split_search_path, to_absolute_path,
+    },
+};
+
+use super::{
+    group::{Group, Member},
+    picker::pick,
+    recent::{self, SessionSort},
+    running::{self, RunningSession},
+    snapshot::{snapshot_dir, Snapshot},
 };
 
 pub(crate) const LAIO_CONFIG: &str = "LAIO_CONFIG";
@@ -19,6 +39,7 @@ const DEFAULT_CONFIG: &str = "_default.yaml";
 
 /// Encode variables to URL-encoded format: key1=value1&key2=value2
 /// Values are percent-encoded for safety with special characters
+/// Secret values are withheld (`token:secret=`) so they never reach the session
 pub(crate) fn encode_variables(variables: &[String]) -> Result<String> {
     if variables.is_empty() {
         return Ok(String::new());
@@ -32,7 +53,7 @@ pub(crate) fn encode_variables(variables: &[String]) -> Result<String> {
                 bail!("Invalid variable format '{}', expected 'key=value'", var);
             }
             let key = parts[0];
-            let value = parts[1];
+            let value = if is_secret_variable(var) { "" } else { parts[1] };
             Ok(format!(
                 "{}={}",
                 urlencoding::encode(key),
@@ -72,33 +93,90 @@ pub(crate) fn decode_variables(encoded: &str) -> Result<Vec<String>> {
         .collect()
 }
 
+/// `variables` added to `base`, replacing the ones of the same name.
+fn replace_variables(base: &[String], variables: &[String]) -> Vec<String> {
+    let replaced: Vec<&str> = variables.iter().filter_map(|v| variable_name(v)).collect();
+    base.iter()
+        .filter(|v| !variable_name(v).is_some_and(|name| replaced.contains(&name)))
+        .chain(variables)
+        .cloned()
+        .collect()
+}
+
+fn session_yaml(session: &Session, secrets: &Secrets) -> Result<String> {
+    let yaml = noyalib::compat::serde_yaml::to_string(session)
+        .into_diagnostic()
+        .wrap_err("Multiplexer unable to generate yaml representation of current session.")?;
+
+    Ok(secrets.mask(&yaml))
+}
+
 pub(crate) struct SessionManager {
+    /// Primary config directory.
     pub(crate) config_path: String,
+    /// All config directories, in order of precedence.
+    pub(crate) search_path: Vec<String>,
+    /// Directory for state such as snapshots.
+    pub(crate) state_path: String,
+    /// Whether the running and recently used sessions are recorded, for
+    /// [`Self::resurrect`] and [`Self::switch`].
+    pub(crate) record_sessions: bool,
+    /// Order of the sessions in the picker.
+    pub(crate) picker_sort: SessionSort,
+    /// Secrets of this invocation, used to render configs and masked in output.
+    pub(crate) secrets: Secrets,
     pub(crate) multiplexer: Box<dyn Multiplexer>,
 }
 
 impl SessionManager {
+    /// Creates a manager for a colon-separated list of config directories.
     pub(crate) fn new(config_path: &str, multiplexer: Box<dyn Multiplexer>) -> Self {
+        let search_path = split_search_path(config_path);
         Self {
-            config_path: config_path.replace('~', env::var("HOME").unwrap().as_str()),
+            config_path: search_path.first().cloned().unwrap_or_default(),
+            search_path,
+            state_path: default_state_dir(),
+            record_sessions: false,
+            picker_sort: SessionSort::default(),
+            secrets: Secrets::default(),
             multiplexer,
         }
     }
 
-    /// Generate _default.yaml if it doesn't exist
-    fn ensure_default_config(&self) -> Result<PathBuf> {
-        let default_path = PathBuf::from(&self.config_path).join(DEFAULT_CONFIG);
+    /// Records the running sessions whenever sessions are started or stopped, and the
+    /// sessions used whenever one is attached or switched to.
+    pub(crate) fn with_session_record(mut self) -> Self {
+        self.record_sessions = true;
+        self
+    }
 
-        if !default_path.exists() {
-            log::info!("Generating default config at {}", default_path.display());
+    pub(crate) fn with_picker_sort(mut self, sort: SessionSort) -> Self {
+        self.picker_sort = sort;
+        self
+    }
+
+    /// Renders configs with `secrets`, shared with the runner of the multiplexer so
+    /// they are masked in the commands it prints.
+    pub(crate) fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = secrets;
+        self
+    }
 
-            // Write the raw template (NOT rendered) so it can be used with variables
-            let mut file = fs::File::create(&default_path)
-                .map_err(|e| miette!("Could not create '{}': {}", default_path.display(), e))?;
-            file.write_all(TEMPLATE.as_bytes())
-                .map_err(|e| miette!("Could not write to '{}': {}", default_path.display(), e))?;
+    /// Use the first _default.yaml on the search path, generating one if none exists
+    fn ensure_default_config(&self) -> Result<PathBuf> {
+        if let Some(default_path) = find_in_search_path(&self.search_path, DEFAULT_CONFIG) {
+            return Ok(default_path);
         }
 
+        let default_path = PathBuf::from(&self.config_path).join(DEFAULT_CONFIG);
+        log::info!("Generating default config at {}", default_path.display());
+
+        // Write the raw template (NOT rendered) so it can be used with variables
+        let mut file = fs::File::create(&default_path)
+            .map_err(|e| miette!("Could not create '{}': {}", default_path.display(), e))?;
+        file.write_all(TEMPLATE.as_bytes())
+            .map_err(|e| miette!("Could not write to '{}': {}", default_path.display(), e))?;
+
         Ok(default_path)
     }
 
@@ -108,14 +186,24 @@ impl SessionManager {
         name: &str,
         variables: &[String],
     ) -> Result<(PathBuf, Vec<String>)> {
-        let config_file = format!("{}/{}.yaml", &self.config_path, name.sanitize());
-        let config_path = to_absolute_path(&config_file)
-            .wrap_err(format!("Could not get absolute path for '{config_file}'"))?;
-
-        // Try to resolve the symlink - this will fail if file doesn't exist
-        let config = match resolve_symlink(&config_path) {
-            Ok(resolved) => resolved,
-            Err(_) => {
+        let file_name = format!("{}.yaml", name.sanitize());
+
+        // Take the config from the first directory on the search path that has it
+        let config = match find_in_search_path(&self.search_path, &file_name) {
+            Some(config_file) => {
+                let config_path = to_absolute_path(&config_file.to_string_lossy())
+                    .wrap_err(format!(
+                        "Could not get absolute path for '{}'",
+                        config_file.display()
+                    ))?;
+                log::info!(
+                    "Using config '{}' from '{}'",
+                    name,
+                    config_path.parent().unwrap_or(&config_path).display()
+                );
+                resolve_symlink(&config_path)?
+            }
+            None => {
                 // Config doesn't exist, fallback to _default.yaml
                 log::info!(
                     "Config '{}' not found, falling back to default template",
@@ -126,26 +214,7 @@ impl SessionManager {
             }
         };
 
-        // Filter out session_name from user variables (it's provided as first parameter)
-        // and check if path is provided
-        let mut effective_variables: Vec<String> = variables
-            .iter()
-            .filter(|v| !v.starts_with("session_name="))
-            .cloned()
-            .collect();
-
-        // ALWAYS auto-inject session_name variable
-        effective_variables.push(format!("session_name={}", name));
-
-        // Auto-inject path variable if not provided, defaulting to cwd
-        if !effective_variables.iter().any(|v| v.starts_with("path=")) {
-            let cwd = env::current_dir()
-                .into_diagnostic()
-                .wrap_err("Failed to get current directory")?;
-            effective_variables.push(format!("path={}", cwd.display()));
-        }
-
-        Ok((config, effective_variables))
+        Ok((config, session_variables(name, variables)?))
     }
 
     pub(crate) fn start(
@@ -171,7 +240,7 @@ impl SessionManager {
                     Some((config, active_session)) => {
                         // If session is already running, switch to it
                         if let Some(ref session_name) = active_session
-                            && self.multiplexer.switch(session_name, skip_attach)?
+                            && self.switch_to(session_name, skip_attach)?
                         {
                             return Ok(());
                         }
@@ -185,12 +254,31 @@ impl SessionManager {
             },
         };
 
-        let session = Session::from_config(&config, Some(&effective_variables)).wrap_err(
-            format!("Could not load session from '{}'", config.to_string_lossy(),),
-        )?;
+        let session = Session::from_config(
+            &config,
+            Some(&effective_variables),
+            &self.search_path,
+            &self.secrets,
+        )
+        .wrap_err(format!(
+            "Could not load session from '{}'",
+            config.to_string_lossy(),
+        ))?;
+
+        // Configs in different folders may use the same session name
+        if let Some(running_config) = self.multiplexer.get_session_config_path(&session.name)?
+            && !same_file(Path::new(&running_config), &config)
+        {
+            bail!(
+                "Session '{}' is already running from '{}', give '{}' another name",
+                session.name,
+                running_config,
+                config.display()
+            );
+        }
 
         // Check if session with the final name (after variable substitution) already exists
-        if self.multiplexer.switch(&session.name, skip_attach)? {
+        if self.switch_to(&session.name, skip_attach)? {
             return Ok(());
         }
 
@@ -200,8 +288,95 @@ impl SessionManager {
         let env_vars: Vec<(&str, &str)> =
             vec![(LAIO_CONFIG, config_path), (LAIO_VARS, &encoded_vars)];
 
-        self.multiplexer
-            .start(&session, &env_vars, skip_attach, skip_cmds)
+        // Recorded up front, attaching only returns once the client detaches, and
+        // dropped again if the session fails to start
+        self.record_sessions(
+            vec![RunningSession {
+                name: session.name.clone(),
+                config: config_path.to_string(),
+                variables: decode_variables(&encoded_vars)?,
+            }],
+            &[],
+        );
+        if !skip_attach {
+            self.record_use(&session.name);
+        }
+        let result = self
+            .multiplexer
+            .start(&session, &env_vars, skip_attach, skip_cmds);
+        if result.is_err() {
+            self.record_sessions(vec![], std::slice::from_ref(&session.name));
+        }
+        result
+    }
+
+    /// Switches to session `name`, or with `-` to the running session used before the
+    /// current one.
+    pub(crate) fn switch(&self, name: &str) -> Result<()> {
+        let name = match name {
+            "-" => self.previous_session()?,
+            name => name.to_string(),
+        };
+        if !self.switch_to(&name, false)? {
+            bail!("Session '{name}' is not running");
+        }
+        Ok(())
+    }
+
+    /// The running session used most recently, other than the current one.
+    fn previous_session(&self) -> Result<String> {
+        let current = self.multiplexer.current_session_name()?;
+        let running: Vec<String> = self.list()?.into_iter().map(|info| info.name).collect();
+        recent::load(&self.state_path)?
+            .into_iter()
+            .map(|session| session.name)
+            .find(|name| current.as_ref() != Some(name) && running.contains(name))
+            .ok_or_else(|| miette!("No previously used session to switch to"))
+    }
+
+    /// Switches to session `name` if it's running, recording its use unless
+    /// `skip_attach` is set. Returns whether the session is running.
+    fn switch_to(&self, name: &str, skip_attach: bool) -> Result<bool> {
+        let running = self.multiplexer.switch(name, skip_attach)?;
+        if running && !skip_attach {
+            self.record_use(name);
+        }
+        Ok(running)
+    }
+
+    /// Records session `name` as the most recently used one, after the session laio
+    /// runs in, which becomes the previous one. Failing to record it doesn't fail the
+    /// command.
+    fn record_use(&self, name: &str) {
+        if !self.record_sessions {
+            return;
+        }
+
+        let result = (|| -> Result<()> {
+            let current = self.multiplexer.current_session_name()?;
+            let names: Vec<&str> = current.iter().map(String::as_str).chain([name]).collect();
+            recent::touch(&self.state_path, &names)
+        })();
+
+        if let Err(e) = result {
+            log::warn!("Could not record the use of '{name}': {e:?}");
+        }
+    }
+
+    /// Orders `sessions` by `sort`, by name they are expected in the order of
+    /// [`SessionInfo::cmp_grouped`] already.
+    pub(crate) fn sort(&self, sessions: &mut [SessionInfo], sort: SessionSort) {
+        if let SessionSort::Recent = sort {
+            let recent: Vec<String> = recent::load(&self.state_path)
+                .unwrap_or_else(|e| {
+                    log::warn!("Could not read the recently used sessions: {e:?}");
+                    vec![]
+                })
+                .into_iter()
+                .map(|session| session.name)
+                .collect();
+            SessionInfo::sort_recent(sessions, &recent);
+        }
     }
 
     pub(crate) fn stop(
@@ -219,52 +394,18 @@ impl SessionManager {
                 // Determine effective variables: use provided variables or retrieve from session
                 let effective_variables = if variables.is_empty() {
                     // No variables provided, try to retrieve from session environment
-                    match self.multiplexer.get_session_variables(session_name)? {
-                        Some(stored_vars) => {
-                            log::debug!(
-                                "Retrieved {} variables from session '{}' environment",
-                                stored_vars.len(),
-                                session_name
-                            );
-                            stored_vars
-                        }
-                        None => {
-                            // No stored variables, use defaults (session_name + path)
-                            let mut vars = Vec::new();
-                            vars.push(format!("session_name={}", session_name));
-                            let cwd = env::current_dir()
-                                .into_diagnostic()
-                                .wrap_err("Failed to get current directory")?;
-                            vars.push(format!("path={}", cwd.display()));
-                            vars
-                        }
-                    }
+                    self.stored_variables(session_name)?
                 } else {
                     // User provided variables, use them
-                    let mut effective_variables: Vec<String> = variables
-                        .iter()
-                        .filter(|v| !v.starts_with("session_name="))
-                        .cloned()
-                        .collect();
-
-                    // ALWAYS auto-inject session_name variable
-                    effective_variables.push(format!("session_name={}", session_name));
-
-                    // Auto-inject path variable if not provided, defaulting to cwd
-                    if !effective_variables.iter().any(|v| v.starts_with("path=")) {
-                        let cwd = env::current_dir()
-                            .into_diagnostic()
-                            .wrap_err("Failed to get current directory")?;
-                        effective_variables.push(format!("path={}", cwd.display()));
-                    }
-
-                    effective_variables
+                    session_variables(session_name, variables)?
                 };
 
                 // Load and render the config
                 match Session::from_config(
                     &resolve_symlink(&to_absolute_path(&config_path)?)?,
                     Some(&effective_variables),
+                    &self.search_path,
+                    &self.secrets,
                 ) {
                     Ok(sess) => Some(sess),
                     Err(e) => {
@@ -285,9 +426,243 @@ impl SessionManager {
             None
         };
 
-        self.multiplexer
+        // Only the sessions this stops are dropped from the record
+        let running = self.running_names();
+        let result = self
+            .multiplexer
             .stop(name, &session, skip_cmds, stop_all, stop_other)
-            .wrap_err("Multiplexer failed to stop session(s)".to_string())
+            .wrap_err("Multiplexer failed to stop session(s)".to_string());
+        let still_running = self.running_names();
+        let stopped: Vec<String> = running
+            .into_iter()
+            .filter(|name| !still_running.contains(name))
+            .collect();
+        self.record_sessions(vec![], &stopped);
+        result
+    }
+
+    /// Restarts session `name` (defaults to the current session) with the config and
+    /// variables it was started with. `variables` replace stored ones of the same name.
+    pub(crate) fn restart(
+        &self,
+        name: &Option<String>,
+        variables: &[String],
+        skip_cmds: bool,
+        skip_attach: bool,
+    ) -> Result<()> {
+        let name = match name {
+            Some(name) => name.clone(),
+            None => self
+                .multiplexer
+                .current_session_name()?
+                .ok_or_else(|| miette!("Specify laio session you want to restart."))?,
+        };
+        let Some(config_path) = self.multiplexer.get_session_config_path(&name)? else {
+            bail!("Session '{name}' was not started by laio");
+        };
+        let config = resolve_symlink(&to_absolute_path(&config_path)?)?;
+
+        // The session keeps its name
+        let variables: Vec<String> = variables
+            .iter()
+            .filter(|v| variable_name(v) != Some("session_name"))
+            .cloned()
+            .collect();
+        let stored_variables = self.stored_variables(&name)?;
+        let effective_variables = replace_variables(&stored_variables, &variables);
+
+        // The shutdown hooks are those of the session as it was started
+        let running = match Session::from_config(
+            &config,
+            Some(&stored_variables),
+            &self.search_path,
+            &self.secrets,
+        ) {
+            Ok(running) => Some(running),
+            Err(e) => {
+                log::warn!("Could not load session '{name}' as it was started: {e:?}");
+                None
+            }
+        };
+        let session = Session::from_config(
+            &config,
+            Some(&effective_variables),
+            &self.search_path,
+            &self.secrets,
+        )
+        .wrap_err(format!(
+            "Could not load session from '{}'",
+            config.to_string_lossy()
+        ))?;
+        let config_path = config.to_string_lossy();
+        let encoded_vars = encode_variables(&effective_variables)?;
+
+        self.record_sessions(
+            vec![RunningSession {
+                name: name.clone(),
+                config: config_path.to_string(),
+                variables: decode_variables(&encoded_vars)?,
+            }],
+            &[],
+        );
+        if !skip_attach {
+            self.record_use(&name);
+        }
+        let result = self.multiplexer.restart(
+            &name,
+            &running,
+            &session,
+            &[(LAIO_CONFIG, &config_path), (LAIO_VARS, &encoded_vars)],
+            skip_attach,
+            skip_cmds,
+        );
+        if result.is_err() {
+            // The session put back in place, if any, is recorded as it runs
+            self.record_sessions(vec![], std::slice::from_ref(&name));
+        }
+        result.wrap_err(format!("Multiplexer failed to restart session '{name}'"))
+    }
+
+    /// Starts the sessions recorded as running that aren't, detached and with the
+    /// config and variables they were started with. Returns the result of each.
+    pub(crate) fn resurrect(&self, skip_cmds: bool) -> Result<Vec<(String, Result<()>)>> {
+        let recorded = running::load(&self.state_path)?;
+        let running: Vec<String> = self
+            .multiplexer
+            .list_sessions()?
+            .into_iter()
+            .map(|info| info.name)
+            .collect();
+
+        let results = recorded
+            .into_iter()
+            .filter(|recorded| !running.contains(&recorded.name))
+            .map(|recorded| {
+                let result = self.start_recorded(&recorded, skip_cmds);
+                if result.is_err() {
+                    // Don't leave a partly started session behind
+                    let _ = self.multiplexer.stop(
+                        &Some(recorded.name.clone()),
+                        &None,
+                        true,
+                        false,
+                        false,
+                    );
+                }
+                (recorded.name, result)
+            })
+            .collect();
+
+        // Sessions that failed to start stay recorded to try again
+        self.record_sessions(vec![], &[]);
+        Ok(results)
+    }
+
+    fn start_recorded(&self, recorded: &RunningSession, skip_cmds: bool) -> Result<()> {
+        let config = resolve_symlink(&to_absolute_path(&recorded.config)?)
+            .wrap_err(format!("Could not locate '{}'", recorded.config))?;
+        let session = Session::from_config(
+            &config,
+            Some(&recorded.variables),
+            &self.search_path,
+            &self.secrets,
+        )
+        .wrap_err(format!(
+            "Could not load session from '{}'",
+            config.to_string_lossy()
+        ))?;
+        let encoded_vars = encode_variables(&recorded.variables)?;
+
+        self.multiplexer.start(
+            &session,
+            &[(LAIO_CONFIG, &recorded.config), (LAIO_VARS, &encoded_vars)],
+            true,
+            skip_cmds,
+        )
+    }
+
+    /// Updates the record of running sessions: `started` sessions, such as one about
+    /// to be started, and the laio sessions that are running are added or replaced,
+    /// `stopped` ones removed. Recorded sessions that merely aren't running, such as
+    /// after a reboot, are kept for `laio resurrect`. Failing to record them doesn't
+    /// fail the command.
+    fn record_sessions(&self, started: Vec<RunningSession>, stopped: &[String]) {
+        if !self.record_sessions {
+            return;
+        }
+
+        let result = (|| -> Result<()> {
+            let mut current = vec![];
+            for info in self.multiplexer.list_sessions()? {
+                if started.iter().any(|s| s.name == info.name) {
+                    continue;
+                }
+                if let Some(config) = self.multiplexer.get_session_config_path(&info.name)? {
+                    let variables = self
+                        .multiplexer
+                        .get_session_variables(&info.name)?
+                        .unwrap_or_default();
+                    current.push(RunningSession {
+                        name: info.name,
+                        config,
+                        variables,
+                    });
+                }
+            }
+            current.extend(started);
+
+            let mut sessions = running::load(&self.state_path)?;
+            sessions.retain(|s| !stopped.contains(&s.name));
+            for session in current {
+                match sessions.iter_mut().find(|s| s.name == session.name) {
+                    Some(recorded) => *recorded = session,
+                    None => sessions.push(session),
+                }
+            }
+            running::save(&self.state_path, &sessions)
+        })();
+
+        if let Err(e) = result {
+            log::warn!("Could not record running sessions: {e:?}");
+        }
+    }
+
+    /// Names of the running sessions, when they are recorded.
+    fn running_names(&self) -> Vec<String> {
+        if !self.record_sessions {
+            return vec![];
+        }
+        match self.multiplexer.list_sessions() {
+            Ok(sessions) => sessions.into_iter().map(|info| info.name).collect(),
+            Err(e) => {
+                log::warn!("Could not list running sessions: {e:?}");
+                vec![]
+            }
+        }
+    }
+
+    /// Variables session `name` was started with, or the defaults if it has none
+    /// stored.
+    fn stored_variables(&self, name: &str) -> Result<Vec<String>> {
+        match self.multiplexer.get_session_variables(name)? {
+            Some(variables) => {
+                log::debug!(
+                    "Retrieved {} variables from session '{}' environment",
+                    variables.len(),
+                    name
+                );
+                Ok(variables)
+            }
+            None => {
+                let cwd = env::current_dir()
+                    .into_diagnostic()
+                    .wrap_err("Failed to get current directory")?;
+                Ok(vec![
+                    format!("session_name={name}"),
+                    format!("path={}", cwd.display()),
+                ])
+            }
+        }
     }
 
     pub(crate) fn list(&self) -> Result<Vec<SessionInfo>> {
@@ -296,87 +671,442 @@ impl SessionManager {
             .wrap_err("Multiplexer failed to list sessions.".to_string())
     }
 
-    pub(crate) fn to_yaml(&self) -> Result<String> {
+    /// Starts the sessions of group `name`, detached except for the one marked
+    /// `attach`, or else the last one. `variables` replace the variables of the
+    /// sessions with the same name.
+    pub(crate) fn start_group(
+        &self,
+        name: &str,
+        variables: &[String],
+        skip_cmds: bool,
+        skip_attach: bool,
+    ) -> Result<()> {
+        let group = Group::load(&self.search_path, name)?;
+        let members = group.start_order();
+        let last = members.len() - 1;
+
+        members.iter().enumerate().try_for_each(|(index, member)| {
+            self.start(
+                &Some(member.config.clone()),
+                &None,
+                &replace_variables(&member.variables, variables),
+                false,
+                skip_cmds,
+                skip_attach || index != last,
+            )
+            .wrap_err(format!(
+                "Could not start '{}' of group '{name}'",
+                member.config
+            ))
+        })
+    }
+
+    /// Stops the running sessions of group `name`, the one laio runs in last.
+    pub(crate) fn stop_group(&self, name: &str, skip_cmds: bool) -> Result<()> {
+        let group = Group::load(&self.search_path, name)?;
+        let running: Vec<String> = self.list()?.into_iter().map(|info| info.name).collect();
+        let current = self.multiplexer.current_session_name()?;
+
+        let mut sessions: Vec<String> = group
+            .sessions
+            .iter()
+            .map(|member| self.member_session_name(member))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|session| running.contains(session))
+            .collect();
+        sessions.sort_by_key(|session| current.as_ref() == Some(session));
+
+        let mut failed = vec![];
+        for session in sessions {
+            if let Err(e) = self.stop(&Some(session.clone()), &[], skip_cmds, false, false) {
+                log::error!("Could not stop '{session}': {e:?}");
+                failed.push(session);
+            }
+        }
+        if !failed.is_empty() {
+            bail!("Could not stop '{}' of group '{name}'", failed.join("', '"));
+        }
+        Ok(())
+    }
+
+    /// Sessions of group `name`, active if they are running.
+    pub(crate) fn list_group(&self, name: &str) -> Result<Vec<SessionInfo>> {
+        let group = Group::load(&self.search_path, name)?;
+        let running = self.list()?;
+
+        group
+            .sessions
+            .iter()
+            .map(|member| {
+                let session = self.member_session_name(member)?;
+                Ok(running
+                    .iter()
+                    .find(|info| info.name == session)
+                    .cloned()
+                    .unwrap_or(SessionInfo::inactive(session)))
+            })
+            .collect()
+    }
+
+    /// Groups defined in the config directories.
+    pub(crate) fn list_groups(&self) -> Result<Vec<Group>> {
+        Group::list(&self.search_path)
+    }
+
+    /// Name of the session of a group member, which may depend on its variables.
+    fn member_session_name(&self, member: &Member) -> Result<String> {
+        let (config, variables) =
+            self.resolve_config_and_variables(&member.config, &member.variables)?;
+        self.secrets
+            .without_resolving(|| {
+                Session::from_config(&config, Some(&variables), &self.search_path, &self.secrets)
+            })
+            .map(|session| session.name)
+            .wrap_err(format!(
+                "Could not load session from '{}'",
+                config.display()
+            ))
+    }
+
+    /// YAML of the current session. With `overlay`, the changes of the session are
+    /// overlaid onto the config it was started from instead.
+    pub(crate) fn to_yaml(&self, overlay: bool) -> Result<String> {
         let session = self
             .multiplexer
-            .get_session()
+            .get_session(None)
             .wrap_err("Unable to determine active session.")?;
-        let yaml = noyalib::compat::serde_yaml::to_string(&session)
-            .into_diagnostic()
-            .wrap_err("Multiplexer unable to generate yaml representation of current session.")?;
+        self.export(&session, overlay)
+    }
 
-        Ok(yaml)
+    fn export(&self, session: &Session, overlay: bool) -> Result<String> {
+        if overlay {
+            match self.overlay_yaml(session)? {
+                Some(yaml) => return Ok(yaml),
+                None => log::warn!(
+                    "Session '{}' was not started from a config, exporting it as is",
+                    session.name
+                ),
+            }
+        }
+        session_yaml(&self.masked(session), &self.secrets)
     }
 
-    pub(crate) fn select_config(
-        &self,
-        show_picker: bool,
-    ) -> Result<Option<(PathBuf, Option<String>)>> {
-        fn picker(
-            config_path: &str,
-            sessions: &[SessionInfo],
-        ) -> Result<Option<(PathBuf, Option<String>)>> {
-            let configs = fs::read_dir(config_path)
+    /// `session` with the environment variables its config sets from a secret
+    /// masked, see [`Secrets::mask_env`]. Their values are masked in any output
+    /// from then on.
+    fn masked(&self, session: &Session) -> Session {
+        let mut session = session.clone();
+        match self.session_config(&session.name) {
+            Ok(Some((_, original))) => self.secrets.mask_env(&mut session.env, &original.env),
+            Ok(None) => {}
+            Err(e) => log::warn!(
+                "Could not tell the secrets of session '{}': {e:?}",
+                session.name
+            ),
+        }
+        session
+    }
+
+    /// Overlays the session onto its originating config, if it has one. The config
+    /// is returned unchanged, templates and all, if the session still matches it.
+    ///
+    /// The overlay is rendered with secrets masked, so a changed session of a config
+    /// using secrets can't be overlaid without losing them.
+    fn overlay_yaml(&self, live: &Session) -> Result<Option<String>> {
+        let Some((config, original)) = self.session_config(&live.name)? else {
+            return Ok(None);
+        };
+        let merged = overlay(&original, &original.absolute_path(&config)?, live);
+
+        let yaml = session_yaml(&merged, &self.secrets)?;
+        if yaml == session_yaml(&original, &self.secrets)? {
+            log::info!("Session '{}' matches '{}'", live.name, config.display());
+            return fs::read_to_string(&config)
                 .into_diagnostic()
+                .wrap_err(format!("Could not read '{}'", config.display()))
+                .map(Some);
+        }
+        if yaml.contains(secrets::MASK) {
+            bail!(
+                "Session '{}' uses secrets of '{}' that an overlay would replace with '{}', edit the config instead",
+                live.name,
+                config.display(),
+                secrets::MASK
+            );
+        }
+        Ok(Some(yaml))
+    }
+
+    /// The config session `name` was started from, rendered with the variables it
+    /// was started with, if it was started from a config.
+    fn session_config(&self, name: &str) -> Result<Option<(PathBuf, Session)>> {
+        let Some(config_path) = self.multiplexer.get_session_config_path(name)? else {
+            return Ok(None);
+        };
+        let config = resolve_symlink(&to_absolute_path(&config_path)?)?;
+
+        let variables = self.stored_variables(name)?;
+
+        // Withheld secrets are masked rather than prompted for, they are never shown
+        let original = self
+            .secrets
+            .without_resolving(|| {
+                Session::render(&config, Some(&variables), &self.search_path, &self.secrets)
+            })
+            .wrap_err(format!(
+                "Could not load session from '{}'",
+                config.to_string_lossy()
+            ))?;
+        Ok(Some((config, original)))
+    }
+
+    /// Compares session `name` (defaults to the current session) with the config it
+    /// was started from.
+    pub(crate) fn diff(&self, name: &Option<String>) -> Result<SessionDiff> {
+        let live = self
+            .multiplexer
+            .get_session(name.as_deref())
+            .wrap_err("Unable to determine active session.")?;
+        let Some((config, original)) = self.session_config(&live.name)? else {
+            bail!("Session '{}' was not started from a config", live.name);
+        };
+
+        let windows = diff(&original, &original.absolute_path(&config)?, &live);
+        Ok(SessionDiff::new(&live.name, &config, windows))
+    }
+
+    /// Adds the windows and panes of its config missing in session `name` (defaults
+    /// to the current session), see [`Multiplexer::apply`]. Returns warnings about
+    /// layouts that couldn't be applied.
+    pub(crate) fn apply(
+        &self,
+        name: &Option<String>,
+        prune: bool,
+        skip_cmds: bool,
+    ) -> Result<Vec<String>> {
+        let live = self
+            .multiplexer
+            .get_session(name.as_deref())
+            .wrap_err("Unable to determine active session.")?;
+        let session = self.started_session(&live.name)?;
+
+        self.multiplexer.apply(&session, prune, skip_cmds)
+    }
+
+    /// Kills `window` of session `name` (defaults to the current session) and
+    /// recreates it from the config, or only its pane named `pane`.
+    pub(crate) fn respawn(
+        &self,
+        name: &Option<String>,
+        window: &str,
+        pane: &Option<String>,
+        skip_cmds: bool,
+    ) -> Result<()> {
+        let name = match name {
+            Some(name) => name.clone(),
+            None => self
+                .multiplexer
+                .current_session_name()?
+                .ok_or_else(|| miette!("Specify the session of window '{window}'."))?,
+        };
+        let session = self.started_session(&name)?;
+
+        self.multiplexer
+            .respawn(&session, window, pane.as_deref(), skip_cmds)
+            .wrap_err(format!("Multiplexer failed to respawn window '{window}'"))
+    }
+
+    /// The config session `name` was started from, rendered with its stored
+    /// variables to run its commands again. The session keeps its name.
+    fn started_session(&self, name: &str) -> Result<Session> {
+        let Some(config_path) = self.multiplexer.get_session_config_path(name)? else {
+            bail!("Session '{name}' was not started from a config");
+        };
+        let config = resolve_symlink(&to_absolute_path(&config_path)?)?;
+        log::info!("Using '{}' for session '{name}'", config.display());
+
+        let variables = self.stored_variables(name)?;
+        let session =
+            Session::from_config(&config, Some(&variables), &self.search_path, &self.secrets)
                 .wrap_err(format!(
-                    "Failed to list config entries in '{}'",
-                    &config_path
-                ))?
-                .filter_map(|entry| entry.ok())
-                .map(|entry| entry.path())
-                .filter(|path| {
-                    // Filter out _default.yaml and only include .yaml files
-                    path.extension().and_then(|ext| ext.to_str()) == Some("yaml")
-                        && path.file_name().and_then(|n| n.to_str()) != Some("_default.yaml")
-                })
-                .map(|path| {
-                    Session::from_config(&path, None)
-                        .map(|session| session.name)
-                        .wrap_err(format!("Warning: Failed to parse '{}'", path.display()))
-                })
-                .collect::<Result<Vec<String>, _>>()?;
+                    "Could not load session from '{}'",
+                    config.to_string_lossy()
+                ))?;
+        Ok(Session {
+            name: name.to_string(),
+            ..session
+        })
+    }
+
+    /// Saves the layout and pane content of session `name` (defaults to the current
+    /// session) in the state directory, replacing its previous snapshot. Returns the
+    /// directory of the snapshot.
+    pub(crate) fn snapshot(&self, name: &Option<String>) -> Result<PathBuf> {
+        if !self.multiplexer.supports_snapshots() {
+            bail!("Snapshots are only supported with tmux");
+        }
+        let session = self
+            .multiplexer
+            .get_session(name.as_deref())
+            .wrap_err("Unable to determine active session.")?;
+        let panes = self
+            .multiplexer
+            .capture_panes(&session.name)
+            .wrap_err(format!("Could not capture the panes of '{}'", session.name))?;
 
-            let session_names: Vec<String> = sessions.iter().map(|s| s.name.clone()).collect();
+        let dir = snapshot_dir(&self.state_path, &session.name)?;
+        let snapshot = Snapshot {
+            config: self.multiplexer.get_session_config_path(&session.name)?,
+            variables: self
+                .multiplexer
+                .get_session_variables(&session.name)?
+                .unwrap_or_default(),
+            session: self.masked(&session),
+        };
+        snapshot.save(&dir, &panes, &self.secrets)?;
+        Ok(dir)
+    }
 
-            let mut merged: Vec<SessionInfo> = sessions.to_vec();
-            merged.extend(
-                configs
-                    .iter()
-                    .filter(|s| !session_names.contains(s))
-                    .map(|s| SessionInfo::inactive(s.to_string())),
-            );
+    /// Starts the session of snapshot `name` with the content of its panes printed
+    /// into them. A session started from a config is linked to it again.
+    pub(crate) fn restore(&self, name: &str, skip_attach: bool, skip_cmds: bool) -> Result<()> {
+        let dir = snapshot_dir(&self.state_path, name)?;
+        let snapshot = Snapshot::load(&dir)?;
+        let config = snapshot.config.clone();
+        let encoded_vars = encode_variables(&snapshot.variables)?;
+        let session = snapshot.into_session(&dir, skip_cmds)?;
 
-            merged.sort_by(|a, b| a.name.cmp(&b.name));
-            merged.dedup_by(|a, b| a.name == b.name);
-
-            let selected = Select::new("Select configuration:", merged)
-                .with_page_size(12)
-                .prompt();
-
-            match selected {
-                Ok(info) => {
-                    let path =
-                        PathBuf::from(format!("{}/{}.yaml", &config_path, info.name.sanitize()));
-                    // Return session name if it's active
-                    let active_session = if info.is_active() {
-                        Some(info.name.clone())
-                    } else {
-                        None
-                    };
-                    Ok(Some((path, active_session)))
-                }
-                Err(_) => Ok(None),
+        let env_vars: Vec<(&str, &str)> = match &config {
+            Some(config) => {
+                self.record_sessions(
+                    vec![RunningSession {
+                        name: session.name.clone(),
+                        config: config.clone(),
+                        variables: decode_variables(&encoded_vars)?,
+                    }],
+                    &[],
+                );
+                vec![(LAIO_CONFIG, config), (LAIO_VARS, &encoded_vars)]
             }
+            None => vec![],
+        };
+        if !skip_attach {
+            self.record_use(&session.name);
+        }
+        let result = self
+            .multiplexer
+            .start(&session, &env_vars, skip_attach, false);
+        if result.is_err() {
+            self.record_sessions(vec![], std::slice::from_ref(&session.name));
         }
+        result.wrap_err(format!("Could not restore session '{}'", session.name))
+    }
+
+    /// Saves the current session as config `name` (defaults to the session name),
+    /// or as `.laio.yaml` in the current directory if `local` is set.
+    ///
+    /// A session not started by laio is pointed at the saved config, so it becomes
+    /// managed by laio from then on. With `overlay`, see [`Self::to_yaml`]. Returns
+    /// the path of the saved config.
+    pub(crate) fn save(
+        &self,
+        name: &Option<String>,
+        local: bool,
+        force: bool,
+        overlay: bool,
+    ) -> Result<PathBuf> {
+        let session = self
+            .multiplexer
+            .get_session(None)
+            .wrap_err("Unable to determine active session.")?;
+
+        let config_file = if local {
+            to_absolute_path(LOCAL_CONFIG)?
+        } else {
+            let name = name.as_deref().unwrap_or(&session.name).sanitize();
+            if name.is_empty() {
+                bail!("Invalid config name for session '{}'", session.name);
+            }
+            PathBuf::from(&self.config_path).join(format!("{name}.yaml"))
+        };
+
+        if config_file.exists() && !force {
+            bail!(
+                "'{}' already exists, use --force to overwrite it",
+                config_file.display()
+            );
+        }
+
+        if let Some(parent) = config_file.parent() {
+            fs::create_dir_all(parent)
+                .into_diagnostic()
+                .wrap_err(format!("Could not create '{}'", parent.display()))?;
+        }
+        fs::write(&config_file, self.export(&session, overlay)?)
+            .into_diagnostic()
+            .wrap_err(format!("Could not write '{}'", config_file.display()))?;
+
+        if self.multiplexer.get_session_config_path(&session.name)?.is_none()
+            && let Err(e) = self
+                .multiplexer
+                .set_session_config_path(&session.name, &config_file.to_string_lossy())
+        {
+            log::warn!(
+                "Session '{}' saved, but could not be marked as laio managed: {:?}",
+                session.name,
+                e
+            );
+        }
+
+        Ok(config_file)
+    }
+
+    pub(crate) fn select_config(
+        &self,
+        show_picker: bool,
+    ) -> Result<Option<(PathBuf, Option<String>)>> {
+        let search_path = &self.search_path;
+        let config_path = |info: &SessionInfo| match &info.config_path {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let file_name = format!("{}.yaml", info.name.sanitize());
+                find_in_search_path(search_path, &file_name)
+                    .unwrap_or_else(|| PathBuf::from(&search_path[0]).join(file_name))
+            }
+        };
+        // Running sessions show what their panes run now, the others their config
+        let load = |info: &SessionInfo| {
+            if info.is_active()
+                && let Ok(session) = self.multiplexer.get_session(Some(info.session_name()))
+            {
+                return Ok(session);
+            }
+            self.secrets.without_resolving(|| {
+                Session::from_config(&config_path(info), None, &self.search_path, &self.secrets)
+            })
+        };
+
+        let picker = |sessions: Vec<SessionInfo>| -> Result<Option<(PathBuf, Option<String>)>> {
+            let configs = list_configs(search_path)?;
+            let mut merged = SessionInfo::merge(sessions, configs);
+            self.sort(&mut merged, self.picker_sort);
+
+            Ok(pick(merged, load)?.map(|info| {
+                // Return session name if it's active
+                let active_session = info.is_active().then(|| info.session_name().to_string());
+                (config_path(&info), active_session)
+            }))
+        };
 
         if show_picker {
-            picker(&self.config_path, &self.list()?)
+            picker(self.list()?)
         } else {
             match find_config(&to_absolute_path(LOCAL_CONFIG)?) {
                 Ok(config) => Ok(Some((config, None))),
                 Err(err) => {
                     log::debug!("{err}");
-                    picker(&self.config_path, &self.list()?)
+                    picker(self.list()?)
                 }
             }
         }
@@ -1,6 +1,13 @@
+pub(crate) mod group;
 pub(crate) mod manager;
+pub(crate) mod picker;
+pub(crate) mod recent;
+pub(crate) mod running;
+pub(crate) mod snapshot;
 
 pub(crate) use manager::SessionManager;
 
+#[cfg(test)]
+mod picker_test;
 #[cfg(test)]
 mod test;
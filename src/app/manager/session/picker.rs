@@ -0,0 +1,483 @@
+//! Fuzzy picker for `laio start`, with a preview of the selected session's windows
+//! and a sketch of their pane layout.
+//!
+//! Laid out to fit the popup `prefix M-l` opens: a filter line on top, the sessions
+//! on the left and the preview on the right.
+
+use std::{collections::HashMap, io::Write};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Print, Stylize},
+    terminal,
+};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use miette::{IntoDiagnostic, Result};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::common::{
+    config::{FlexDirection, Pane, Session, Window},
+    session_info::SessionInfo,
+};
+
+/// Narrower terminals show the list without the preview.
+const MIN_PREVIEW_WIDTH: usize = 40;
+/// Rows a window takes in the preview at most, its name included.
+const MAX_WINDOW_ROWS: usize = 7;
+/// Rows a window needs to be sketched, its name included.
+const MIN_WINDOW_ROWS: usize = 4;
+
+/// Indices of `items` matching `query`, best match first. Without a query all items
+/// are kept in their order.
+pub(crate) fn filter(items: &[SessionInfo], query: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return (0..items.len()).collect();
+    }
+    let matcher = SkimMatcherV2::default();
+    let mut scored: Vec<(i64, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            matcher
+                .fuzzy_match(&item.to_string(), query)
+                .map(|score| (score, i))
+        })
+        .collect();
+    // Stable, so equal matches keep the order the sessions are sorted in
+    scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Box-drawn sketch of the pane layout of `window`, `width` by `height` characters.
+/// Panes are labelled with their first command, or their name.
+pub(crate) fn sketch(window: &Window, width: usize, height: usize) -> Vec<String> {
+    if width < 3 || height < 3 {
+        return vec![];
+    }
+    let mut canvas = Canvas::new(width, height);
+    canvas.rect(0, 0, width - 1, height - 1);
+    canvas.split(
+        &window.panes,
+        &window.flex_direction,
+        (0, 0, width - 1, height - 1),
+    );
+    canvas.lines()
+}
+
+/// Lines previewing `session`, at most `width` by `height` characters.
+pub(crate) fn preview(
+    info: &SessionInfo,
+    session: &Result<Session>,
+    width: usize,
+    height: usize,
+) -> Vec<String> {
+    let mut lines = vec![];
+    if let Some(description) = &info.description {
+        lines.push(truncate(description, width));
+    }
+    if !info.tags.is_empty() {
+        let tags: Vec<String> = info.tags.iter().map(|tag| format!("#{tag}")).collect();
+        lines.push(truncate(&tags.join(" "), width));
+    }
+
+    let session = match session {
+        Ok(session) => session,
+        Err(e) => {
+            let error = e.to_string();
+            lines.push(truncate("No preview:", width));
+            lines.push(truncate(error.lines().next().unwrap_or_default(), width));
+            lines.truncate(height);
+            return lines;
+        }
+    };
+
+    let windows = &session.windows;
+    let rows = height.saturating_sub(lines.len());
+    let per_window = (rows / windows.len().max(1)).clamp(MIN_WINDOW_ROWS, MAX_WINDOW_ROWS);
+    let mut sketched = 0;
+    for (i, window) in windows.iter().enumerate() {
+        let left = height.saturating_sub(lines.len());
+        if left < MIN_WINDOW_ROWS {
+            break;
+        }
+        lines.push(truncate(&format!("{} {}", i + 1, window.name), width));
+        lines.extend(sketch(window, width, per_window.min(left) - 1));
+        sketched += 1;
+    }
+
+    // Windows without room for a sketch are only named
+    let left = height.saturating_sub(lines.len());
+    let unsketched = windows.len() - sketched;
+    let named = if unsketched > left {
+        left.saturating_sub(1)
+    } else {
+        unsketched
+    };
+    for (i, window) in windows.iter().enumerate().skip(sketched).take(named) {
+        lines.push(truncate(&format!("{} {}", i + 1, window.name), width));
+    }
+    if named < unsketched {
+        lines.push(truncate(&format!("… {} more", unsketched - named), width));
+    }
+    lines.truncate(height);
+    lines
+}
+
+/// The picker's state: the filter typed so far and the highlighted match.
+pub(crate) struct Picker {
+    items: Vec<SessionInfo>,
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+    offset: usize,
+}
+
+impl Picker {
+    pub(crate) fn new(items: Vec<SessionInfo>) -> Self {
+        let matches = filter(&items, "");
+        Self {
+            items,
+            query: String::new(),
+            matches,
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    /// The highlighted item, if anything matches.
+    pub(crate) fn current(&self) -> Option<&SessionInfo> {
+        self.matches.get(self.selected).map(|&i| &self.items[i])
+    }
+
+    fn set_query(&mut self, query: String) {
+        self.matches = filter(&self.items, &query);
+        self.query = query;
+        self.selected = 0;
+        self.offset = 0;
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+    }
+
+    /// Handles a key press, returning whether the picker is done: `Some(true)` to
+    /// take the current item, `Some(false)` to cancel.
+    pub(crate) fn handle(&mut self, key: KeyEvent) -> Option<bool> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return Some(self.current().is_some()),
+            KeyCode::Esc => return Some(false),
+            KeyCode::Char('c') if ctrl => return Some(false),
+            KeyCode::Up | KeyCode::BackTab => self.move_by(-1),
+            KeyCode::Char('p' | 'k') if ctrl => self.move_by(-1),
+            KeyCode::Down | KeyCode::Tab => self.move_by(1),
+            KeyCode::Char('n' | 'j') if ctrl => self.move_by(1),
+            KeyCode::Char('u') if ctrl => self.set_query(String::new()),
+            KeyCode::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(query);
+            }
+            KeyCode::Char(c) if !ctrl => self.set_query(format!("{}{c}", self.query)),
+            _ => {}
+        }
+        None
+    }
+
+    /// The lines of a `width` by `height` terminal showing the picker next to
+    /// `preview`, and the line highlighted, if anything matches.
+    pub(crate) fn frame(
+        &mut self,
+        width: usize,
+        height: usize,
+        preview: &[String],
+    ) -> (Vec<String>, Option<usize>) {
+        let rows = height.saturating_sub(1);
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if rows > 0 && self.selected >= self.offset + rows {
+            self.offset = self.selected + 1 - rows;
+        }
+
+        let list_width = list_width(width);
+        let count = format!(" {}/{}", self.matches.len(), self.items.len());
+        let prompt_width = width.saturating_sub(count.len());
+        let prompt = truncate(&format!("> {}", self.query), prompt_width);
+        let mut lines = vec![truncate(
+            &format!("{}{count}", pad(&prompt, prompt_width)),
+            width,
+        )];
+
+        for row in 0..rows {
+            let entry = self
+                .matches
+                .get(self.offset + row)
+                .map(|&i| {
+                    let item = &self.items[i];
+                    format!("{} {}", item.status.icon(), item.name)
+                })
+                .unwrap_or_default();
+            let mut line = pad(&truncate(&entry, list_width), list_width);
+            if list_width < width {
+                line.push('│');
+                let preview_width = width - list_width - 1;
+                let text = preview.get(row).map(String::as_str).unwrap_or_default();
+                line.push_str(&pad(&truncate(text, preview_width), preview_width));
+            }
+            lines.push(line);
+        }
+
+        let highlighted = (!self.matches.is_empty()).then(|| self.selected - self.offset + 1);
+        (lines, highlighted)
+    }
+}
+
+/// Width of the list in a `width` wide terminal, the preview takes the rest after a
+/// separator.
+fn list_width(width: usize) -> usize {
+    if width >= MIN_PREVIEW_WIDTH {
+        (width * 2 / 5).min(30)
+    } else {
+        width
+    }
+}
+
+/// Shows the picker over `items` until one is picked or it's cancelled. `load`
+/// returns the session previewed for an item, it's called once per item.
+pub(crate) fn pick(
+    items: Vec<SessionInfo>,
+    load: impl Fn(&SessionInfo) -> Result<Session>,
+) -> Result<Option<SessionInfo>> {
+    let mut picker = Picker::new(items);
+    let mut sessions: HashMap<String, Result<Session>> = HashMap::new();
+    let mut stderr = std::io::stderr();
+
+    terminal::enable_raw_mode().into_diagnostic()?;
+    let _guard = RawMode;
+    queue!(stderr, terminal::EnterAlternateScreen, cursor::Hide).into_diagnostic()?;
+
+    loop {
+        let (width, height) = terminal::size().into_diagnostic()?;
+        let (width, height) = (width as usize, height as usize);
+
+        let list_width = list_width(width);
+        let preview_width = width.saturating_sub(list_width + 1);
+        let lines = match picker.current() {
+            Some(info) if preview_width > 0 => {
+                let session = sessions
+                    .entry(info.name.clone())
+                    .or_insert_with(|| load(info));
+                preview(info, session, preview_width, height.saturating_sub(1))
+            }
+            _ => vec![],
+        };
+
+        let (frame, highlighted) = picker.frame(width, height, &lines);
+        for (row, line) in frame.iter().enumerate() {
+            queue!(stderr, cursor::MoveTo(0, row as u16)).into_diagnostic()?;
+            if Some(row) == highlighted {
+                let (list, rest) = line.split_at(byte_at_width(line, list_width));
+                queue!(stderr, Print(list.reverse()), Print(rest)).into_diagnostic()?;
+            } else {
+                queue!(stderr, Print(line)).into_diagnostic()?;
+            }
+        }
+        stderr.flush().into_diagnostic()?;
+
+        if let Event::Key(key) = event::read().into_diagnostic()?
+            && key.kind != KeyEventKind::Release
+            && let Some(done) = picker.handle(key)
+        {
+            return Ok(if done {
+                picker.current().cloned()
+            } else {
+                None
+            });
+        }
+    }
+}
+
+/// Restores the terminal when the picker is done, whichever way it ends.
+struct RawMode;
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(
+            std::io::stderr(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Grid the layout sketch is drawn on: walls first, turned into box-drawing
+/// characters once all of them are known, then the labels.
+struct Canvas {
+    width: usize,
+    walls: Vec<Vec<bool>>,
+    labels: Vec<(usize, usize, usize, String)>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            walls: vec![vec![false; width]; height],
+            labels: vec![],
+        }
+    }
+
+    fn rect(&mut self, x0: usize, y0: usize, x1: usize, y1: usize) {
+        for x in x0..=x1 {
+            self.walls[y0][x] = true;
+            self.walls[y1][x] = true;
+        }
+        for row in &mut self.walls[y0..=y1] {
+            row[x0] = true;
+            row[x1] = true;
+        }
+    }
+
+    /// Splits the box `area` between `panes` along `direction`, by their flex.
+    fn split(
+        &mut self,
+        panes: &[Pane],
+        direction: &FlexDirection,
+        area: (usize, usize, usize, usize),
+    ) {
+        let (x0, y0, x1, y1) = area;
+        if panes.is_empty() {
+            return;
+        }
+        if panes.len() == 1 {
+            return self.pane(&panes[0], area);
+        }
+
+        let row = *direction == FlexDirection::Row;
+        let (start, end) = if row { (x0, x1) } else { (y0, y1) };
+        if end - start < 2 * panes.len() {
+            // Too small to tell the panes apart, show the first one
+            return self.pane(&panes[0], area);
+        }
+        let total: usize = panes.iter().map(|pane| pane.flex.max(1)).sum();
+        let mut flex = 0;
+        let mut from = start;
+        for pane in panes {
+            flex += pane.flex.max(1);
+            let to = start + ((end - start) * flex + total / 2) / total;
+            let to = to.clamp(from, end);
+            if row {
+                self.rect(from, y0, to, y1);
+                self.pane(pane, (from, y0, to, y1));
+            } else {
+                self.rect(x0, from, x1, to);
+                self.pane(pane, (x0, from, x1, to));
+            }
+            from = to;
+        }
+    }
+
+    fn pane(&mut self, pane: &Pane, area: (usize, usize, usize, usize)) {
+        if !pane.panes.is_empty() {
+            return self.split(&pane.panes, &pane.flex_direction, area);
+        }
+        let (x0, y0, x1, y1) = area;
+        let label = pane
+            .commands
+            .first()
+            .map(|command| command.to_string())
+            .or(pane.name.clone())
+            .unwrap_or_default();
+        if x1 > x0 + 1 && y1 > y0 + 1 && !label.is_empty() {
+            self.labels.push((x0 + 1, y0 + 1, x1 - x0 - 1, label));
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let wall = |x: usize, y: usize| {
+            self.walls
+                .get(y)
+                .and_then(|row| row.get(x))
+                .copied()
+                .unwrap_or_default()
+        };
+        let mut grid: Vec<Vec<String>> = (0..self.walls.len())
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        if !wall(x, y) {
+                            return " ".to_string();
+                        }
+                        let up = y > 0 && wall(x, y - 1);
+                        let down = wall(x, y + 1);
+                        let left = x > 0 && wall(x - 1, y);
+                        let right = wall(x + 1, y);
+                        match (up, down, left, right) {
+                            (true, true, true, true) => "┼",
+                            (true, true, true, false) => "┤",
+                            (true, true, false, true) => "├",
+                            (true, false, true, true) => "┴",
+                            (false, true, true, true) => "┬",
+                            (false, true, false, true) => "┌",
+                            (false, true, true, false) => "┐",
+                            (true, false, false, true) => "└",
+                            (true, false, true, false) => "┘",
+                            (true, _, false, false) | (_, true, false, false) => "│",
+                            _ => "─",
+                        }
+                        .to_string()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // A label takes the first cell of its pane, padded over the ones it covers
+        for (x, y, width, label) in &self.labels {
+            grid[*y][*x] = pad(&truncate(label, *width), *width);
+            for cell in &mut grid[*y][x + 1..x + width] {
+                cell.clear();
+            }
+        }
+        grid.into_iter().map(|row| row.concat()).collect()
+    }
+}
+
+/// `text` cut to `width` terminal columns, ending in `…` when cut. Control
+/// characters are dropped, they'd move the cursor.
+fn truncate(text: &str, width: usize) -> String {
+    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+    if text.width() <= width {
+        return text;
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut cut = text[..byte_at_width(&text, width - 1)].to_string();
+    cut.push('…');
+    cut
+}
+
+/// `text` padded with spaces to `width` terminal columns.
+fn pad(text: &str, width: usize) -> String {
+    format!("{text}{}", " ".repeat(width.saturating_sub(text.width())))
+}
+
+/// Byte offset of the end of the characters of `text` that fit in `width` terminal
+/// columns.
+fn byte_at_width(text: &str, width: usize) -> usize {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        used += c.width().unwrap_or_default();
+        if used > width {
+            return i;
+        }
+    }
+    text.len()
+}
@@ -0,0 +1,139 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use miette::miette;
+use unicode_width::UnicodeWidthStr;
+
+use crate::common::{
+    config::{Session, Window},
+    session_info::SessionInfo,
+};
+
+use super::picker::{filter, preview, sketch, Picker};
+
+fn window(yaml: &str) -> Window {
+    noyalib::compat::serde_yaml::from_str(yaml).unwrap()
+}
+
+fn items() -> Vec<SessionInfo> {
+    vec![
+        SessionInfo::inactive("api".to_string()),
+        SessionInfo::active("web-frontend".to_string(), false),
+        SessionInfo::inactive("backend".to_string())
+            .with_description(Some("Services".to_string()), vec!["work".to_string()]),
+    ]
+}
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+#[test]
+fn picker_sketch_nested_layout() {
+    let window = window(
+        r#"
+name: code
+flex_direction: row
+panes:
+  - commands:
+      - command: nvim
+  - flex_direction: column
+    panes:
+      - name: b
+      - name: c
+"#,
+    );
+
+    assert_eq!(
+        sketch(&window, 11, 5),
+        vec![
+            "┌────┬────┐",
+            "│nvim│b   │",
+            "│    ├────┤",
+            "│    │c   │",
+            "└────┴────┘",
+        ]
+    );
+    assert_eq!(
+        sketch(&window, 11, 3),
+        vec!["┌────┬────┐", "│nvim│b   │", "└────┴────┘"]
+    );
+    assert!(sketch(&window, 2, 5).is_empty());
+}
+
+#[test]
+fn picker_wide_characters_keep_layout() {
+    let window = window(
+        r#"
+name: code
+flex_direction: row
+panes:
+  - name: 日本語の説明
+  - name: b
+"#,
+    );
+    let lines = sketch(&window, 11, 3);
+    assert_eq!(lines[1], "│日… │b   │");
+    assert!(lines.iter().all(|line| line.width() == 11));
+
+    let mut picker = Picker::new(vec![SessionInfo::inactive(
+        "プロジェクト-フロントエンド".to_string(),
+    )
+    .with_description(Some("説明".repeat(20)), vec![])]);
+    let info = picker.current().unwrap().clone();
+    let preview = preview(&info, &Err(miette!("エラー")), 28, 13);
+    let (frame, _) = picker.frame(48, 14, &preview);
+    assert!(frame.iter().all(|line| line.width() == 48));
+    assert!(frame[1].starts_with("· プロジェクト-フ… │説明"));
+}
+
+#[test]
+fn picker_filter_fuzzy() {
+    let items = items();
+
+    assert_eq!(filter(&items, ""), vec![0, 1, 2]);
+    assert_eq!(filter(&items, "wfr"), vec![1]);
+    assert_eq!(filter(&items, "#work"), vec![2]);
+    assert!(filter(&items, "zzz").is_empty());
+}
+
+#[test]
+fn picker_preview_names_windows_without_room() {
+    let info = SessionInfo::inactive("many".to_string());
+    let windows: String = (1..=6).map(|i| format!("\n  - name: w{i}")).collect();
+    let session: Session =
+        noyalib::compat::serde_yaml::from_str(&format!("name: many\nwindows:{windows}")).unwrap();
+
+    let lines = preview(&info, &Ok(session), 20, 10);
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[0], "1 w1");
+    assert_eq!(lines[4], "2 w2");
+    assert_eq!(&lines[8..], ["3 w3", "… 3 more"]);
+
+    let lines = preview(&info, &Err(miette!("Undefined variable")), 20, 10);
+    assert_eq!(lines, vec!["No preview:", "Undefined variable"]);
+}
+
+#[test]
+fn picker_fits_popup() {
+    let mut picker = Picker::new(items());
+
+    // The popup laio binds to `prefix M-l`, less its border
+    let (frame, highlighted) = picker.frame(48, 14, &["1 editor".to_string()]);
+    assert_eq!(frame.len(), 14);
+    assert!(frame.iter().all(|line| line.width() == 48));
+    assert!(frame[0].starts_with("> ") && frame[0].ends_with(" 3/3"));
+    assert!(frame[1].starts_with("· api") && frame[1].contains("│1 editor"));
+    assert_eq!(highlighted, Some(1));
+
+    assert_eq!(picker.handle(key(KeyCode::Char('b'))), None);
+    assert_eq!(picker.handle(key(KeyCode::Char('k'))), None);
+    assert_eq!(picker.current().unwrap().name, "backend");
+    assert_eq!(picker.handle(key(KeyCode::Down)), None);
+    assert_eq!(picker.current().unwrap().name, "backend");
+
+    assert_eq!(picker.handle(key(KeyCode::Backspace)), None);
+    assert_eq!(picker.handle(key(KeyCode::Backspace)), None);
+    assert_eq!(picker.handle(key(KeyCode::Up)), None);
+    assert_eq!(picker.current().unwrap().name, "backend");
+    assert_eq!(picker.handle(key(KeyCode::Enter)), Some(true));
+    assert_eq!(picker.handle(key(KeyCode::Esc)), Some(false));
+}
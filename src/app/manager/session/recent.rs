@@ -0,0 +1,82 @@
+//! Sessions in the order they were last used through laio, for `--sort recent` and
+//! `laio switch -`.
+
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+use miette::{Context, IntoDiagnostic, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::common::path::to_absolute_path;
+
+const RECENT_FILE: &str = "recent.yaml";
+/// Sessions remembered, the ones used longest ago are dropped first.
+const MAX_RECENT: usize = 50;
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub(crate) enum SessionSort {
+    /// Alphabetically, grouped by namespace
+    #[default]
+    Name,
+    /// Most recently used first
+    Recent,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub(crate) struct RecentSession {
+    pub(crate) name: String,
+    /// Unix timestamp in seconds.
+    pub(crate) used: u64,
+}
+
+/// Sessions recorded in the state directory `state_path`, the most recently used
+/// first.
+pub(crate) fn load(state_path: &str) -> Result<Vec<RecentSession>> {
+    let file = to_absolute_path(state_path)?.join(RECENT_FILE);
+    if !file.exists() {
+        return Ok(vec![]);
+    }
+    let yaml = fs::read_to_string(&file)
+        .into_diagnostic()
+        .wrap_err(format!("Could not read '{}'", file.display()))?;
+    noyalib::compat::serde_yaml::from_str(&yaml)
+        .into_diagnostic()
+        .wrap_err(format!("Could not parse '{}'", file.display()))
+}
+
+/// Records that sessions `names` were used now, in this order, so the last one
+/// becomes the most recently used.
+pub(crate) fn touch(state_path: &str, names: &[&str]) -> Result<()> {
+    let used = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+
+    let mut sessions = load(state_path)?;
+    for name in names {
+        sessions.retain(|session| session.name != *name);
+        sessions.insert(
+            0,
+            RecentSession {
+                name: name.to_string(),
+                used,
+            },
+        );
+    }
+    sessions.truncate(MAX_RECENT);
+
+    let dir = to_absolute_path(state_path)?;
+    fs::create_dir_all(&dir)
+        .into_diagnostic()
+        .wrap_err(format!("Could not create '{}'", dir.display()))?;
+    let yaml = noyalib::compat::serde_yaml::to_string(&sessions)
+        .into_diagnostic()
+        .wrap_err("Could not serialize recent sessions")?;
+    let file = dir.join(RECENT_FILE);
+    fs::write(&file, yaml)
+        .into_diagnostic()
+        .wrap_err(format!("Could not write '{}'", file.display()))
+}
@@ -0,0 +1,52 @@
+//! Record of the laio sessions that are running, to start them again with
+//! `laio resurrect`, e.g. after a reboot.
+
+use std::fs;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::common::path::to_absolute_path;
+
+const SESSIONS_FILE: &str = "sessions.yaml";
+
+/// A running session with the config and variables it was started with.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub(crate) struct RunningSession {
+    pub(crate) name: String,
+    pub(crate) config: String,
+    /// Secret values are withheld, as in `LAIO_VARS`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) variables: Vec<String>,
+}
+
+/// Sessions recorded in the state directory `state_path`, none if nothing was
+/// recorded yet.
+pub(crate) fn load(state_path: &str) -> Result<Vec<RunningSession>> {
+    let file = to_absolute_path(state_path)?.join(SESSIONS_FILE);
+    if !file.exists() {
+        return Ok(vec![]);
+    }
+    let yaml = fs::read_to_string(&file)
+        .into_diagnostic()
+        .wrap_err(format!("Could not read '{}'", file.display()))?;
+    noyalib::compat::serde_yaml::from_str(&yaml)
+        .into_diagnostic()
+        .wrap_err(format!("Could not parse '{}'", file.display()))
+}
+
+/// Replaces the sessions recorded in the state directory `state_path`.
+pub(crate) fn save(state_path: &str, sessions: &[RunningSession]) -> Result<()> {
+    let dir = to_absolute_path(state_path)?;
+    fs::create_dir_all(&dir)
+        .into_diagnostic()
+        .wrap_err(format!("Could not create '{}'", dir.display()))?;
+
+    let yaml = noyalib::compat::serde_yaml::to_string(&sessions)
+        .into_diagnostic()
+        .wrap_err("Could not serialize running sessions")?;
+    let file = dir.join(SESSIONS_FILE);
+    fs::write(&file, yaml)
+        .into_diagnostic()
+        .wrap_err(format!("Could not write '{}'", file.display()))
+}
@@ -0,0 +1,186 @@
+//! Snapshots of running sessions: their layout and the content of their panes, so
+//! they can be restored after they were stopped.
+
+use std::{
+    fs::{self, DirBuilder, OpenOptions},
+    io::Write,
+    os::unix::fs::{DirBuilderExt, OpenOptionsExt},
+    path::{Path, PathBuf},
+};
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+use noyalib::compat::serde_yaml::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    config::{overlay::or_single_pane, secrets::Secrets, Command, Session},
+    path::{sanitize_filename, to_absolute_path},
+};
+
+const SNAPSHOTS_DIR: &str = "snapshots";
+const SNAPSHOT_FILE: &str = "snapshot.yaml";
+
+/// A session as it was running, with the config and variables it was started with.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Snapshot {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) config: Option<String>,
+    /// Secret values are withheld, as in `LAIO_VARS`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) variables: Vec<String>,
+    pub(crate) session: Session,
+}
+
+/// Directory of the snapshot of session `name` in the state directory `state_path`.
+pub(crate) fn snapshot_dir(state_path: &str, name: &str) -> Result<PathBuf> {
+    let name = sanitize_filename(name);
+    if name.is_empty() {
+        bail!("Invalid snapshot name");
+    }
+    Ok(to_absolute_path(state_path)?.join(SNAPSHOTS_DIR).join(name))
+}
+
+impl Snapshot {
+    /// Writes the snapshot to `dir`, replacing the one there. The content of every
+    /// pane goes into its own file, see [`pane_file`].
+    ///
+    /// The snapshot is written next to `dir` first, so a failed write keeps the
+    /// previous one. Pane content can hold tokens, only the user can read it, and
+    /// the values of `secrets` are masked.
+    pub(crate) fn save(&self, dir: &Path, panes: &[Vec<String>], secrets: &Secrets) -> Result<()> {
+        let (Some(parent), Some(name)) = (dir.parent(), dir.file_name()) else {
+            bail!("Invalid snapshot directory '{}'", dir.display());
+        };
+        create_private_dir(parent, true)?;
+        let name = name.to_string_lossy();
+        let new_dir = parent.join(format!(".{name}.new"));
+        let old_dir = parent.join(format!(".{name}.old"));
+        for stale in [&new_dir, &old_dir] {
+            if stale.exists() {
+                fs::remove_dir_all(stale)
+                    .into_diagnostic()
+                    .wrap_err(format!("Could not remove '{}'", stale.display()))?;
+            }
+        }
+
+        let written = self.write(&new_dir, panes, secrets);
+        if written.is_err() {
+            let _ = fs::remove_dir_all(&new_dir);
+            return written;
+        }
+
+        let replaced = dir.exists();
+        if replaced {
+            fs::rename(dir, &old_dir)
+                .into_diagnostic()
+                .wrap_err(format!("Could not replace '{}'", dir.display()))?;
+        }
+        if let Err(e) = fs::rename(&new_dir, dir) {
+            if replaced {
+                let _ = fs::rename(&old_dir, dir);
+            }
+            let _ = fs::remove_dir_all(&new_dir);
+            return Err(e)
+                .into_diagnostic()
+                .wrap_err(format!("Could not write snapshot to '{}'", dir.display()));
+        }
+        if replaced {
+            let _ = fs::remove_dir_all(&old_dir);
+        }
+        Ok(())
+    }
+
+    fn write(&self, dir: &Path, panes: &[Vec<String>], secrets: &Secrets) -> Result<()> {
+        create_private_dir(dir, false)?;
+
+        let yaml = noyalib::compat::serde_yaml::to_string(self)
+            .into_diagnostic()
+            .wrap_err("Could not serialize snapshot")?;
+        write_private_file(&dir.join(SNAPSHOT_FILE), &secrets.mask(&yaml))?;
+
+        for (window, contents) in panes.iter().enumerate() {
+            for (pane, content) in contents.iter().enumerate() {
+                // The shell prompt follows the content when it's printed
+                let content = format!("{}\n", secrets.mask(content));
+                write_private_file(&pane_file(dir, window, pane), &content)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn load(dir: &Path) -> Result<Self> {
+        let file = dir.join(SNAPSHOT_FILE);
+        if !file.exists() {
+            bail!("No snapshot found at '{}'", dir.display());
+        }
+        let yaml = fs::read_to_string(&file)
+            .into_diagnostic()
+            .wrap_err(format!("Could not read '{}'", file.display()))?;
+        noyalib::compat::serde_yaml::from_str(&yaml)
+            .into_diagnostic()
+            .wrap_err(format!("Could not parse '{}'", file.display()))
+    }
+
+    /// The session to start, where every pane prints its saved content before running
+    /// its commands. With `skip_cmds` only the content is printed.
+    pub(crate) fn into_session(self, dir: &Path, skip_cmds: bool) -> Result<Session> {
+        let mut session = self.session;
+        session.path = to_absolute_path(&session.path)?
+            .to_string_lossy()
+            .to_string();
+        if skip_cmds {
+            session.startup.clear();
+            session.startup_script = None;
+        }
+
+        for (window, config_window) in session.windows.iter_mut().enumerate() {
+            // A window of a single pane is exported without panes
+            config_window.panes = or_single_pane(&config_window.panes);
+            for (pane, config_pane) in config_window.leaf_panes_mut().enumerate() {
+                if skip_cmds {
+                    config_pane.commands.clear();
+                    config_pane.script = None;
+                }
+                let file = pane_file(dir, window, pane);
+                if file.exists() {
+                    config_pane.commands.insert(
+                        0,
+                        Command {
+                            command: "cat".to_string(),
+                            args: vec![Value::String(file.to_string_lossy().to_string())],
+                        },
+                    );
+                }
+            }
+        }
+        Ok(session)
+    }
+}
+
+/// Creates `dir`, and with `recursive` its parents, readable by the user only.
+fn create_private_dir(dir: &Path, recursive: bool) -> Result<()> {
+    DirBuilder::new()
+        .recursive(recursive)
+        .mode(0o700)
+        .create(dir)
+        .into_diagnostic()
+        .wrap_err(format!("Could not create '{}'", dir.display()))
+}
+
+/// Writes `content` to a new `file` readable by the user only.
+fn write_private_file(file: &Path, content: &str) -> Result<()> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(file)
+        .and_then(|mut f| f.write_all(content.as_bytes()))
+        .into_diagnostic()
+        .wrap_err(format!("Could not write '{}'", file.display()))
+}
+
+/// File with the content of the pane at position `pane` of the layout of the window
+/// at position `window`, numbered from 1 such as `1.2.txt`.
+fn pane_file(dir: &Path, window: usize, pane: usize) -> PathBuf {
+    dir.join(format!("{}.{}.txt", window + 1, pane + 1))
+}
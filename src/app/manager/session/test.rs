@@ -1,5 +1,5 @@
 use crate::app::manager::session::SessionManager;
-use crate::common::config::Session;
+use crate::common::config::{secrets::Secrets, FlexDirection, Session, Window};
 use crate::common::muxer::test::MockMultiplexer;
 use crate::common::session_info::SessionInfo;
 use std::collections::HashMap;
@@ -94,6 +94,9 @@ windows:
     .expect("Failed to write valid.yaml");
 
     let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
 
     // Set expectations for the `switch` method - should return false (session doesn't exist yet)
     mock_multiplexer
@@ -133,9 +136,11 @@ fn session_to_yaml() {
     let mut mock_multiplexer = MockMultiplexer::new();
 
     // Set up expectations for `get_session`
-    mock_multiplexer.expect_get_session().returning(|| {
+    mock_multiplexer.expect_get_session().returning(|_| {
         Ok(Session {
             name: "yaml_test".to_string(),
+            description: None,
+            tags: vec![],
             path: std::env::temp_dir().to_string_lossy().to_string(),
             startup: vec![],
             shutdown: vec![],
@@ -147,14 +152,278 @@ fn session_to_yaml() {
             windows: vec![],
         })
     });
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
 
     let session_manager = SessionManager::new("/path/to/config", Box::new(mock_multiplexer));
 
-    let res = session_manager.to_yaml();
+    let res = session_manager.to_yaml(false);
     assert!(res.is_ok());
     // Further assertions can validate the YAML output
 }
 
+#[test]
+fn session_to_yaml_overlay() {
+    initialize();
+    let test_config_dir = std::env::temp_dir().join("laio_test_session_overlay");
+    let _ = fs::remove_dir_all(&test_config_dir);
+    fs::create_dir_all(&test_config_dir).unwrap();
+
+    let config = test_config_dir.join("overlay.yaml");
+    let raw = "name: {{ session_name }}\npath: /tmp\nstartup:\n  - command: date\nwindows:\n  - name: code\n";
+    fs::write(&config, raw).unwrap();
+
+    let live = |windows: &[&str]| Session {
+        name: "overlay".to_string(),
+        description: None,
+        tags: vec![],
+        path: "/tmp".to_string(),
+        startup: vec![],
+        shutdown: vec![],
+        startup_script: None,
+        shutdown_script: None,
+        env: HashMap::new(),
+        shell: None,
+        pane_cmd_delay: None,
+        windows: windows
+            .iter()
+            .map(|name| Window {
+                name: name.to_string(),
+                path: None,
+                flex_direction: FlexDirection::default(),
+                panes: vec![],
+                focus: false,
+            })
+            .collect(),
+    };
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    let mut sessions = vec![live(&["code", "shell"]), live(&["code"])];
+    mock_multiplexer
+        .expect_get_session()
+        .times(2)
+        .returning(move |_| Ok(sessions.pop().unwrap()));
+    let config_path = config.to_string_lossy().to_string();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("overlay"))
+        .returning(move |_| Ok(Some(config_path.clone())));
+    mock_multiplexer
+        .expect_get_session_variables()
+        .with(mockall::predicate::eq("overlay"))
+        .returning(|_| Ok(Some(vec!["session_name=overlay".to_string()])));
+
+    let session_manager = SessionManager::new(
+        test_config_dir.to_str().unwrap(),
+        Box::new(mock_multiplexer),
+    );
+
+    // Unchanged sessions export the config as written
+    assert_eq!(session_manager.to_yaml(true).unwrap(), raw);
+
+    // Changes are overlaid, keeping the hooks of the config
+    let yaml = session_manager.to_yaml(true).unwrap();
+    let session: Session = noyalib::compat::serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(session.name, "overlay");
+    assert_eq!(session.startup[0].command, "date");
+    let names: Vec<&str> = session.windows.iter().map(|w| w.name.as_str()).collect();
+    assert_eq!(names, vec!["code", "shell"]);
+
+    let _ = fs::remove_dir_all(&test_config_dir);
+}
+
+#[test]
+fn session_save_overlay_keeps_secrets() {
+    initialize();
+    let test_config_dir = std::env::temp_dir().join("laio_test_session_overlay_secret");
+    let _ = fs::remove_dir_all(&test_config_dir);
+    fs::create_dir_all(&test_config_dir).unwrap();
+
+    let config = test_config_dir.join("vault.yaml");
+    let raw = "name: vault\npath: /tmp\nstartup:\n  - command: login {{ secret(name=\"vault_token\", command=\"echo s3cr3t\") }}\nwindows:\n  - name: code\n";
+    fs::write(&config, raw).unwrap();
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer.expect_get_session().returning(|_| {
+        Ok(Session {
+            name: "vault".to_string(),
+            description: None,
+            tags: vec![],
+            path: "/tmp".to_string(),
+            startup: vec![],
+            shutdown: vec![],
+            startup_script: None,
+            shutdown_script: None,
+            env: HashMap::new(),
+            shell: None,
+            pane_cmd_delay: None,
+            windows: ["code", "shell"]
+                .iter()
+                .map(|name| Window {
+                    name: name.to_string(),
+                    path: None,
+                    flex_direction: FlexDirection::default(),
+                    panes: vec![],
+                    focus: false,
+                })
+                .collect(),
+        })
+    });
+    let config_path = config.to_string_lossy().to_string();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("vault"))
+        .returning(move |_| Ok(Some(config_path.clone())));
+    mock_multiplexer
+        .expect_get_session_variables()
+        .with(mockall::predicate::eq("vault"))
+        .returning(|_| Ok(Some(vec![])));
+
+    let session_manager = SessionManager::new(
+        test_config_dir.to_str().unwrap(),
+        Box::new(mock_multiplexer),
+    );
+
+    // The overlay would mask the secret, so the config is left alone
+    let res = session_manager.save(&None, false, true, true);
+    assert!(res.is_err());
+    assert_eq!(fs::read_to_string(&config).unwrap(), raw);
+
+    let _ = fs::remove_dir_all(&test_config_dir);
+}
+
+#[test]
+fn session_diff() {
+    initialize();
+    let test_config_dir = std::env::temp_dir().join("laio_test_session_diff");
+    let _ = fs::remove_dir_all(&test_config_dir);
+    fs::create_dir_all(&test_config_dir).unwrap();
+
+    let config = test_config_dir.join("drift.yaml");
+    fs::write(
+        &config,
+        "name: {{ session_name }}\npath: /tmp\nwindows:\n  - name: code\n  - name: logs\n",
+    )
+    .unwrap();
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session()
+        .withf(|name| *name == Some("drift"))
+        .returning(|_| {
+            Ok(Session {
+                name: "drift".to_string(),
+                description: None,
+                tags: vec![],
+                path: "/tmp".to_string(),
+                startup: vec![],
+                shutdown: vec![],
+                startup_script: None,
+                shutdown_script: None,
+                env: HashMap::new(),
+                shell: None,
+                pane_cmd_delay: None,
+                windows: vec![Window {
+                    name: "code".to_string(),
+                    path: None,
+                    flex_direction: FlexDirection::default(),
+                    panes: vec![],
+                    focus: false,
+                }],
+            })
+        });
+    let config_path = config.to_string_lossy().to_string();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("drift"))
+        .returning(move |_| Ok(Some(config_path.clone())));
+    mock_multiplexer
+        .expect_get_session_variables()
+        .with(mockall::predicate::eq("drift"))
+        .returning(|_| Ok(Some(vec!["session_name=drift".to_string()])));
+
+    let session_manager = SessionManager::new(
+        test_config_dir.to_str().unwrap(),
+        Box::new(mock_multiplexer),
+    );
+
+    let diff = session_manager.diff(&Some("drift".to_string())).unwrap();
+    assert!(diff.differs);
+    assert_eq!(diff.session, "drift");
+    let json = serde_json::to_value(&diff).unwrap();
+    assert_eq!(json["windows"][0]["label"], "window 'logs'");
+    assert_eq!(json["windows"][0]["status"], "removed");
+
+    let _ = fs::remove_dir_all(&test_config_dir);
+}
+
+#[test]
+fn session_save() {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    initialize();
+    let test_config_dir = std::env::temp_dir().join("laio_test_session_save");
+    let _ = fs::remove_dir_all(&test_config_dir);
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer.expect_get_session().returning(|_| {
+        Ok(Session {
+            name: "Saved Test".to_string(),
+            description: None,
+            tags: vec![],
+            path: "/tmp".to_string(),
+            startup: vec![],
+            shutdown: vec![],
+            startup_script: None,
+            shutdown_script: None,
+            env: HashMap::new(),
+            shell: None,
+            pane_cmd_delay: None,
+            windows: vec![],
+        })
+    });
+
+    // Not started by laio at first, managed once saved
+    let managed = Arc::new(AtomicBool::new(false));
+    let is_managed = managed.clone();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("Saved Test"))
+        .returning(move |_| {
+            Ok(is_managed
+                .load(Ordering::SeqCst)
+                .then(|| "saved-test.yaml".to_string()))
+        });
+    mock_multiplexer
+        .expect_set_session_config_path()
+        .withf(|name, path| name == "Saved Test" && path.ends_with("saved-test.yaml"))
+        .times(1)
+        .returning(move |_, _| {
+            managed.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+
+    let session_manager = SessionManager::new(
+        test_config_dir.to_str().unwrap(),
+        Box::new(mock_multiplexer),
+    );
+
+    let path = session_manager.save(&None, false, false, false).unwrap();
+    assert_eq!(path, test_config_dir.join("saved-test.yaml"));
+    let saved = Session::from_config(&path, None, &[], &Secrets::default()).unwrap();
+    assert_eq!(saved.name, "Saved Test");
+
+    // Existing configs are only replaced with --force
+    assert!(session_manager.save(&None, false, false, false).is_err());
+    assert!(session_manager.save(&None, false, true, false).is_ok());
+
+    let _ = fs::remove_dir_all(&test_config_dir);
+}
+
 #[test]
 fn session_start_with_default_fallback() {
     initialize();
@@ -181,6 +450,9 @@ windows:
     .expect("Failed to write _default.yaml");
 
     let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
 
     // Switch should return false (session doesn't exist yet)
     mock_multiplexer
@@ -234,6 +506,9 @@ fn session_start_generates_default_if_missing() {
     assert!(!default_config.exists());
 
     let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
 
     // Switch should return false
     mock_multiplexer.expect_switch().returning(|_, _| Ok(false));
@@ -309,6 +584,9 @@ windows:
     .expect("Failed to write myconfig.yaml");
 
     let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
 
     mock_multiplexer.expect_switch().returning(|_, _| Ok(false));
 
@@ -338,6 +616,207 @@ windows:
     let _ = fs::remove_dir_all(&test_config_dir);
 }
 
+#[test]
+fn session_start_uses_config_search_path() {
+    initialize();
+    let temp_dir = std::env::temp_dir();
+    let personal_dir = temp_dir.join("laio_test_search_personal");
+    let team_dir = temp_dir.join("laio_test_search_team");
+
+    for dir in [&personal_dir, &team_dir] {
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir).expect("Failed to create test dir");
+    }
+
+    let config = |name: &str| {
+        format!("---\nname: {name}\npath: /tmp\nwindows:\n  - name: main\n    panes:\n      - flex: 1\n")
+    };
+    fs::write(personal_dir.join("shared.yaml"), config("personal_shared"))
+        .expect("Failed to write shared.yaml");
+    fs::write(team_dir.join("shared.yaml"), config("team_shared"))
+        .expect("Failed to write shared.yaml");
+    fs::write(team_dir.join("teamonly.yaml"), config("team_only"))
+        .expect("Failed to write teamonly.yaml");
+
+    let search_path = format!("{}:{}", personal_dir.display(), team_dir.display());
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
+    mock_multiplexer.expect_switch().returning(|_, _| Ok(false));
+    // Earlier directories take precedence, later ones still supply configs
+    mock_multiplexer
+        .expect_start()
+        .withf(|session, _, _, _| session.name == "personal_shared")
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+    mock_multiplexer
+        .expect_start()
+        .withf(|session, _, _, _| session.name == "team_only")
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let session_manager = SessionManager::new(&search_path, Box::new(mock_multiplexer));
+
+    for name in ["shared", "teamonly"] {
+        let res = session_manager.start(&Some(name.to_string()), &None, &[], false, false, false);
+        assert!(res.is_ok(), "{res:?}");
+    }
+
+    for dir in [&personal_dir, &team_dir] {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+#[test]
+fn session_start_namespaced_config() {
+    initialize();
+    let test_config_dir = std::env::temp_dir().join("laio_test_namespaced_start");
+
+    let _ = fs::remove_dir_all(&test_config_dir);
+    fs::create_dir_all(test_config_dir.join("work")).expect("Failed to create test dir");
+    fs::write(
+        test_config_dir.join("work/api.yaml"),
+        "---\nname: {{ session_name }}\npath: /tmp\nwindows:\n  - name: main\n    panes:\n      - flex: 1\n",
+    )
+    .expect("Failed to write work/api.yaml");
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
+    mock_multiplexer.expect_switch().returning(|_, _| Ok(false));
+    // The namespace isn't part of the session name
+    mock_multiplexer
+        .expect_start()
+        .withf(|session, _, _, _| session.name == "api")
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let session_manager = SessionManager::new(
+        test_config_dir.to_str().unwrap(),
+        Box::new(mock_multiplexer),
+    );
+
+    let res = session_manager.start(
+        &Some("work/api".to_string()),
+        &None,
+        &[],
+        false,
+        false,
+        false,
+    );
+    assert!(res.is_ok(), "{res:?}");
+
+    let _ = fs::remove_dir_all(&test_config_dir);
+}
+
+#[test]
+fn session_info_merge_groups_namespaces() {
+    use crate::common::session_info::ConfigSource;
+
+    let config = |name: &str| {
+        SessionInfo::inactive(name.to_string())
+            .with_config_path(format!("/configs/{name}.yaml"), ConfigSource::Local)
+    };
+    let configs = || {
+        vec![
+            config("zeta"),
+            config("work/api"),
+            config("home/api"),
+            config("work/web"),
+            config("alpha"),
+        ]
+    };
+    let merge = |sessions: Vec<SessionInfo>| -> Vec<String> {
+        SessionInfo::merge(sessions, configs())
+            .into_iter()
+            .map(|s| s.name)
+            .collect()
+    };
+
+    // A session started from a config replaces it and is listed with its folder
+    let api = SessionInfo {
+        config_path: Some("/configs/home/api.yaml".to_string()),
+        ..SessionInfo::active("api".to_string(), true)
+    };
+    let merged = SessionInfo::merge(vec![api], configs());
+    let home_api = merged.iter().find(|s| s.is_active()).unwrap();
+    assert_eq!(home_api.name, "home/api");
+    assert_eq!(home_api.session.as_deref(), Some("api"));
+    assert_eq!(home_api.session_name(), "api");
+    assert_eq!(
+        merged.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+        vec!["alpha", "zeta", "home/api", "work/api", "work/web"]
+    );
+
+    // Without its config, the session name is ambiguous and both configs are kept
+    assert_eq!(
+        merge(vec![SessionInfo::active("api".to_string(), true)]),
+        vec!["alpha", "api", "zeta", "home/api", "work/api", "work/web"]
+    );
+
+    // A name used in a single folder still finds its config
+    assert_eq!(
+        merge(vec![SessionInfo::active("web".to_string(), false)]),
+        vec!["alpha", "zeta", "home/api", "work/api", "work/web"]
+    );
+
+    // Configs of the same name in different directories are both kept
+    let other_zeta = SessionInfo::inactive("zeta".to_string())
+        .with_config_path("/other/zeta.yaml".to_string(), ConfigSource::Local);
+    let mut configs = configs();
+    configs.push(other_zeta);
+    assert_eq!(SessionInfo::merge(vec![], configs).len(), 6);
+}
+
+#[test]
+fn session_start_refuses_session_name_of_another_config() {
+    initialize();
+    let test_config_dir = std::env::temp_dir().join("laio_test_start_name_collision");
+    let _ = fs::remove_dir_all(&test_config_dir);
+    for namespace in ["work", "home"] {
+        fs::create_dir_all(test_config_dir.join(namespace)).unwrap();
+        fs::write(
+            test_config_dir.join(format!("{namespace}/api.yaml")),
+            "name: api\npath: /tmp\nwindows:\n  - name: main\n",
+        )
+        .unwrap();
+    }
+
+    // "api" is running from work/api.yaml
+    let running_config = test_config_dir
+        .join("work/api.yaml")
+        .to_string_lossy()
+        .to_string();
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("api"))
+        .returning(move |_| Ok(Some(running_config.clone())));
+    mock_multiplexer
+        .expect_switch()
+        .withf(|name, _| name == "api")
+        .times(1)
+        .returning(|_, _| Ok(true));
+    mock_multiplexer.expect_start().times(0);
+
+    let session_manager = SessionManager::new(
+        test_config_dir.to_str().unwrap(),
+        Box::new(mock_multiplexer),
+    );
+
+    let start =
+        |name: &str| session_manager.start(&Some(name.to_string()), &None, &[], false, false, true);
+    let res = start("home/api");
+    assert!(res.is_err());
+    assert!(format!("{:?}", res.unwrap_err()).contains("already running"));
+    assert!(start("work/api").is_ok());
+
+    let _ = fs::remove_dir_all(&test_config_dir);
+}
+
 #[test]
 fn default_yaml_filtered_from_picker() {
     initialize();
@@ -680,6 +1159,48 @@ fn encode_decode_roundtrip() {
     assert_eq!(decoded, original);
 }
 
+#[test]
+fn encode_decode_roundtrip_typed_values() {
+    use crate::app::manager::session::manager::{decode_variables, encode_variables};
+    use crate::common::config::variables::parse_variables;
+
+    let original = vec![
+        "port:int=3000".to_string(),
+        "debug:bool=false".to_string(),
+        "services=[\"api\",\"web\"]".to_string(),
+        "db.host=localhost".to_string(),
+        "db.port:int=5432".to_string(),
+    ];
+
+    let encoded = encode_variables(&original).expect("Encoding failed");
+    let decoded = decode_variables(&encoded).expect("Decoding failed");
+
+    assert_eq!(decoded, original);
+    assert_eq!(
+        parse_variables(&decoded, &Secrets::default()).unwrap(),
+        parse_variables(&original, &Secrets::default()).unwrap()
+    );
+}
+
+#[test]
+fn encode_variables_withholds_secrets() {
+    use crate::app::manager::session::manager::{decode_variables, encode_variables};
+
+    let original = vec![
+        "name=test".to_string(),
+        "api_token:secret=s3cr3t-encode-test".to_string(),
+    ];
+
+    let encoded = encode_variables(&original).expect("Encoding failed");
+    assert!(!encoded.contains("s3cr3t-encode-test"));
+
+    let decoded = decode_variables(&encoded).expect("Decoding failed");
+    assert_eq!(
+        decoded,
+        vec!["name=test".to_string(), "api_token:secret=".to_string()]
+    );
+}
+
 #[test]
 fn session_stop_retrieves_stored_variables() {
     initialize();
@@ -915,6 +1436,9 @@ windows:
     .expect("Failed to write myproject.yaml");
 
     let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
 
     // Switch should be called with the FINAL substituted name
     mock_multiplexer
@@ -975,6 +1499,13 @@ windows:
 
     let mut mock_multiplexer = MockMultiplexer::new();
 
+    // The running session was started from the same config
+    let running_config = config_file.to_string_lossy().to_string();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("project-dev"))
+        .returning(move |_| Ok(Some(running_config.clone())));
+
     // Switch should be called with substituted name and return true (session exists)
     mock_multiplexer
         .expect_switch()
@@ -1030,6 +1561,9 @@ windows:
     .expect("Failed to write api.yaml");
 
     let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
 
     // Switch should be called with fully substituted name
     mock_multiplexer
@@ -1089,6 +1623,9 @@ windows:
     .expect("Failed to write simple.yaml");
 
     let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
 
     // Switch should be called with just the session_name
     mock_multiplexer
@@ -1114,3 +1651,651 @@ windows:
     // Cleanup
     let _ = fs::remove_dir_all(&test_config_dir);
 }
+
+#[test]
+fn session_restart_with_stored_variables() {
+    initialize();
+    let test_config_dir = std::env::temp_dir().join("laio_test_restart");
+    let _ = fs::remove_dir_all(&test_config_dir);
+    fs::create_dir_all(&test_config_dir).unwrap();
+
+    let config_file = test_config_dir.join("restart.yaml");
+    fs::write(
+        &config_file,
+        "name: {{ session_name }}\npath: /tmp/{{ env }}\nwindows:\n  - name: main\n",
+    )
+    .unwrap();
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_current_session_name()
+        .returning(|| Ok(Some("restart".to_string())));
+    let config_path = config_file.to_str().unwrap().to_string();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("restart"))
+        .returning(move |_| Ok(Some(config_path.clone())));
+    mock_multiplexer
+        .expect_get_session_variables()
+        .with(mockall::predicate::eq("restart"))
+        .returning(|_| {
+            Ok(Some(vec![
+                "session_name=restart".to_string(),
+                "env=dev".to_string(),
+            ]))
+        });
+
+    // --var replaces the stored variable, the session keeps its name. Shutdown hooks
+    // run for the session as it was started.
+    let expected_config = config_file.to_str().unwrap().to_string();
+    mock_multiplexer
+        .expect_restart()
+        .withf(
+            move |name, running, session, env_vars, skip_attach, skip_cmds| {
+                name == "restart"
+                    && running
+                        .as_ref()
+                        .is_some_and(|running| running.path == "/tmp/dev")
+                    && session.name == "restart"
+                    && session.path == "/tmp/prod"
+                    && env_vars.contains(&("LAIO_CONFIG", expected_config.as_str()))
+                    && env_vars.contains(&("LAIO_VARS", "session_name=restart&env=prod"))
+                    && !*skip_attach
+                    && *skip_cmds
+            },
+        )
+        .times(1)
+        .returning(|_, _, _, _, _, _| Ok(()));
+
+    let session_manager = SessionManager::new(
+        test_config_dir.to_str().unwrap(),
+        Box::new(mock_multiplexer),
+    );
+
+    let res = session_manager.restart(
+        &None,
+        &["env=prod".to_string(), "session_name=other".to_string()],
+        true,
+        false,
+    );
+    assert!(res.is_ok(), "{res:?}");
+
+    let _ = fs::remove_dir_all(&test_config_dir);
+}
+
+#[test]
+fn session_snapshot_and_restore() {
+    initialize();
+    let test_state_dir = std::env::temp_dir().join("laio_test_snapshot");
+    let _ = fs::remove_dir_all(&test_state_dir);
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session()
+        .withf(|name| *name == Some("snap"))
+        .returning(|_| {
+            Ok(Session {
+                name: "snap".to_string(),
+                description: None,
+                tags: vec![],
+                path: "/tmp".to_string(),
+                startup: vec![],
+                shutdown: vec![],
+                startup_script: None,
+                shutdown_script: None,
+                env: HashMap::new(),
+                shell: None,
+                pane_cmd_delay: None,
+                windows: vec![Window {
+                    name: "main".to_string(),
+                    path: None,
+                    flex_direction: FlexDirection::default(),
+                    panes: vec![],
+                    focus: false,
+                }],
+            })
+        });
+    mock_multiplexer
+        .expect_supports_snapshots()
+        .return_const(true);
+    mock_multiplexer
+        .expect_capture_panes()
+        .with(mockall::predicate::eq("snap"))
+        .returning(|_| Ok(vec![vec!["$ make test".to_string()]]));
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("snap"))
+        .returning(|_| Ok(Some("/configs/snap.yaml".to_string())));
+    mock_multiplexer
+        .expect_get_session_variables()
+        .with(mockall::predicate::eq("snap"))
+        .returning(|_| Ok(Some(vec!["env=dev".to_string()])));
+
+    // The single pane prints its content, the session is linked to its config again
+    let pane_file = test_state_dir.join("snapshots/snap/1.1.txt");
+    let expected_cat = format!("cat {}", pane_file.display());
+    mock_multiplexer
+        .expect_start()
+        .withf(move |session, env_vars, skip_attach, skip_cmds| {
+            let pane = &session.windows[0].panes[0];
+            session.name == "snap"
+                && pane.commands.len() == 1
+                && pane.commands[0].to_string() == expected_cat
+                && env_vars.contains(&("LAIO_CONFIG", "/configs/snap.yaml"))
+                && env_vars.contains(&("LAIO_VARS", "env=dev"))
+                && *skip_attach
+                && !*skip_cmds
+        })
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let mut session_manager = SessionManager::new("/path/to/config", Box::new(mock_multiplexer));
+    session_manager.state_path = test_state_dir.to_string_lossy().to_string();
+
+    let dir = session_manager.snapshot(&Some("snap".to_string())).unwrap();
+    assert_eq!(dir, test_state_dir.join("snapshots/snap"));
+    assert_eq!(fs::read_to_string(&pane_file).unwrap(), "$ make test\n");
+
+    let res = session_manager.restore("snap", true, false);
+    assert!(res.is_ok(), "{res:?}");
+    assert!(session_manager.restore("other", true, false).is_err());
+
+    let _ = fs::remove_dir_all(&test_state_dir);
+}
+
+#[test]
+fn session_snapshot_unsupported_fails_before_reading_session() {
+    initialize();
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_supports_snapshots()
+        .return_const(false);
+    mock_multiplexer.expect_get_session().never();
+    mock_multiplexer.expect_stop().never();
+
+    let session_manager = SessionManager::new("/path/to/config", Box::new(mock_multiplexer));
+    let err = session_manager
+        .snapshot(&Some("snap".to_string()))
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("only supported with tmux"),
+        "{err}"
+    );
+}
+
+#[test]
+fn session_yaml_and_snapshot_mask_secrets_by_name() {
+    initialize();
+    let test_dir = std::env::temp_dir().join("laio_test_snapshot_secrets");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+
+    // The token was passed with --secret-var, the session only knows its name
+    let config = test_dir.join("vault.yaml");
+    fs::write(
+        &config,
+        "name: vault\npath: /tmp\nenv:\n  API_TOKEN: \"{{ api_token }}\"\n  EDITOR: vim\nwindows:\n  - name: main\n",
+    )
+    .unwrap();
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer.expect_get_session().returning(|_| {
+        Ok(Session {
+            name: "vault".to_string(),
+            description: None,
+            tags: vec![],
+            path: "/tmp".to_string(),
+            startup: vec![],
+            shutdown: vec![],
+            startup_script: None,
+            shutdown_script: None,
+            env: HashMap::from([
+                ("API_TOKEN".to_string(), "live-token-value".to_string()),
+                ("EDITOR".to_string(), "vim".to_string()),
+            ]),
+            shell: None,
+            pane_cmd_delay: None,
+            windows: vec![Window {
+                name: "main".to_string(),
+                path: None,
+                flex_direction: FlexDirection::default(),
+                panes: vec![],
+                focus: false,
+            }],
+        })
+    });
+    mock_multiplexer
+        .expect_supports_snapshots()
+        .return_const(true);
+    mock_multiplexer
+        .expect_capture_panes()
+        .returning(|_| Ok(vec![vec!["$ curl -H live-token-value".to_string()]]));
+    let config_path = config.to_string_lossy().to_string();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("vault"))
+        .returning(move |_| Ok(Some(config_path.clone())));
+    mock_multiplexer
+        .expect_get_session_variables()
+        .with(mockall::predicate::eq("vault"))
+        .returning(|_| Ok(Some(vec!["api_token:secret=".to_string()])));
+
+    let mut session_manager =
+        SessionManager::new(test_dir.to_str().unwrap(), Box::new(mock_multiplexer));
+    session_manager.state_path = test_dir.to_string_lossy().to_string();
+
+    let yaml = session_manager.to_yaml(false).unwrap();
+    assert!(!yaml.contains("live-token-value"), "{yaml}");
+    assert!(yaml.contains("EDITOR: vim"), "{yaml}");
+
+    let dir = session_manager
+        .snapshot(&Some("vault".to_string()))
+        .unwrap();
+    let snapshot = fs::read_to_string(dir.join("snapshot.yaml")).unwrap();
+    assert!(!snapshot.contains("live-token-value"), "{snapshot}");
+    assert_eq!(
+        fs::read_to_string(dir.join("1.1.txt")).unwrap(),
+        "$ curl -H ********\n"
+    );
+
+    let _ = fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn session_snapshot_save_is_private_and_keeps_previous_on_failure() {
+    use crate::app::manager::session::snapshot::Snapshot;
+    use std::os::unix::fs::PermissionsExt;
+
+    let test_dir = std::env::temp_dir().join("laio_test_snapshot_save");
+    let _ = fs::remove_dir_all(&test_dir);
+    let dir = test_dir.join("snapshots/snap");
+    let snapshot = |name: &str| Snapshot {
+        config: None,
+        variables: vec![],
+        session: noyalib::compat::serde_yaml::from_str(&format!(
+            "name: {name}\nwindows:\n  - name: main\n"
+        ))
+        .unwrap(),
+    };
+    let mode = |path: &std::path::Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    snapshot("first")
+        .save(&dir, &[vec!["TOKEN=abc".to_string()]], &Secrets::default())
+        .unwrap();
+    assert_eq!(mode(&test_dir.join("snapshots")), 0o700);
+    assert_eq!(mode(&dir), 0o700);
+    assert_eq!(mode(&dir.join("snapshot.yaml")), 0o600);
+    assert_eq!(mode(&dir.join("1.1.txt")), 0o600);
+
+    snapshot("second")
+        .save(&dir, &[], &Secrets::default())
+        .unwrap();
+    assert_eq!(Snapshot::load(&dir).unwrap().session.name, "second");
+    assert!(!dir.join("1.1.txt").exists());
+
+    // A save that fails leaves the previous snapshot in place
+    fs::write(test_dir.join("snapshots/.snap.new"), "in the way").unwrap();
+    assert!(snapshot("third")
+        .save(&dir, &[], &Secrets::default())
+        .is_err());
+    assert_eq!(Snapshot::load(&dir).unwrap().session.name, "second");
+
+    let _ = fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn session_resurrect_reports_failures_per_session() {
+    use crate::app::manager::session::running::{self, RunningSession};
+
+    initialize();
+    let test_dir = std::env::temp_dir().join("laio_test_resurrect");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let config_file = test_dir.join("app.yaml");
+    fs::write(
+        &config_file,
+        "name: app-{{ env }}\npath: /tmp\nwindows:\n  - name: main\n",
+    )
+    .unwrap();
+    let config = config_file.to_string_lossy().to_string();
+    let state_path = test_dir.join("state").to_string_lossy().to_string();
+
+    let recorded = |name: &str, config: &str| RunningSession {
+        name: name.to_string(),
+        config: config.to_string(),
+        variables: vec!["env=dev".to_string()],
+    };
+    running::save(
+        &state_path,
+        &[
+            recorded("running", &config),
+            recorded("app-dev", &config),
+            recorded("broken", "/nonexistent/broken.yaml"),
+        ],
+    )
+    .unwrap();
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_list_sessions()
+        .returning(|| Ok(vec![SessionInfo::active("running".to_string(), false)]));
+    let running_config = config.clone();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .with(mockall::predicate::eq("running"))
+        .returning(move |_| Ok(Some(running_config.clone())));
+    mock_multiplexer
+        .expect_get_session_variables()
+        .with(mockall::predicate::eq("running"))
+        .returning(|_| Ok(Some(vec!["env=dev".to_string()])));
+
+    // Only sessions that aren't running are started, detached
+    mock_multiplexer
+        .expect_start()
+        .withf(|session, env_vars, skip_attach, skip_cmds| {
+            session.name == "app-dev"
+                && env_vars.contains(&("LAIO_VARS", "env=dev"))
+                && *skip_attach
+                && !*skip_cmds
+        })
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+    mock_multiplexer
+        .expect_stop()
+        .withf(|name, _, _, _, _| name.as_deref() == Some("broken"))
+        .times(1)
+        .returning(|_, _, _, _, _| Ok(()));
+
+    let mut session_manager =
+        SessionManager::new("/path/to/config", Box::new(mock_multiplexer)).with_session_record();
+    session_manager.state_path = state_path.clone();
+
+    let results = session_manager.resurrect(false).unwrap();
+    let names: Vec<&str> = results.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["app-dev", "broken"]);
+    assert!(results[0].1.is_ok());
+    assert!(results[1].1.is_err());
+
+    // The failed session stays recorded to try again
+    let names: Vec<String> = running::load(&state_path)
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, vec!["running", "app-dev", "broken"]);
+
+    let _ = fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn session_record_keeps_sessions_not_running() {
+    use crate::app::manager::session::running::{self, RunningSession};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    initialize();
+    let test_dir = std::env::temp_dir().join("laio_test_record_incremental");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let config_file = test_dir.join("foo.yaml");
+    fs::write(
+        &config_file,
+        "name: foo\npath: /tmp\nwindows:\n  - name: main\n",
+    )
+    .unwrap();
+    let state_path = test_dir.join("state").to_string_lossy().to_string();
+
+    let recorded = |name: &str| RunningSession {
+        name: name.to_string(),
+        config: format!("/configs/{name}.yaml"),
+        variables: vec![],
+    };
+    running::save(&state_path, &[recorded("api"), recorded("web")]).unwrap();
+    let names = || -> Vec<String> {
+        running::load(&state_path)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect()
+    };
+
+    // After a reboot nothing runs, starting one session keeps the others recorded
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_list_sessions()
+        .returning(|| Ok(vec![]));
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
+    mock_multiplexer.expect_switch().returning(|_, _| Ok(false));
+    mock_multiplexer
+        .expect_start()
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+    let mut session_manager =
+        SessionManager::new("/path/to/config", Box::new(mock_multiplexer)).with_session_record();
+    session_manager.state_path = state_path.clone();
+
+    let file = config_file.to_string_lossy().to_string();
+    let res = session_manager.start(&None, &Some(file), &[], false, false, true);
+    assert!(res.is_ok(), "{res:?}");
+    assert_eq!(names(), vec!["api", "web", "foo"]);
+
+    // Stopping drops only the session it stopped
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer.expect_list_sessions().returning(move || {
+        Ok(match calls.fetch_add(1, Ordering::SeqCst) {
+            0 => vec![SessionInfo::active("foo".to_string(), false)],
+            _ => vec![],
+        })
+    });
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
+    mock_multiplexer
+        .expect_stop()
+        .times(1)
+        .returning(|_, _, _, _, _| Ok(()));
+    let mut session_manager =
+        SessionManager::new("/path/to/config", Box::new(mock_multiplexer)).with_session_record();
+    session_manager.state_path = state_path.clone();
+
+    let res = session_manager.stop(&Some("foo".to_string()), &[], false, false, false);
+    assert!(res.is_ok(), "{res:?}");
+    assert_eq!(names(), vec!["api", "web"]);
+
+    let _ = fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn session_record_drops_sessions_failing_to_start() {
+    use crate::app::manager::session::running::{self, RunningSession};
+
+    initialize();
+    let test_dir = std::env::temp_dir().join("laio_test_record_failed_start");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let config_file = test_dir.join("foo.yaml");
+    fs::write(
+        &config_file,
+        "name: foo\npath: /tmp\nwindows:\n  - name: main\n",
+    )
+    .unwrap();
+    let state_path = test_dir.join("state").to_string_lossy().to_string();
+    running::save(
+        &state_path,
+        &[RunningSession {
+            name: "api".to_string(),
+            config: "/configs/api.yaml".to_string(),
+            variables: vec![],
+        }],
+    )
+    .unwrap();
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_list_sessions()
+        .returning(|| Ok(vec![]));
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
+    mock_multiplexer.expect_switch().returning(|_, _| Ok(false));
+    mock_multiplexer
+        .expect_start()
+        .times(1)
+        .returning(|_, _, _, _| Err(miette::miette!("bad layout")));
+    let mut session_manager =
+        SessionManager::new("/path/to/config", Box::new(mock_multiplexer)).with_session_record();
+    session_manager.state_path = state_path.clone();
+
+    let file = config_file.to_string_lossy().to_string();
+    let res = session_manager.start(&None, &Some(file), &[], false, false, true);
+    assert!(res.is_err());
+    let names: Vec<String> = running::load(&state_path)
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, vec!["api"]);
+
+    let _ = fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn session_group_start_and_list() {
+    initialize();
+    let test_dir = std::env::temp_dir().join("laio_test_group");
+    let _ = fs::remove_dir_all(&test_dir);
+    fs::create_dir_all(test_dir.join("_groups")).unwrap();
+    fs::write(
+        test_dir.join("app.yaml"),
+        "name: app-{{ env }}\npath: /tmp\nwindows:\n  - name: main\n",
+    )
+    .unwrap();
+    fs::write(
+        test_dir.join("notes.yaml"),
+        "name: notes\npath: /tmp\nwindows:\n  - name: main\n",
+    )
+    .unwrap();
+    fs::write(
+        test_dir.join("_groups/work.yaml"),
+        "sessions:\n  - config: notes\n    attach: true\n  - config: app\n    variables:\n      - env=dev\n",
+    )
+    .unwrap();
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_get_session_config_path()
+        .returning(|_| Ok(None));
+    mock_multiplexer.expect_switch().returning(|_, _| Ok(false));
+    mock_multiplexer
+        .expect_list_sessions()
+        .returning(|| Ok(vec![SessionInfo::active("notes".to_string(), false)]));
+    // The session to attach to is started last, the others detached
+    let mut sequence = mockall::Sequence::new();
+    mock_multiplexer
+        .expect_start()
+        .withf(|session, env_vars, skip_attach, _| {
+            session.name == "app-prod"
+                && env_vars
+                    .iter()
+                    .any(|(k, v)| *k == "LAIO_VARS" && v.starts_with("env=prod&"))
+                && *skip_attach
+        })
+        .times(1)
+        .in_sequence(&mut sequence)
+        .returning(|_, _, _, _| Ok(()));
+    mock_multiplexer
+        .expect_start()
+        .withf(|session, _, skip_attach, _| session.name == "notes" && !*skip_attach)
+        .times(1)
+        .in_sequence(&mut sequence)
+        .returning(|_, _, _, _| Ok(()));
+
+    let session_manager =
+        SessionManager::new(&test_dir.to_string_lossy(), Box::new(mock_multiplexer));
+
+    // Variables given on the command line replace those of the members
+    let res = session_manager.start_group("work", &["env=prod".to_string()], false, false);
+    assert!(res.is_ok(), "{res:?}");
+
+    let sessions = session_manager.list_group("work").unwrap();
+    let names: Vec<(&str, bool)> = sessions
+        .iter()
+        .map(|info| (info.name.as_str(), info.is_active()))
+        .collect();
+    assert_eq!(names, vec![("notes", true), ("app-dev", false)]);
+
+    let groups = session_manager.list_groups().unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].name, "work");
+    assert!(session_manager
+        .start_group("missing", &[], false, false)
+        .is_err());
+
+    let _ = fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn session_switch_to_previous_and_sort_recent() {
+    use crate::app::manager::session::recent::{self, SessionSort};
+
+    initialize();
+    let state_dir = std::env::temp_dir().join("laio_test_recent");
+    let _ = fs::remove_dir_all(&state_dir);
+    let state_path = state_dir.to_string_lossy().to_string();
+    recent::touch(&state_path, &["gone", "api", "web"]).unwrap();
+
+    let mut mock_multiplexer = MockMultiplexer::new();
+    mock_multiplexer
+        .expect_current_session_name()
+        .returning(|| Ok(Some("web".to_string())));
+    mock_multiplexer.expect_list_sessions().returning(|| {
+        Ok(["web", "api", "notes"]
+            .map(|name| SessionInfo::active(name.to_string(), false))
+            .to_vec())
+    });
+    // "gone" isn't running anymore and "web" is the current session
+    mock_multiplexer
+        .expect_switch()
+        .with(mockall::predicate::eq("api"), mockall::predicate::eq(false))
+        .times(1)
+        .returning(|_, _| Ok(true));
+    mock_multiplexer
+        .expect_switch()
+        .with(
+            mockall::predicate::eq("stopped"),
+            mockall::predicate::eq(false),
+        )
+        .returning(|_, _| Ok(false));
+
+    let mut session_manager =
+        SessionManager::new("/path/to/config", Box::new(mock_multiplexer)).with_session_record();
+    session_manager.state_path = state_path.clone();
+
+    session_manager.switch("-").unwrap();
+    assert!(session_manager.switch("stopped").is_err());
+
+    // Switching records the session it was switched from before the one switched to
+    let names: Vec<String> = recent::load(&state_path)
+        .unwrap()
+        .into_iter()
+        .map(|session| session.name)
+        .collect();
+    assert_eq!(names, vec!["api", "web", "gone"]);
+
+    let mut sessions = vec![
+        SessionInfo::inactive("alpha".to_string()),
+        SessionInfo::inactive("work/web".to_string()),
+        SessionInfo::active("api".to_string(), false),
+        SessionInfo::inactive("zeta".to_string()),
+    ];
+    session_manager.sort(&mut sessions, SessionSort::Recent);
+    let names: Vec<&str> = sessions.iter().map(|info| info.name.as_str()).collect();
+    assert_eq!(names, vec!["api", "work/web", "alpha", "zeta"]);
+
+    let _ = fs::remove_dir_all(&state_dir);
+}
@@ -1,8 +1,11 @@
 mod model;
+mod recording_runner;
 mod shell_runner;
+pub(crate) use model::quote;
 pub(crate) use model::Cmd;
 pub(crate) use model::Runner;
 pub(crate) use model::Type;
+pub(crate) use recording_runner::{RecordedCommand, RecordingRunner};
 pub(crate) use shell_runner::ShellRunner;
 
 #[cfg(test)]
@@ -7,6 +7,8 @@ pub(crate) enum Type {
     Basic(Command),
     Verbose(Command),
     Forget(Command),
+    /// Only reads state, so it also runs in a dry run.
+    Query(Command),
 }
 
 impl fmt::Display for Type {
@@ -15,13 +17,31 @@ impl fmt::Display for Type {
             Type::Basic(cmd) => write!(f, "Basic: {cmd:?}"),
             Type::Verbose(cmd) => write!(f, "Verbose: {cmd:?}"),
             Type::Forget(cmd) => write!(f, "Forget: {cmd:?}"),
+            Type::Query(cmd) => write!(f, "Query: {cmd:?}"),
         }
     }
 }
 
+/// Quotes `word` for the shell, unless it's safe as is.
+pub(crate) fn quote(word: &str) -> String {
+    let safe = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    match safe {
+        true => word.to_string(),
+        false => format!("'{}'", word.replace('\'', "'\\''")),
+    }
+}
+
 pub(crate) trait Runner:
     Cmd<()> + Cmd<String> + Cmd<bool> + Clone + Send + Sync + 'static
 {
+    /// Whether commands other than queries are only recorded, in which case
+    /// nothing else should be changed either.
+    fn is_dry_run(&self) -> bool {
+        false
+    }
 }
 
 pub(crate) trait Cmd<T> {
@@ -63,3 +83,15 @@ macro_rules! cmd_verbose {
         })
     };
 }
+
+#[macro_export]
+macro_rules! cmd_query {
+    ($cmd:expr $(, args = [$($args:expr),*])? $(, env = [$($key:expr => $val:expr),*])?) => {
+        Type::Query({
+            let mut command = std::process::Command::new($cmd);
+            $( $(command.arg($args);)* )?
+            $( $(command.env($key, $val);)* )?
+            command
+        })
+    };
+}
@@ -0,0 +1,160 @@
+use super::{quote, Cmd, Runner, ShellRunner, Type};
+use crate::common::config::secrets::Secrets;
+use miette::Result;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    env, fmt,
+    process::Command,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// A command skipped in a dry run, with secrets masked.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct RecordedCommand {
+    pub(crate) program: String,
+    pub(crate) args: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) env: BTreeMap<String, String>,
+    /// Directory the command would run in.
+    pub(crate) cwd: String,
+}
+
+impl RecordedCommand {
+    fn new(command: &Command, secrets: &Secrets) -> Self {
+        let text = |s: &std::ffi::OsStr| secrets.mask(&s.to_string_lossy());
+        Self {
+            program: text(command.get_program()),
+            args: command.get_args().map(text).collect(),
+            env: command
+                .get_envs()
+                .filter_map(|(key, value)| Some((text(key), text(value?))))
+                .collect(),
+            cwd: env::current_dir()
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for RecordedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words: Vec<String> = self
+            .env
+            .iter()
+            .map(|(key, value)| format!("{key}={}", quote(value)))
+            .chain(std::iter::once(quote(&self.program)))
+            .chain(self.args.iter().map(|arg| quote(arg)))
+            .collect();
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// Runner for dry runs: [`Type::Query`] commands go to the wrapped runner,
+/// everything else is recorded instead of run.
+///
+/// Skipped commands that print the id of a new window or pane get a made-up one,
+/// so the muxer can carry on laying out the session.
+#[derive(Clone, Debug)]
+pub(crate) struct RecordingRunner<R: Runner = ShellRunner> {
+    runner: Arc<R>,
+    recorded: Arc<Mutex<Vec<RecordedCommand>>>,
+    ids: Arc<AtomicUsize>,
+    /// Masked in the recorded commands.
+    secrets: Secrets,
+}
+
+impl RecordingRunner {
+    pub(crate) fn new(secrets: Secrets) -> Self {
+        Self::new_with_runner(ShellRunner::new(secrets.clone()), secrets)
+    }
+}
+
+impl<R: Runner> RecordingRunner<R> {
+    pub(crate) fn new_with_runner(runner: R, secrets: Secrets) -> Self {
+        Self {
+            runner: Arc::new(runner),
+            recorded: Arc::new(Mutex::new(vec![])),
+            ids: Arc::new(AtomicUsize::new(0)),
+            secrets,
+        }
+    }
+
+    /// The skipped commands, in the order they would have run.
+    pub(crate) fn recorded(&self) -> Vec<RecordedCommand> {
+        self.recorded
+            .lock()
+            .map(|recorded| recorded.clone())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, cmd: &Type) {
+        let (Type::Basic(command)
+        | Type::Verbose(command)
+        | Type::Forget(command)
+        | Type::Query(command)) = cmd;
+        if let Ok(mut recorded) = self.recorded.lock() {
+            recorded.push(RecordedCommand::new(command, &self.secrets));
+        }
+    }
+
+    /// Made-up id for commands printing the id of a window or pane.
+    fn fake_id(&self, cmd: &Type) -> Option<String> {
+        let (Type::Basic(command)
+        | Type::Verbose(command)
+        | Type::Forget(command)
+        | Type::Query(command)) = cmd;
+        let prefix = command.get_args().find_map(|arg| match arg.to_str() {
+            Some("#{window_id}" | "#I") => Some('@'),
+            Some("#{pane_id}" | "#P") => Some('%'),
+            _ => None,
+        })?;
+        Some(format!(
+            "{prefix}{}",
+            self.ids.fetch_add(1, Ordering::Relaxed) + 1
+        ))
+    }
+}
+
+impl<R: Runner> Runner for RecordingRunner<R> {
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+impl<R: Runner> Cmd<()> for RecordingRunner<R> {
+    fn run(&self, cmd: &Type) -> Result<()> {
+        if matches!(cmd, Type::Query(_)) {
+            return self.runner.as_ref().run(cmd);
+        }
+        self.record(cmd);
+        Ok(())
+    }
+}
+
+impl<R: Runner> Cmd<String> for RecordingRunner<R> {
+    fn run(&self, cmd: &Type) -> Result<String> {
+        if matches!(cmd, Type::Query(_)) {
+            // Windows and panes of a session yet to be created can't be looked up
+            return match Cmd::<String>::run(self.runner.as_ref(), cmd) {
+                Ok(output) if !output.is_empty() => Ok(output),
+                result => self.fake_id(cmd).map(Ok).unwrap_or(result),
+            };
+        }
+        self.record(cmd);
+        Ok(self.fake_id(cmd).unwrap_or_default())
+    }
+}
+
+impl<R: Runner> Cmd<bool> for RecordingRunner<R> {
+    fn run(&self, cmd: &Type) -> Result<bool> {
+        if matches!(cmd, Type::Query(_)) {
+            return self.runner.as_ref().run(cmd);
+        }
+        self.record(cmd);
+        Ok(true)
+    }
+}
@@ -1,6 +1,7 @@
 use super::Cmd;
 use super::Runner;
 use super::Type;
+use crate::common::config::secrets::Secrets;
 use miette::miette;
 use miette::Context;
 use miette::IntoDiagnostic;
@@ -13,7 +14,10 @@ use std::{
 const PROMPT_CHAR: &str = "❯";
 
 #[derive(Clone, Debug)]
-pub(crate) struct ShellRunner;
+pub(crate) struct ShellRunner {
+    /// Masked in the commands this runner prints or reports.
+    secrets: Secrets,
+}
 
 impl Runner for ShellRunner {}
 
@@ -21,10 +25,10 @@ impl Cmd<()> for ShellRunner {
     fn run(&self, cmd: &Type) -> Result<()> {
         let (output, status) = self
             .run(cmd)
-            .wrap_err_with(|| format!("Failed to execute: {cmd}"))?;
+            .wrap_err_with(|| format!("Failed to execute: {}", self.describe(cmd)))?;
 
         if !status.success() {
-            return Err(miette!(output)).wrap_err_with(|| cmd.to_string());
+            return Err(miette!(output)).wrap_err_with(|| self.describe(cmd));
         }
 
         log::trace!("Result:() {output}");
@@ -36,10 +40,10 @@ impl Cmd<String> for ShellRunner {
     fn run(&self, cmd: &Type) -> Result<String> {
         let (output, status) = self
             .run(cmd)
-            .wrap_err_with(|| format!("Failed to execute: {cmd}"))?;
+            .wrap_err_with(|| format!("Failed to execute: {}", self.describe(cmd)))?;
 
         if !status.success() {
-            return Err(miette!(output)).wrap_err_with(|| cmd.to_string());
+            return Err(miette!(output)).wrap_err_with(|| self.describe(cmd));
         }
 
         log::trace!("Result:<String> {output}");
@@ -51,10 +55,10 @@ impl Cmd<bool> for ShellRunner {
     fn run(&self, cmd: &Type) -> Result<bool> {
         let (output, status) = self
             .run(cmd)
-            .wrap_err_with(|| format!("Failed to execute: {cmd}"))?;
+            .wrap_err_with(|| format!("Failed to execute: {}", self.describe(cmd)))?;
 
         if !status.success() {
-            return Err(miette!(output)).wrap_err_with(|| cmd.to_string());
+            return Err(miette!(output)).wrap_err_with(|| self.describe(cmd));
         }
 
         log::trace!("Result:<bool> {output}");
@@ -63,22 +67,27 @@ impl Cmd<bool> for ShellRunner {
 }
 
 impl ShellRunner {
-    pub(crate) fn new() -> Self {
-        Self {}
+    pub(crate) fn new(secrets: Secrets) -> Self {
+        Self { secrets }
+    }
+
+    /// `cmd` with the secrets known to this invocation masked.
+    fn describe(&self, cmd: &Type) -> String {
+        self.secrets.mask(&cmd.to_string())
     }
 
     fn run(&self, cmd: &Type) -> Result<(String, ExitStatus)> {
         let (oc, is_verbose, should_wait) = match cmd {
-            Type::Basic(c) => (c, false, true),
+            Type::Basic(c) | Type::Query(c) => (c, false, true),
             Type::Verbose(c) => (c, true, true),
             Type::Forget(c) => (c, true, false),
         };
 
         if is_verbose {
-            println!("{PROMPT_CHAR} {oc:?}");
+            println!("{PROMPT_CHAR} {}", self.secrets.mask(&format!("{oc:?}")));
         }
 
-        log::debug!("Running: {}", &cmd.to_string());
+        log::debug!("Running: {}", self.describe(cmd));
 
         let mut command = Command::new(oc.get_program());
         command.args(oc.get_args()).stderr(Stdio::piped());
@@ -66,7 +66,7 @@ impl Type {
     #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         match self {
-            Type::Basic(cmd) | Type::Verbose(cmd) | Type::Forget(cmd) => {
+            Type::Basic(cmd) | Type::Verbose(cmd) | Type::Forget(cmd) | Type::Query(cmd) => {
                 let envs: Vec<_> = cmd
                     .get_envs()
                     .filter_map(|(key, value)| {
@@ -88,3 +88,74 @@ impl Type {
         }
     }
 }
+
+#[test]
+fn recording_runner_records_skipped_commands() {
+    use super::RecordingRunner;
+    use crate::{cmd_basic, cmd_forget, cmd_query, common::config::secrets::Secrets};
+
+    let mut cmd_string = MockCmdStringMock::new();
+    let mut cmd_bool = MockCmdBoolMock::new();
+    cmd_bool
+        .expect_run()
+        .times(1)
+        .withf(|cmd| cmd.to_string() == "tmux has-session -t test")
+        .returning(|_| Err(miette::miette!("can't find session: test")));
+    cmd_string
+        .expect_run()
+        .times(1)
+        .withf(|cmd| cmd.to_string() == "tmux display-message -t test -p #I")
+        .returning(|_| Ok(String::new()));
+
+    let runner = RecordingRunner::new_with_runner(
+        RunnerMock {
+            cmd_unit: MockCmdUnitMock::new(),
+            cmd_string,
+            cmd_bool,
+        },
+        Secrets::default(),
+    );
+
+    // Queries are run, everything else is recorded
+    assert!(runner.is_dry_run());
+    let exists: Result<bool> =
+        runner.run(&cmd_query!("tmux", args = ["has-session", "-t", "test"]));
+    assert!(exists.is_err());
+    let window: String = runner
+        .run(&cmd_query!(
+            "tmux",
+            args = ["display-message", "-t", "test", "-p", "#I"]
+        ))
+        .unwrap();
+    assert_eq!(window, "@1");
+    let pane: String = runner
+        .run(&cmd_basic!(
+            "tmux",
+            args = ["split-window", "-t", "test:@1", "-P", "-F", "#{pane_id}"]
+        ))
+        .unwrap();
+    assert_eq!(pane, "%2");
+    // Even if it looks like one, a command of the config isn't a query
+    let _: () = runner
+        .run(&cmd_basic!("cat", args = ["/dev/stdin"]))
+        .unwrap();
+    let _: () = runner
+        .run(&cmd_forget!("zellij", args = ["attach", "my session"], env = ["LAIO_VARS" => "a=b"]))
+        .unwrap();
+
+    let recorded = runner.recorded();
+    assert_eq!(recorded.len(), 3);
+    assert_eq!(
+        recorded[0].to_string(),
+        "tmux split-window -t test:@1 -P -F '#{pane_id}'"
+    );
+    assert_eq!(recorded[1].to_string(), "cat /dev/stdin");
+    assert_eq!(
+        recorded[2].to_string(),
+        "LAIO_VARS=a=b zellij attach 'my session'"
+    );
+    assert_eq!(
+        recorded[2].cwd,
+        std::env::current_dir().unwrap().to_string_lossy()
+    );
+}
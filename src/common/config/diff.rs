@@ -0,0 +1,322 @@
+//! Differences between a live session and the config it was started from.
+//!
+//! Windows are matched by name and panes by position, the same way a live session
+//! is overlaid onto its config. Only what an overlay would take over is reported,
+//! so focus, zoom and the commands of a pane with a script are left out.
+
+use std::{fmt, path::Path};
+
+use serde::Serialize;
+
+use crate::common::path::home_dir;
+
+use super::{
+    overlay::{or_single_pane, same_ratio, Overlay},
+    Command, FlexDirection, Pane, Session, Window,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Status {
+    /// Only in the session.
+    Added,
+    /// Only in the config.
+    Removed,
+    Changed,
+}
+
+impl Status {
+    fn marker(self) -> char {
+        match self {
+            Status::Added => '+',
+            Status::Removed => '-',
+            Status::Changed => '~',
+        }
+    }
+}
+
+/// A property of a window or pane that differs, `None` where it is not set.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Change {
+    pub(crate) field: &'static str,
+    pub(crate) config: Option<String>,
+    pub(crate) session: Option<String>,
+}
+
+impl Change {
+    fn new(field: &'static str, config: impl ToString, session: impl ToString) -> Self {
+        Self {
+            field,
+            config: Some(config.to_string()),
+            session: Some(session.to_string()),
+        }
+    }
+}
+
+/// A window or pane that differs, with the panes differing within it.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Node {
+    pub(crate) label: String,
+    pub(crate) status: Status,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) changes: Vec<Change>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: String, status: Status) -> Self {
+        Self {
+            label,
+            status,
+            changes: vec![],
+            children: vec![],
+        }
+    }
+
+    fn changed(label: String, changes: Vec<Change>, children: Vec<Node>) -> Option<Self> {
+        (!changes.is_empty() || !children.is_empty()).then_some(Self {
+            label,
+            status: Status::Changed,
+            changes,
+            children,
+        })
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        write!(f, "\n{indent}{} {}", self.status.marker(), self.label)?;
+        for change in &self.changes {
+            let value = |value: &Option<String>| value.clone().unwrap_or("none".to_string());
+            write!(
+                f,
+                "\n{indent}    {}: {} -> {}",
+                change.field,
+                value(&change.config),
+                value(&change.session)
+            )?;
+        }
+        self.children
+            .iter()
+            .try_for_each(|child| child.write(f, depth + 1))
+    }
+}
+
+/// Differences between session `session` and its config at `config`.
+#[derive(Debug, Serialize)]
+pub(crate) struct SessionDiff {
+    pub(crate) session: String,
+    pub(crate) config: String,
+    pub(crate) differs: bool,
+    pub(crate) windows: Vec<Node>,
+}
+
+impl SessionDiff {
+    pub(crate) fn new(session: &str, config: &Path, windows: Vec<Node>) -> Self {
+        Self {
+            session: session.to_string(),
+            config: config.to_string_lossy().to_string(),
+            differs: !windows.is_empty(),
+            windows,
+        }
+    }
+}
+
+impl fmt::Display for SessionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.differs {
+            return write!(f, "Session '{}' matches {}", self.session, self.config);
+        }
+        write!(
+            f,
+            "Session '{}' differs from {} (config -> session):",
+            self.session, self.config
+        )?;
+        self.windows
+            .iter()
+            .try_for_each(|window| window.write(f, 0))
+    }
+}
+
+/// Compares the `live` session with `original`, the config it was started from.
+///
+/// `session_path` is the absolute path of the original session.
+pub(crate) fn diff(original: &Session, session_path: &str, live: &Session) -> Vec<Node> {
+    let diff = Diff {
+        paths: Overlay {
+            home: home_dir().unwrap_or_default(),
+            live_path: live.path.clone(),
+        },
+    };
+
+    let mut unmatched: Vec<&Window> = original.windows.iter().collect();
+    let mut windows: Vec<Node> = live
+        .windows
+        .iter()
+        .filter_map(
+            |window| match unmatched.iter().position(|w| w.name == window.name) {
+                Some(index) => {
+                    let original_window = unmatched.remove(index);
+                    let window_path = original_window.effective_path(session_path);
+                    diff.window(original_window, window, &window_path)
+                }
+                None => Some(Node::new(window_label(window), Status::Added)),
+            },
+        )
+        .collect();
+    windows.extend(
+        unmatched
+            .into_iter()
+            .map(|window| Node::new(window_label(window), Status::Removed)),
+    );
+    windows
+}
+
+fn window_label(window: &Window) -> String {
+    format!("window '{}'", window.name)
+}
+
+struct Diff {
+    paths: Overlay,
+}
+
+impl Diff {
+    fn window(&self, original: &Window, live: &Window, window_path: &str) -> Option<Node> {
+        let (changes, children) = self.panes(
+            (&original.flex_direction, &or_single_pane(&original.panes)),
+            (&live.flex_direction, &or_single_pane(&live.panes)),
+            window_path,
+            "",
+        );
+        Node::changed(window_label(original), changes, children)
+    }
+
+    /// Compares the panes of a split by position. Panes are numbered after their
+    /// position in each split, prefixed with the number of the split pane.
+    fn panes(
+        &self,
+        (original_direction, original): (&FlexDirection, &[Pane]),
+        (live_direction, live): (&FlexDirection, &[Pane]),
+        window_path: &str,
+        prefix: &str,
+    ) -> (Vec<Change>, Vec<Node>) {
+        let mut changes = vec![];
+        if original.len() > 1 && live.len() > 1 && original_direction != live_direction {
+            changes.push(Change::new(
+                "flex_direction",
+                direction(original_direction),
+                direction(live_direction),
+            ));
+        }
+        if original.len() != live.len() {
+            changes.push(Change::new("panes", original.len(), live.len()));
+        } else if !same_ratio(&flex(original), &flex(live)) {
+            changes.push(Change::new("flex", shares(original), shares(live)));
+        }
+
+        let children = (0..original.len().max(live.len()))
+            .filter_map(|index| {
+                let number = format!("{prefix}{}", index + 1);
+                match (original.get(index), live.get(index)) {
+                    (Some(original), Some(live)) => self.pane(original, live, window_path, &number),
+                    (Some(_), None) => Some(Node::new(format!("pane {number}"), Status::Removed)),
+                    (None, Some(_)) => Some(Node::new(format!("pane {number}"), Status::Added)),
+                    (None, None) => None,
+                }
+            })
+            .collect();
+        (changes, children)
+    }
+
+    fn pane(&self, original: &Pane, live: &Pane, window_path: &str, number: &str) -> Option<Node> {
+        let (changes, children) = if original.panes.is_empty() && live.panes.is_empty() {
+            (self.leaf(original, live, window_path), vec![])
+        } else {
+            // A pane split on one side only is compared with the first pane of the split
+            self.panes(
+                (&original.flex_direction, or_self(original)),
+                (&live.flex_direction, or_self(live)),
+                window_path,
+                &format!("{number}."),
+            )
+        };
+        Node::changed(format!("pane {number}"), changes, children)
+    }
+
+    fn leaf(&self, original: &Pane, live: &Pane, window_path: &str) -> Vec<Change> {
+        let mut changes = vec![];
+
+        if live.name.is_some() && live.name != original.name {
+            changes.push(Change {
+                field: "name",
+                config: original.name.clone(),
+                session: live.name.clone(),
+            });
+        }
+
+        let live_path = self.paths.live_path(&live.path);
+        if live_path != self.paths.absolute(&original.path, window_path) {
+            changes.push(Change::new(
+                "path",
+                &original.path,
+                self.paths.relative(&live_path, window_path),
+            ));
+        }
+
+        // Commands that finished leave the shell behind, so only a program other than
+        // the configured ones counts. Scripts and variables can't be compared.
+        let comparable = original.script.is_none()
+            && !original.commands.iter().any(|c| c.command.starts_with('$'));
+        if let Some(command) = live.commands.last()
+            && comparable
+            && !original
+                .commands
+                .iter()
+                .any(|c| program(c) == program(command))
+        {
+            changes.push(Change {
+                field: "command",
+                config: original.commands.last().map(ToString::to_string),
+                session: Some(command.to_string()),
+            });
+        }
+
+        changes
+    }
+}
+
+fn or_self(pane: &Pane) -> &[Pane] {
+    if pane.panes.is_empty() {
+        std::slice::from_ref(pane)
+    } else {
+        &pane.panes
+    }
+}
+
+fn direction(direction: &FlexDirection) -> &'static str {
+    match direction {
+        FlexDirection::Row => "row",
+        FlexDirection::Column => "column",
+    }
+}
+
+fn flex(panes: &[Pane]) -> Vec<usize> {
+    panes.iter().map(|pane| pane.flex).collect()
+}
+
+/// Share of each pane in its split, such as `67% 33%`.
+fn shares(panes: &[Pane]) -> String {
+    let total: usize = panes.iter().map(|pane| pane.flex).sum();
+    panes
+        .iter()
+        .map(|pane| format!("{:.0}%", pane.flex as f64 * 100.0 / total.max(1) as f64))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn program(command: &Command) -> &str {
+    Path::new(&command.command)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(&command.command)
+}
@@ -0,0 +1,173 @@
+use super::diff::{diff, Change, SessionDiff, Status};
+use super::Session;
+use noyalib::compat::serde_yaml::from_str;
+use std::path::Path;
+
+const ORIGINAL: &str = "
+name: proj
+path: .
+windows:
+  - name: code
+    flex_direction: row
+    panes:
+      - flex: 2
+        name: editor
+        commands:
+          - command: nvim
+      - flex: 1
+        path: src
+        script: |
+          #!/usr/bin/env bash
+          cargo watch -x test
+  - name: logs
+";
+
+fn session(yaml: &str) -> Session {
+    from_str(yaml).unwrap()
+}
+
+#[test]
+fn diff_unchanged_session() {
+    let original = session(ORIGINAL);
+    let live = session(
+        "
+name: proj
+path: /work/proj
+windows:
+  - name: code
+    flex_direction: row
+    panes:
+      - flex: 5
+        name: editor
+        commands:
+          - command: /usr/bin/nvim
+            args: [main.rs]
+      - flex: 3
+        path: src
+        commands:
+          - command: cargo-watch
+  - name: logs
+",
+    );
+
+    let windows = diff(&original, "/work/proj", &live);
+    assert!(windows.is_empty());
+
+    let diff = SessionDiff::new("proj", Path::new("/cfg/proj.yaml"), windows);
+    assert!(!diff.differs);
+    assert_eq!(diff.to_string(), "Session 'proj' matches /cfg/proj.yaml");
+}
+
+#[test]
+fn diff_reports_windows_and_panes() {
+    let original = session(ORIGINAL);
+    let live = session(
+        "
+name: proj
+path: /work/proj
+windows:
+  - name: code
+    flex_direction: row
+    panes:
+      - flex: 1
+        name: notes
+        commands:
+          - command: htop
+      - flex: 1
+        flex_direction: column
+        panes:
+          - flex: 1
+            path: /work/proj/src
+          - flex: 1
+            path: /elsewhere
+  - name: shell
+",
+    );
+
+    let windows = diff(&original, "/work/proj", &live);
+
+    assert_eq!(windows.len(), 3);
+    assert_eq!(windows[1].label, "window 'shell'");
+    assert_eq!(windows[1].status, Status::Added);
+    assert_eq!(windows[2].label, "window 'logs'");
+    assert_eq!(windows[2].status, Status::Removed);
+
+    let code = &windows[0];
+    assert_eq!(code.status, Status::Changed);
+    assert_eq!(
+        code.changes,
+        vec![Change {
+            field: "flex",
+            config: Some("67% 33%".to_string()),
+            session: Some("50% 50%".to_string()),
+        }]
+    );
+
+    let editor = &code.children[0];
+    assert_eq!(editor.label, "pane 1");
+    let fields: Vec<&str> = editor.changes.iter().map(|c| c.field).collect();
+    assert_eq!(fields, vec!["name", "command"]);
+    assert_eq!(editor.changes[1].config.as_deref(), Some("nvim"));
+    assert_eq!(editor.changes[1].session.as_deref(), Some("htop"));
+
+    // The split pane keeps its first pane in place, the second one is new
+    let split = &code.children[1];
+    assert_eq!(split.label, "pane 2");
+    assert_eq!(split.changes[0].field, "panes");
+    assert_eq!(split.children.len(), 1);
+    assert_eq!(split.children[0].label, "pane 2.2");
+    assert_eq!(split.children[0].status, Status::Added);
+
+    let diff = SessionDiff::new("proj", Path::new("/cfg/proj.yaml"), windows);
+    assert!(diff.differs);
+    assert_eq!(
+        diff.to_string(),
+        "Session 'proj' differs from /cfg/proj.yaml (config -> session):
+~ window 'code'
+    flex: 67% 33% -> 50% 50%
+  ~ pane 1
+      name: editor -> notes
+      command: nvim -> htop
+  ~ pane 2
+      panes: 1 -> 2
+    + pane 2.2
++ window 'shell'
+- window 'logs'"
+    );
+}
+
+#[test]
+fn diff_reports_paths() {
+    let original = session(ORIGINAL);
+    let live = session(
+        "
+name: proj
+path: /work/proj
+windows:
+  - name: code
+    flex_direction: column
+    panes:
+      - flex: 2
+        name: editor
+      - flex: 1
+        path: /work/proj/docs
+  - name: logs
+    panes:
+      - path: /tmp
+",
+    );
+
+    let windows = diff(&original, "/work/proj", &live);
+
+    let code = &windows[0];
+    assert_eq!(code.changes[0].field, "flex_direction");
+    let path = &code.children[0].changes[0];
+    assert_eq!(code.children[0].label, "pane 2");
+    assert_eq!(path.field, "path");
+    assert_eq!(path.config.as_deref(), Some("src"));
+    assert_eq!(path.session.as_deref(), Some("docs"));
+
+    let logs = &windows[1];
+    assert_eq!(logs.children[0].changes[0].config.as_deref(), Some("."));
+    assert_eq!(logs.children[0].changes[0].session.as_deref(), Some("/tmp"));
+}
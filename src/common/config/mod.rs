@@ -1,4 +1,7 @@
+pub(crate) mod diff;
 mod model;
+pub(crate) mod overlay;
+pub mod secrets;
 pub mod template;
 pub(crate) mod util;
 
@@ -6,11 +9,17 @@ pub mod variables;
 
 pub(crate) use model::command::Command;
 pub(crate) use model::flex_direction::FlexDirection;
-pub(crate) use model::pane::Pane;
+pub(crate) use model::pane::{leaf_panes, leaf_panes_mut, Pane};
 pub(crate) use model::script::Script;
 pub(crate) use model::session::Session;
 pub(crate) use model::window::Window;
 
+#[cfg(test)]
+mod diff_test;
+#[cfg(test)]
+mod overlay_test;
+#[cfg(test)]
+mod secrets_test;
 #[cfg(test)]
 mod template_test;
 #[cfg(test)]
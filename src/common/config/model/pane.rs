@@ -39,17 +39,45 @@ fn if_is_default_path(value: &str) -> bool {
 
 impl Pane {
     pub(crate) fn first_leaf_path(&self) -> Option<&String> {
-        if self.panes.is_empty() {
-            return Some(&self.path);
-        }
-        for pane in &self.panes {
-            if let Some(path) = pane.first_leaf_path() {
-                return Some(path);
-            }
-        }
-        None
+        self.leaves().next().map(|pane| &pane.path)
     }
+
+    /// The pane itself if it isn't split, or else the panes it is split into that
+    /// aren't, in the order they are laid out.
+    pub(crate) fn leaves(&self) -> impl Iterator<Item = &Pane> {
+        leaf_panes(std::slice::from_ref(self))
+    }
+}
+
+/// Panes of `panes` that aren't split, in the order they are laid out, which is
+/// the order tmux numbers them in.
+pub(crate) fn leaf_panes(panes: &[Pane]) -> Box<dyn Iterator<Item = &Pane> + '_> {
+    Box::new(
+        panes
+            .iter()
+            .flat_map(|pane| -> Box<dyn Iterator<Item = &Pane>> {
+                match pane.panes.is_empty() {
+                    true => Box::new(std::iter::once(pane)),
+                    false => leaf_panes(&pane.panes),
+                }
+            }),
+    )
 }
+
+/// Mutable [`leaf_panes`].
+pub(crate) fn leaf_panes_mut(panes: &mut [Pane]) -> Box<dyn Iterator<Item = &mut Pane> + '_> {
+    Box::new(
+        panes
+            .iter_mut()
+            .flat_map(|pane| -> Box<dyn Iterator<Item = &mut Pane>> {
+                match pane.panes.is_empty() {
+                    true => Box::new(std::iter::once(pane)),
+                    false => leaf_panes_mut(&mut pane.panes),
+                }
+            }),
+    )
+}
+
 pub(crate) fn count_matching_panes(panes: &[Pane], predicate: &impl Fn(&Pane) -> bool) -> usize {
     panes.iter().fold(0, |acc, pane| {
         acc + usize::from(predicate(pane)) + count_matching_panes(&pane.panes, predicate)
@@ -22,10 +22,13 @@ impl Script {
         result.iter().map(|b| format!("{:02x}", b)).collect()
     }
 
+    fn path(&self) -> PathBuf {
+        std::env::temp_dir().join(format!("laio-{}", self.checksum()))
+    }
+
     pub(crate) fn script_to_path(&self) -> Result<PathBuf> {
         let checksum = self.checksum();
-        let mut path = std::env::temp_dir();
-        path.push(format!("laio-{checksum}"));
+        let path = self.path();
 
         if path.exists() {
             let mut file = File::open(&path).into_diagnostic()?;
@@ -55,9 +58,15 @@ impl Script {
         Ok(path)
     }
 
-    pub(crate) fn to_cmd(&self) -> Result<Command> {
+    /// Command running the script. In a dry run the script isn't written, only its
+    /// path is used.
+    pub(crate) fn to_cmd(&self, dry_run: bool) -> Result<Command> {
+        let path = match dry_run {
+            true => self.path(),
+            false => self.script_to_path()?,
+        };
         Ok(Command {
-            command: self.script_to_path()?.to_string_lossy().to_string(),
+            command: path.to_string_lossy().to_string(),
             args: vec![],
         })
     }
@@ -2,16 +2,21 @@ use super::{
     command::Command, common::default_path, pane::count_matching_panes, pane::Pane, script::Script,
     window::Window,
 };
-use crate::common::config::{template, variables::parse_variables};
+use crate::common::config::{secrets::Secrets, template, variables::parse_variables};
 use crate::common::path::to_absolute_path;
 use miette::{bail, IntoDiagnostic, Result};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs::read_to_string, path::Path};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct Session {
     pub(crate) name: String,
+    /// Shown next to the name in `laio list` and the picker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tags: Vec<String>,
     #[serde(default = "default_path")]
     pub(crate) path: String,
     #[serde(default, alias = "commands", skip_serializing_if = "Vec::is_empty")]
@@ -32,14 +37,36 @@ pub(crate) struct Session {
 }
 
 impl Session {
-    pub(crate) fn from_config(config: &Path, variables: Option<&[String]>) -> Result<Session> {
+    pub(crate) fn from_config(
+        config: &Path,
+        variables: Option<&[String]>,
+        search_path: &[String],
+        secrets: &Secrets,
+    ) -> Result<Session> {
+        let mut session = Self::render(config, variables, search_path, secrets)?;
+        session.path = session.absolute_path(config)?;
+
+        log::debug!("Final session path: {}", session.path);
+        Ok(session)
+    }
+
+    /// Renders and validates a config, leaving the session path as written. Partials
+    /// are looked up from the directory of `search_path` holding the config.
+    pub(crate) fn render(
+        config: &Path,
+        variables: Option<&[String]>,
+        search_path: &[String],
+        secrets: &Secrets,
+    ) -> Result<Session> {
         let session_config = read_to_string(config).into_diagnostic()?;
 
         // Parse variables and render template
-        let var_map = parse_variables(variables.unwrap_or(&[]))?;
-        let rendered_config = template::render(&session_config, &var_map)?;
+        let var_map = parse_variables(variables.unwrap_or(&[]), secrets)?;
+        let partials_dirs = template::templates_dirs(config, search_path);
+        let rendered_config =
+            template::render(&session_config, &var_map, &partials_dirs, secrets)?;
 
-        let mut session: Session =
+        let session: Session =
             noyalib::compat::serde_yaml::from_str(&rendered_config).map_err(|e| {
                 miette::Report::msg(format!(
                     "Failed to parse config: {:?}\n\n{}",
@@ -51,7 +78,12 @@ impl Session {
         session.validate_exclusive_pane_property(|p| p.focus, "focus")?;
         session.validate_window_focus()?;
 
-        let session_path = if session.path.starts_with('.') {
+        Ok(session)
+    }
+
+    /// Absolute session path, with relative paths resolved against the directory of `config`.
+    pub(crate) fn absolute_path(&self, config: &Path) -> Result<String> {
+        let session_path = if self.path.starts_with('.') {
             let parent = config
                 .parent()
                 .ok_or_else(|| miette::miette!("Config path has no parent directory: {:?}", config))?
@@ -60,13 +92,10 @@ impl Session {
 
             to_absolute_path(parent)?
         } else {
-            to_absolute_path(&session.path)?
+            to_absolute_path(&self.path)?
         };
 
-        session.path = session_path.to_string_lossy().to_string();
-
-        log::debug!("Final session path: {}", session.path);
-        Ok(session)
+        Ok(session_path.to_string_lossy().to_string())
     }
 
     fn validate_exclusive_pane_property(
@@ -1,5 +1,6 @@
 use super::pane::{count_matching_panes, Pane};
 use super::session::Session;
+use crate::common::config::secrets::Secrets;
 use std::path::PathBuf;
 
 #[test]
@@ -11,7 +12,8 @@ fn test_from_config_with_variables() {
         "window_name=editor".to_string(),
     ];
 
-    let session = Session::from_config(&config_path, Some(&variables)).unwrap();
+    let session =
+        Session::from_config(&config_path, Some(&variables), &[], &Secrets::default()).unwrap();
 
     assert_eq!(session.name, "my-project");
     assert_eq!(session.path, "/home/user/projects");
@@ -23,7 +25,7 @@ fn test_from_config_with_variables() {
 fn test_from_config_with_defaults() {
     let config_path = PathBuf::from("src/common/config/test/templated.yaml");
 
-    let session = Session::from_config(&config_path, None).unwrap();
+    let session = Session::from_config(&config_path, None, &[], &Secrets::default()).unwrap();
 
     // Should use default values from template
     assert_eq!(session.name, "test-session");
@@ -36,7 +38,8 @@ fn test_from_config_partial_variables() {
     let config_path = PathBuf::from("src/common/config/test/templated.yaml");
     let variables = vec!["name=partial-test".to_string()];
 
-    let session = Session::from_config(&config_path, Some(&variables)).unwrap();
+    let session =
+        Session::from_config(&config_path, Some(&variables), &[], &Secrets::default()).unwrap();
 
     assert_eq!(session.name, "partial-test");
     assert_eq!(session.path, "/tmp"); // Uses default
@@ -54,7 +57,8 @@ fn test_from_config_with_array_variables() {
         "projects=cli".to_string(),
     ];
 
-    let session = Session::from_config(&config_path, Some(&variables)).unwrap();
+    let session =
+        Session::from_config(&config_path, Some(&variables), &[], &Secrets::default()).unwrap();
 
     assert_eq!(session.name, "multi-env");
     assert_eq!(session.path, "/home/dev");
@@ -72,7 +76,7 @@ fn test_from_config_with_array_variables() {
 #[test]
 fn test_window_level_path() {
     let config_path = PathBuf::from("src/common/config/test/window_path.yaml");
-    let session = Session::from_config(&config_path, None).unwrap();
+    let session = Session::from_config(&config_path, None, &[], &Secrets::default()).unwrap();
 
     assert_eq!(session.path, "/home/dev");
 
@@ -113,16 +117,32 @@ fn test_count_matching_panes_empty() {
 #[test]
 fn test_count_matching_panes_flat() {
     let config_path = PathBuf::from("src/common/config/test/valid.yaml");
-    let session = Session::from_config(&config_path, None).unwrap();
+    let session = Session::from_config(&config_path, None, &[], &Secrets::default()).unwrap();
     // valid.yaml has exactly one zoomed pane in the first window
     let count = count_matching_panes(&session.windows[0].panes, &|p: &Pane| p.zoom);
     assert!(count <= 1);
 }
 
+#[test]
+fn test_leaf_panes_in_layout_order() {
+    let config_path = PathBuf::from("src/common/config/test/valid.yaml");
+    let mut session = Session::from_config(&config_path, None, &[], &Secrets::default()).unwrap();
+    let window = &mut session.windows[0];
+
+    // The split pane 'foo' is left out for the two panes it holds
+    let flexes: Vec<usize> = window.leaf_panes().map(|p| p.flex).collect();
+    assert_eq!(flexes, vec![1, 2, 1]);
+    assert!(window.leaf_panes().next().unwrap().focus);
+    assert_eq!(window.panes[0].leaves().count(), 2);
+
+    window.leaf_panes_mut().nth(1).unwrap().zoom = true;
+    assert!(window.panes[0].panes[1].zoom);
+}
+
 #[test]
 fn test_multi_zoom_rejected() {
     let config_path = PathBuf::from("src/common/config/test/multi_zoom.yaml");
-    let result = Session::from_config(&config_path, None);
+    let result = Session::from_config(&config_path, None, &[], &Secrets::default());
     assert!(result.is_err());
     let err = result.unwrap_err().to_string();
     assert!(err.contains("zoom"), "Expected zoom error, got: {err}");
@@ -131,8 +151,97 @@ fn test_multi_zoom_rejected() {
 #[test]
 fn test_multi_focus_rejected() {
     let config_path = PathBuf::from("src/common/config/test/multi_focus.yaml");
-    let result = Session::from_config(&config_path, None);
+    let result = Session::from_config(&config_path, None, &[], &Secrets::default());
     assert!(result.is_err());
     let err = result.unwrap_err().to_string();
     assert!(err.contains("focus"), "Expected focus error, got: {err}");
 }
+
+#[test]
+fn test_from_config_with_partials() {
+    let config_path = PathBuf::from("src/common/config/test/partials.yaml");
+    let session = Session::from_config(&config_path, None, &[], &Secrets::default()).unwrap();
+
+    let panes = &session.windows[0].panes;
+    assert_eq!(panes.len(), 2);
+    assert_eq!(panes[0].name, Some("server-8080".to_string()));
+    assert_eq!(panes[0].flex, 2);
+    assert_eq!(panes[0].commands[0].command, "npm");
+    assert_eq!(panes[1].name, Some("shell".to_string()));
+}
+
+#[test]
+fn test_from_config_with_root_partials() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join("laio_test_root_partials");
+    let _ = fs::remove_dir_all(&dir);
+    for sub in [
+        "main/_templates",
+        "main/work/_templates",
+        "team/_templates",
+        "team/ops",
+    ] {
+        fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    let pane = |name: &str| format!("- name: {name}\n");
+    fs::write(dir.join("main/_templates/shell.yaml"), pane("root-shell")).unwrap();
+    fs::write(dir.join("main/_templates/editor.yaml"), pane("root-editor")).unwrap();
+    fs::write(
+        dir.join("main/work/_templates/editor.yaml"),
+        pane("work-editor"),
+    )
+    .unwrap();
+    fs::write(dir.join("team/_templates/shell.yaml"), pane("team-shell")).unwrap();
+    let config = "name: api\npath: /tmp\nwindows:\n  - name: main\n    panes:\n{% filter indent(width=6, first=true) %}{% include \"shell.yaml\" %}{% include \"editor.yaml\" %}{% endfilter %}\n";
+    fs::write(dir.join("main/work/api.yaml"), config).unwrap();
+    fs::write(
+        dir.join("team/ops/api.yaml"),
+        config.replace("{% include \"editor.yaml\" %}", ""),
+    )
+    .unwrap();
+
+    let search_path = vec![
+        dir.join("main").to_string_lossy().to_string(),
+        dir.join("team").to_string_lossy().to_string(),
+    ];
+    let pane_names = |config: &str| -> Vec<String> {
+        Session::from_config(&dir.join(config), None, &search_path, &Secrets::default())
+            .unwrap()
+            .windows[0]
+            .panes
+            .iter()
+            .filter_map(|p| p.name.clone())
+            .collect()
+    };
+
+    // Shared partials come from the root of the config's directory, the folder's
+    // own partials override them
+    assert_eq!(
+        pane_names("main/work/api.yaml"),
+        vec!["root-shell", "work-editor"]
+    );
+    assert_eq!(pane_names("team/ops/api.yaml"), vec!["team-shell"]);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_script_to_cmd_dry_run_writes_nothing() {
+    use super::script::Script;
+
+    let script: Script = noyalib::compat::serde_yaml::from_str(&format!(
+        "\"#!/bin/sh\\necho dry run {}\"",
+        std::process::id()
+    ))
+    .unwrap();
+
+    let cmd = script.to_cmd(true).unwrap();
+    assert!(cmd.command.contains("laio-"));
+    assert!(!PathBuf::from(&cmd.command).exists());
+
+    let written = script.to_cmd(false).unwrap();
+    assert_eq!(written.command, cmd.command);
+    assert!(PathBuf::from(&written.command).exists());
+    let _ = std::fs::remove_file(&written.command);
+}
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use crate::common::path::sanitize_path;
 
-use super::{flex_direction::FlexDirection, pane::Pane};
+use super::{
+    flex_direction::FlexDirection,
+    pane::{leaf_panes, leaf_panes_mut, Pane},
+};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct Window {
     pub(crate) name: String,
@@ -19,12 +22,17 @@ pub(crate) struct Window {
 
 impl Window {
     pub(crate) fn first_leaf_path(&self) -> Option<&String> {
-        for pane in &self.panes {
-            if let Some(path) = pane.first_leaf_path() {
-                return Some(path);
-            }
-        }
-        None
+        self.leaf_panes().next().map(|pane| &pane.path)
+    }
+
+    /// Panes that aren't split, in the order they are laid out.
+    pub(crate) fn leaf_panes(&self) -> impl Iterator<Item = &Pane> {
+        leaf_panes(&self.panes)
+    }
+
+    /// Mutable [`Self::leaf_panes`].
+    pub(crate) fn leaf_panes_mut(&mut self) -> impl Iterator<Item = &mut Pane> {
+        leaf_panes_mut(&mut self.panes)
     }
 
     /// Effective working directory for this window's panes.
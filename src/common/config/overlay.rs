@@ -0,0 +1,285 @@
+//! Overlay of a live session onto the config it was started from.
+//!
+//! A session exported from the multiplexer loses everything that is not visible
+//! in it, such as hooks, scripts and the exact commands of a pane. Overlaying keeps
+//! the original config and only takes over what changed in the live session:
+//! added or removed windows and panes, new split ratios and changed paths.
+
+use std::collections::HashMap;
+
+use crate::common::path::{home_dir, sanitize_path};
+
+use super::{FlexDirection, Pane, Session, Window};
+
+/// Largest difference in a pane's share of its split still seen as the same ratio.
+/// Sizes read back from the multiplexer are rounded, so they never match exactly.
+const RATIO_TOLERANCE: f64 = 0.05;
+
+/// Overlays the `live` session onto `original`, the config it was started from.
+///
+/// `session_path` is the absolute path of the original session. Windows are matched
+/// by name and panes by position. Focus and zoom are taken from the original, as
+/// they change with every move in the session.
+pub(crate) fn overlay(original: &Session, session_path: &str, live: &Session) -> Session {
+    let overlay = Overlay {
+        home: home_dir().unwrap_or_default(),
+        live_path: live.path.clone(),
+    };
+
+    let mut unmatched: Vec<&Window> = original.windows.iter().collect();
+    let windows = live
+        .windows
+        .iter()
+        .map(
+            |window| match unmatched.iter().position(|w| w.name == window.name) {
+                Some(index) => {
+                    let original_window = unmatched.remove(index);
+                    let window_path = original_window.effective_path(session_path);
+                    overlay.window(original_window, window, &window_path)
+                }
+                None => overlay.added_window(window, session_path),
+            },
+        )
+        .collect();
+
+    Session {
+        env: overlay_env(&original.env, &live.env),
+        windows,
+        ..original.clone()
+    }
+}
+
+/// Keeps the original value of every variable still set, adds new ones and drops
+/// removed ones. A multiplexer that cannot read the environment leaves it untouched.
+fn overlay_env(
+    original: &HashMap<String, String>,
+    live: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    if live.is_empty() {
+        return original.clone();
+    }
+    live.iter()
+        .map(|(name, value)| {
+            let value = original.get(name).unwrap_or(value);
+            (name.clone(), value.clone())
+        })
+        .collect()
+}
+
+pub(super) struct Overlay {
+    pub(super) home: String,
+    /// Path the live pane paths are relative to.
+    pub(super) live_path: String,
+}
+
+impl Overlay {
+    fn window(&self, original: &Window, live: &Window, window_path: &str) -> Window {
+        // A window with a single, plain pane is exported without panes
+        let unchanged_single_pane = original.panes.is_empty()
+            && match live.panes.as_slice() {
+                [] => true,
+                [pane] => {
+                    pane.panes.is_empty()
+                        && pane.commands.is_empty()
+                        && pane.name.is_none()
+                        && pane.style.is_none()
+                        && self.live_path(&pane.path) == self.absolute(".", window_path)
+                }
+                _ => false,
+            };
+        if unchanged_single_pane {
+            return original.clone();
+        }
+
+        let flex_direction = if live.panes.len() > 1 {
+            live.flex_direction.clone()
+        } else {
+            original.flex_direction.clone()
+        };
+
+        Window {
+            flex_direction,
+            panes: self.panes(
+                &or_single_pane(&original.panes),
+                &or_single_pane(&live.panes),
+                window_path,
+            ),
+            ..original.clone()
+        }
+    }
+
+    fn added_window(&self, live: &Window, session_path: &str) -> Window {
+        Window {
+            // The original config decides which window has focus
+            focus: false,
+            panes: live
+                .panes
+                .iter()
+                .map(|pane| self.added_pane(pane, session_path))
+                .collect(),
+            ..live.clone()
+        }
+    }
+
+    /// Pairs the panes of a split by position. Flex values of the original are kept
+    /// as long as the split has the same panes in about the same ratio.
+    fn panes(&self, original: &[Pane], live: &[Pane], window_path: &str) -> Vec<Pane> {
+        let keep_flex = original.len() == live.len()
+            && same_ratio(
+                &original.iter().map(|p| p.flex).collect::<Vec<_>>(),
+                &live.iter().map(|p| p.flex).collect::<Vec<_>>(),
+            );
+
+        live.iter()
+            .enumerate()
+            .map(|(index, live_pane)| match original.get(index) {
+                Some(original_pane) => Pane {
+                    flex: if keep_flex {
+                        original_pane.flex
+                    } else {
+                        live_pane.flex
+                    },
+                    ..self.pane(original_pane, live_pane, window_path)
+                },
+                None => Pane {
+                    focus: false,
+                    zoom: false,
+                    ..self.added_pane(live_pane, window_path)
+                },
+            })
+            .collect()
+    }
+
+    fn pane(&self, original: &Pane, live: &Pane, window_path: &str) -> Pane {
+        match (original.panes.is_empty(), live.panes.is_empty()) {
+            // Split in the live session: the original pane stays the first one
+            (true, false) => Pane {
+                flex_direction: live.flex_direction.clone(),
+                flex: original.flex,
+                name: None,
+                path: ".".to_string(),
+                style: None,
+                commands: vec![],
+                script: None,
+                panes: self.panes(std::slice::from_ref(original), &live.panes, window_path),
+                zoom: false,
+                focus: false,
+            },
+            (false, false) => Pane {
+                flex_direction: live.flex_direction.clone(),
+                panes: self.panes(&original.panes, &live.panes, window_path),
+                ..original.clone()
+            },
+            // Panes closed down to one: it continues the first original pane
+            (false, true) => Pane {
+                flex: original.flex,
+                ..self.pane(
+                    original.leaves().next().unwrap_or(original),
+                    live,
+                    window_path,
+                )
+            },
+            (true, true) => {
+                let has_commands = !original.commands.is_empty() || original.script.is_some();
+                Pane {
+                    name: live.name.clone().or_else(|| original.name.clone()),
+                    style: live.style.clone().or_else(|| original.style.clone()),
+                    path: self.leaf_path(&original.path, &live.path, window_path),
+                    commands: if has_commands {
+                        original.commands.clone()
+                    } else {
+                        live.commands.clone()
+                    },
+                    ..original.clone()
+                }
+            }
+        }
+    }
+
+    fn added_pane(&self, live: &Pane, window_path: &str) -> Pane {
+        Pane {
+            path: if live.panes.is_empty() {
+                self.relative(&self.live_path(&live.path), window_path)
+            } else {
+                live.path.clone()
+            },
+            panes: live
+                .panes
+                .iter()
+                .map(|pane| self.added_pane(pane, window_path))
+                .collect(),
+            ..live.clone()
+        }
+    }
+
+    /// The original path if the pane is still there, the live one otherwise.
+    fn leaf_path(&self, original: &str, live: &str, window_path: &str) -> String {
+        let live = self.live_path(live);
+        if live == self.absolute(original, window_path) {
+            original.to_string()
+        } else {
+            self.relative(&live, window_path)
+        }
+    }
+
+    pub(super) fn live_path(&self, path: &str) -> String {
+        self.absolute(path, &self.live_path)
+    }
+
+    pub(super) fn absolute(&self, path: &str, parent: &str) -> String {
+        let path = sanitize_path(&path.to_string(), &parent.to_string());
+        match path.strip_prefix('~') {
+            Some(rest) => format!("{}{rest}", self.home),
+            None => path,
+        }
+        .trim_end_matches('/')
+        .to_string()
+    }
+
+    /// `path` relative to the window path, or with `~` for the home directory if it
+    /// is outside of it.
+    pub(super) fn relative(&self, path: &str, window_path: &str) -> String {
+        let window_path = self.absolute(".", window_path);
+        if path == window_path {
+            return ".".to_string();
+        }
+        if let Some(rest) = path.strip_prefix(&format!("{window_path}/")) {
+            return rest.to_string();
+        }
+        match path.strip_prefix(&self.home) {
+            Some(rest) if !self.home.is_empty() && rest.starts_with('/') => format!("~{rest}"),
+            _ => path.to_string(),
+        }
+    }
+}
+
+/// A window without panes has a single pane in the window path.
+pub(crate) fn or_single_pane(panes: &[Pane]) -> Vec<Pane> {
+    if !panes.is_empty() {
+        return panes.to_vec();
+    }
+    vec![Pane {
+        flex_direction: FlexDirection::default(),
+        flex: 1,
+        name: None,
+        path: ".".to_string(),
+        style: None,
+        commands: vec![],
+        script: None,
+        panes: vec![],
+        zoom: false,
+        focus: false,
+    }]
+}
+
+pub(super) fn same_ratio(original: &[usize], live: &[usize]) -> bool {
+    let original_total: usize = original.iter().sum();
+    let live_total: usize = live.iter().sum();
+    if original_total == 0 || live_total == 0 {
+        return original_total == live_total;
+    }
+    original.iter().zip(live).all(|(&o, &l)| {
+        let share = |flex: usize, total: usize| flex as f64 / total as f64;
+        (share(o, original_total) - share(l, live_total)).abs() <= RATIO_TOLERANCE
+    })
+}
@@ -0,0 +1,162 @@
+use super::overlay::overlay;
+use super::Session;
+use noyalib::compat::serde_yaml::{from_str, to_string};
+
+const ORIGINAL: &str = "
+name: proj
+path: .
+startup:
+  - command: docker
+    args: [compose, up, -d]
+env:
+  RUST_LOG: debug
+windows:
+  - name: code
+    flex_direction: row
+    focus: true
+    panes:
+      - flex: 2
+        name: editor
+        focus: true
+        commands:
+          - command: nvim
+      - flex: 1
+        path: src
+        script: |
+          #!/usr/bin/env bash
+          cargo watch -x test
+  - name: logs
+";
+
+fn session(yaml: &str) -> Session {
+    from_str(yaml).unwrap()
+}
+
+#[test]
+fn overlay_unchanged_session_keeps_config() {
+    let original = session(ORIGINAL);
+    let live = session(
+        "
+name: proj
+path: /work/proj
+env:
+  RUST_LOG: debug
+windows:
+  - name: code
+    flex_direction: row
+    panes:
+      - flex: 5
+        name: editor
+        commands:
+          - command: /usr/bin/nvim
+      - flex: 3
+        path: src
+        commands:
+          - command: cargo-watch
+  - name: logs
+    focus: true
+",
+    );
+
+    let merged = overlay(&original, "/work/proj", &live);
+
+    assert_eq!(to_string(&merged).unwrap(), to_string(&original).unwrap());
+}
+
+#[test]
+fn overlay_takes_over_changes() {
+    let original = session(ORIGINAL);
+    let live = session(
+        "
+name: proj
+path: /work/proj
+env:
+  RUST_LOG: debug
+  EXTRA: '1'
+windows:
+  - name: shell
+    focus: true
+    panes:
+      - path: tmp
+  - name: code
+    flex_direction: row
+    panes:
+      - flex: 1
+        name: editor
+      - flex: 1
+        path: docs
+      - flex: 1
+        flex_direction: column
+        panes:
+          - flex: 1
+          - flex: 1
+            commands:
+              - command: htop
+",
+    );
+
+    let merged = overlay(&original, "/work/proj", &live);
+
+    // Hooks are kept, the environment follows the live session
+    assert_eq!(merged.startup, original.startup);
+    assert_eq!(
+        merged.env.get("RUST_LOG").map(String::as_str),
+        Some("debug")
+    );
+    assert_eq!(merged.env.get("EXTRA").map(String::as_str), Some("1"));
+
+    // The closed window is dropped, the new one never takes focus from the config
+    let names: Vec<&str> = merged.windows.iter().map(|w| w.name.as_str()).collect();
+    assert_eq!(names, vec!["shell", "code"]);
+    assert!(!merged.windows[0].focus);
+    assert_eq!(merged.windows[0].panes[0].path, "tmp");
+    assert!(merged.windows[1].focus);
+
+    let panes = &merged.windows[1].panes;
+    assert_eq!(panes.len(), 3);
+    // A new pane changes the ratio, so the live flex values are used
+    assert!(panes.iter().all(|p| p.flex == 1));
+    // Commands, scripts and focus stay with the original panes
+    assert_eq!(panes[0].commands[0].command, "nvim");
+    assert!(panes[0].focus);
+    assert_eq!(panes[1].path, "docs");
+    assert!(panes[1].script.is_some());
+    // The added split is taken as is
+    assert_eq!(panes[2].panes.len(), 2);
+    assert_eq!(panes[2].panes[1].commands[0].command, "htop");
+}
+
+#[test]
+fn overlay_split_pane_keeps_original_first() {
+    let original = session(ORIGINAL);
+    let live = session(
+        "
+name: proj
+path: /work/proj
+windows:
+  - name: code
+    flex_direction: row
+    panes:
+      - flex: 2
+        flex_direction: column
+        panes:
+          - flex: 1
+            name: editor
+          - flex: 1
+            path: /elsewhere
+      - flex: 1
+        path: src
+  - name: logs
+",
+    );
+
+    let merged = overlay(&original, "/work/proj", &live);
+    let panes = &merged.windows[0].panes;
+
+    assert_eq!(panes[0].flex, 2);
+    assert_eq!(panes[0].panes.len(), 2);
+    assert_eq!(panes[0].panes[0].commands[0].command, "nvim");
+    assert_eq!(panes[0].panes[1].path, "/elsewhere");
+    // No live environment means the multiplexer could not read it
+    assert_eq!(merged.env, original.env);
+}
@@ -0,0 +1,194 @@
+//! Secret template variables.
+//!
+//! Secrets are template variables whose values must never be persisted in the
+//! session environment or shown in logs. They are either passed with
+//! `--secret-var` (stored as `name:secret=value`) or declared in a config with the
+//! `secret(name=..., command=...)` template function. Values resolved during an
+//! invocation are kept in its [`Secrets`] so they can be masked in any output.
+
+use inquire::Password;
+use miette::{bail, miette, IntoDiagnostic, Result};
+use std::{
+    collections::HashMap,
+    process::Command,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// Type suffix marking a variable as secret, e.g. `token:secret=...`.
+pub const SECRET_TYPE: &str = "secret";
+
+/// Replacement for secret values in output.
+pub const MASK: &str = "********";
+
+/// Values shorter than this aren't masked, they would match unrelated text.
+pub const MIN_MASK_LEN: usize = 4;
+
+/// Secrets known to one invocation of laio.
+///
+/// Clones share their secrets, so a runner holding a clone masks what rendering a
+/// config with another one resolved.
+#[derive(Clone, Debug, Default)]
+pub struct Secrets {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    values: HashMap<String, String>,
+    deferred: bool,
+}
+
+/// Restores whether secrets are resolved when dropped, even if rendering panics.
+struct Deferred<'a> {
+    secrets: &'a Secrets,
+    previous: bool,
+}
+
+impl Drop for Deferred<'_> {
+    fn drop(&mut self) {
+        self.secrets.state().deferred = self.previous;
+    }
+}
+
+/// Converts a `--secret-var` argument (`key=value`) into a secret variable
+/// (`key:secret=value`).
+pub fn secret_variable(var: &str) -> Result<String> {
+    let (key, value) = split_secret(var)?;
+    Ok(format!("{key}:{SECRET_TYPE}={value}"))
+}
+
+/// Appends `--secret-var` arguments to `variables` as secret variables.
+pub fn with_secret_variables(variables: &[String], secret_vars: &[String]) -> Result<Vec<String>> {
+    let mut all = variables.to_vec();
+    for var in secret_vars {
+        all.push(secret_variable(var)?);
+    }
+    Ok(all)
+}
+
+fn split_secret(var: &str) -> Result<(&str, &str)> {
+    let (key, value) = var.split_once('=').ok_or_else(|| {
+        miette!(
+            "Invalid secret variable format: '{}'. Expected format: key=value",
+            var
+        )
+    })?;
+    let key = key.trim();
+    if key.is_empty() || key.contains(':') {
+        bail!("Invalid secret variable: '{}' needs a plain key", var);
+    }
+    Ok((key, value.trim()))
+}
+
+impl Secrets {
+    fn state(&self) -> MutexGuard<'_, State> {
+        // The state stays consistent even if a holder of the lock panicked
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Makes secret values available without exposing them as template variables,
+    /// e.g. for `laio stop --secret-var`.
+    pub fn provide(&self, secret_vars: &[String]) -> Result<()> {
+        for var in secret_vars {
+            let (key, value) = split_secret(var)?;
+            self.insert(key, value);
+        }
+        Ok(())
+    }
+
+    /// Records a resolved secret so it can be reused and masked.
+    pub fn insert(&self, name: &str, value: &str) {
+        self.state()
+            .values
+            .insert(name.to_string(), value.to_string());
+    }
+
+    fn lookup(&self, name: &str) -> Option<String> {
+        self.state().values.get(name).cloned()
+    }
+
+    /// Runs `f` without resolving unknown secrets: commands aren't run and the user
+    /// isn't prompted, [`MASK`] is used instead. Useful when rendering configs only
+    /// to read their metadata, e.g. for listing.
+    pub fn without_resolving<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = std::mem::replace(&mut self.state().deferred, true);
+        let _deferred = Deferred {
+            secrets: self,
+            previous,
+        };
+        f()
+    }
+
+    /// Resolves a secret by name.
+    ///
+    /// Values already known win, then `command` is run through `sh -c` (e.g. a
+    /// password manager CLI), and finally the user is prompted.
+    pub fn resolve(&self, name: &str, command: Option<&str>) -> Result<String> {
+        if let Some(value) = self.lookup(name) {
+            return Ok(value);
+        }
+
+        if self.state().deferred {
+            return Ok(MASK.to_string());
+        }
+
+        let value = match command {
+            Some(command) => {
+                log::debug!("Resolving secret '{name}' from command");
+                let output = Command::new("sh")
+                    .args(["-c", command])
+                    .output()
+                    .into_diagnostic()?;
+                if !output.status.success() {
+                    bail!(
+                        "Command for secret '{}' failed: {}",
+                        name,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+                String::from_utf8_lossy(&output.stdout)
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string()
+            }
+            None => Password::new(&format!("Secret '{name}':"))
+                .without_confirmation()
+                .prompt()
+                .map_err(|e| {
+                    miette!("Secret '{}' is required but was not provided: {}", name, e)
+                })?,
+        };
+
+        self.insert(name, &value);
+        Ok(value)
+    }
+
+    /// Replaces every known secret value of at least [`MIN_MASK_LEN`] characters in
+    /// `text` with [`MASK`].
+    pub fn mask(&self, text: &str) -> String {
+        let state = self.state();
+        let mut values: Vec<&String> = state
+            .values
+            .values()
+            .filter(|v| v.chars().count() >= MIN_MASK_LEN)
+            .collect();
+        // Mask longer values first so overlapping secrets don't leak a suffix.
+        values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+
+        values.into_iter().fold(text.to_string(), |acc, value| {
+            acc.replace(value.as_str(), MASK)
+        })
+    }
+
+    /// Masks the environment variables of `env` that hold a secret in `rendered`,
+    /// the same environment rendered without resolving secrets. Their values are
+    /// recorded, so they are masked wherever else they show up.
+    pub fn mask_env(&self, env: &mut HashMap<String, String>, rendered: &HashMap<String, String>) {
+        for (name, value) in env.iter_mut() {
+            if rendered.get(name).is_some_and(|v| v.contains(MASK)) {
+                // Kept apart from the secrets resolved by their variable name
+                self.insert(&format!("env:{name}"), value);
+                *value = MASK.to_string();
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use super::secrets::{secret_variable, with_secret_variables, Secrets, MASK};
+use super::template::render;
+use super::variables::{is_secret_variable, parse_variables};
+use std::collections::HashMap;
+
+#[test]
+fn test_secret_variable() {
+    assert_eq!(
+        secret_variable("api_token=abc123").unwrap(),
+        "api_token:secret=abc123"
+    );
+    assert!(secret_variable("api_token").is_err());
+    assert!(secret_variable("api_token:int=1").is_err());
+    assert!(secret_variable("=abc").is_err());
+}
+
+#[test]
+fn test_with_secret_variables() {
+    let vars = vec!["name=test".to_string()];
+    let secret_vars = vec!["token=xyz".to_string()];
+
+    assert_eq!(
+        with_secret_variables(&vars, &secret_vars).unwrap(),
+        vec!["name=test".to_string(), "token:secret=xyz".to_string()]
+    );
+}
+
+#[test]
+fn test_is_secret_variable() {
+    assert!(is_secret_variable("token:secret=xyz"));
+    assert!(is_secret_variable("token:SECRET="));
+    assert!(!is_secret_variable("token=xyz"));
+    assert!(!is_secret_variable("token:str=xyz"));
+}
+
+#[test]
+fn test_parse_secret_variable_is_masked() {
+    let secrets = Secrets::default();
+    let vars = vec!["db_password:secret=hunter2-parse-test".to_string()];
+    let result = parse_variables(&vars, &secrets).unwrap();
+
+    assert_eq!(
+        result.get("db_password").unwrap().as_str(),
+        Some("hunter2-parse-test")
+    );
+    assert_eq!(
+        secrets.mask("psql --password=hunter2-parse-test"),
+        format!("psql --password={MASK}")
+    );
+}
+
+#[test]
+fn test_parse_withheld_secret_uses_provided_value() {
+    let secrets = Secrets::default();
+    secrets
+        .provide(&["stop_token=provided-stop-test".to_string()])
+        .unwrap();
+
+    let vars = vec!["stop_token:secret=".to_string()];
+    let result = parse_variables(&vars, &secrets).unwrap();
+
+    assert_eq!(
+        result.get("stop_token").unwrap().as_str(),
+        Some("provided-stop-test")
+    );
+}
+
+#[test]
+fn test_template_secret_from_command() {
+    let template = "token: {{ secret(name=\"cmd_token\", command=\"echo from-command-test\") }}";
+    let secrets = Secrets::default();
+    let result = render(template, &HashMap::new(), &[], &secrets).unwrap();
+
+    assert_eq!(result, "token: from-command-test");
+    assert_eq!(
+        secrets.resolve("cmd_token", None).unwrap(),
+        "from-command-test"
+    );
+    assert_eq!(secrets.mask(&result), format!("token: {MASK}"));
+
+    // Other invocations don't know it
+    assert_eq!(Secrets::default().mask(&result), result);
+}
+
+#[test]
+fn test_template_secret_failing_command() {
+    let template = "token: {{ secret(name=\"failing_token\", command=\"exit 1\") }}";
+
+    assert!(render(template, &HashMap::new(), &[], &Secrets::default()).is_err());
+}
+
+#[test]
+fn test_mask_prefers_longer_secrets() {
+    let secrets = Secrets::default();
+    secrets
+        .provide(&[
+            "short_mask=overlap-mask".to_string(),
+            "long_mask=overlap-mask-test-long".to_string(),
+        ])
+        .unwrap();
+
+    assert_eq!(
+        secrets.mask("x overlap-mask-test-long y"),
+        format!("x {MASK} y")
+    );
+}
+
+#[test]
+fn test_mask_skips_short_secrets() {
+    let secrets = Secrets::default();
+    secrets
+        .provide(&["env=dev".to_string(), "pin=1".to_string()])
+        .unwrap();
+
+    assert_eq!(secrets.mask("cd ~/dev && make 1"), "cd ~/dev && make 1");
+}
+
+#[test]
+fn test_without_resolving_restores_after_panic() {
+    let secrets = Secrets::default();
+    let template = "token: {{ secret(name=\"panic_token\", command=\"echo resolved\") }}";
+
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        secrets.without_resolving(|| panic!("rendering failed"))
+    }));
+    assert!(panicked.is_err());
+
+    assert_eq!(
+        render(template, &HashMap::new(), &[], &secrets).unwrap(),
+        "token: resolved"
+    );
+}
+
+#[test]
+fn test_mask_env_by_name() {
+    let secrets = Secrets::default();
+    let rendered = HashMap::from([
+        ("API_TOKEN".to_string(), MASK.to_string()),
+        ("EDITOR".to_string(), "vim".to_string()),
+    ]);
+    let mut env = HashMap::from([
+        ("API_TOKEN".to_string(), "live-token-value".to_string()),
+        ("EDITOR".to_string(), "vim".to_string()),
+    ]);
+
+    secrets.mask_env(&mut env, &rendered);
+
+    assert_eq!(env["API_TOKEN"], MASK);
+    assert_eq!(env["EDITOR"], "vim");
+    // The value is masked wherever else it shows up
+    assert_eq!(
+        secrets.mask("curl -H 'Authorization: live-token-value'"),
+        format!("curl -H 'Authorization: {MASK}'")
+    );
+}
@@ -1,34 +1,144 @@
 //! Template rendering for configuration files using Tera.
 //!
 //! This module provides a simple interface for rendering YAML templates with variables.
+//! Partials placed in a `_templates/` directory at the root of a config directory,
+//! or next to a config in a subfolder, are loaded into the Tera instance so they can
+//! be shared across configs. Tera 2 replaces macros and `{% import %}` with
+//! components: a component defined in any partial can be used from a config directly.
 
-use miette::Result;
+use miette::{IntoDiagnostic, Result, miette};
 use serde_json::Value;
-use std::collections::HashMap;
-use tera::{Context, Tera};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tera::{Context, Kwargs, State, Tera, TeraResult};
 
-/// Renders a template string with the provided variables.
+use super::secrets::Secrets;
+use crate::common::path::same_file;
+
+/// Directory (relative to a config directory) holding shared partials and components.
+pub const TEMPLATES_DIR: &str = "_templates";
+
+/// Directories to load partials from for `config`: the `_templates` of the
+/// directory of `search_path` holding the config, then the one next to it, so a
+/// folder can override shared partials.
+pub fn templates_dirs(config: &Path, search_path: &[String]) -> Vec<PathBuf> {
+    let parent = config.parent().unwrap_or(Path::new(""));
+    let root = search_path
+        .iter()
+        .map(PathBuf::from)
+        .find(|dir| parent.starts_with(dir) || ancestor_of(dir, parent));
+
+    let mut dirs: Vec<PathBuf> = root
+        .into_iter()
+        .map(|dir| dir.join(TEMPLATES_DIR))
+        .collect();
+    let sibling = parent.join(TEMPLATES_DIR);
+    if !dirs.iter().any(|dir| same_file(dir, &sibling)) {
+        dirs.push(sibling);
+    }
+    dirs
+}
+
+/// Whether `dir` contains `path`, following symlinks.
+fn ancestor_of(dir: &Path, path: &Path) -> bool {
+    matches!((dir.canonicalize(), path.canonicalize()), (Ok(dir), Ok(path)) if path.starts_with(&dir))
+}
+
+/// Renders a template string with the provided variables and partials.
+///
+/// Every file below each of `partials_dirs` is registered under its path relative to
+/// that directory (e.g. `panes/editor.yaml`), making it available to `{% include %}`.
+/// Components defined in those files can be used from the template directly. Files
+/// of later directories replace those of earlier ones with the same name.
 ///
 /// # Arguments
 ///
 /// * `template` - The template string to render
 /// * `variables` - A map of variable names to their values (can be strings, arrays, objects)
+/// * `partials_dirs` - Directories to load partials from, ignored if they don't exist
+/// * `secrets` - The secrets of this invocation, used by `secret()`
 ///
-/// # Returns
+/// # Errors
 ///
-/// Returns the rendered string with all variables expanded.
-pub fn render(template: &str, variables: &HashMap<String, Value>) -> Result<String> {
+/// Returns an error if a partial fails to parse, or if the template references a
+/// partial or component that doesn't exist.
+pub fn render(
+    template: &str,
+    variables: &HashMap<String, Value>,
+    partials_dirs: &[PathBuf],
+    secrets: &Secrets,
+) -> Result<String> {
     // Create a one-time Tera instance
     let mut tera = Tera::default();
 
     // Disable auto-escaping since we're rendering YAML, not HTML
     tera.autoescape_on(Vec::<&str>::new());
 
+    let secrets = secrets.clone();
+    tera.register_function("secret", move |kwargs: Kwargs, _: &State| {
+        secret(&secrets, kwargs)
+    });
+
+    for dir in partials_dirs.iter().filter(|dir| dir.is_dir()) {
+        load_partials(&mut tera, dir)?;
+    }
+
     // Build Tera context from the variables map
-    let context = Context::from_serialize(variables)
-        .map_err(|e| miette::miette!("Template context error: {}", e))?;
+    let context =
+        Context::from_serialize(variables).map_err(|e| miette!("Template context error: {}", e))?;
 
     // Render the template with the context
     tera.render_str(template, &context, false)
-        .map_err(|e| miette::miette!("Template rendering failed: {}", e))
+        .map_err(|e| miette!("Template rendering failed: {}", e))
+}
+
+/// `secret(name="token", command="pass show token")` resolves a secret value,
+/// prompting for it if no command is given and it wasn't passed in.
+fn secret(secrets: &Secrets, kwargs: Kwargs) -> TeraResult<String> {
+    let name: &str = kwargs.must_get("name")?;
+    let command: Option<&str> = kwargs.get("command")?;
+    secrets
+        .resolve(name, command)
+        .map_err(|e| tera::Error::message(e.to_string()))
+}
+
+fn load_partials(tera: &mut Tera, dir: &Path) -> Result<()> {
+    let mut files = Vec::new();
+    collect_partials(dir, dir, &mut files)?;
+    files.sort();
+
+    log::debug!(
+        "Loading {} template partials from {}",
+        files.len(),
+        dir.display()
+    );
+
+    tera.add_template_files(files.iter().map(|(path, name)| (path, Some(name))))
+        .map_err(|e| {
+            miette!(
+                "Failed to load template partials from '{}': {}",
+                dir.display(),
+                e
+            )
+        })
+}
+
+fn collect_partials(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, String)>) -> Result<()> {
+    for entry in fs::read_dir(dir).into_diagnostic()? {
+        let path = entry.into_diagnostic()?.path();
+        if path.is_dir() {
+            collect_partials(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let name = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((path, name));
+        }
+    }
+    Ok(())
 }
@@ -1,3 +1,4 @@
+use super::secrets::Secrets;
 use super::template::render;
 use serde_json::json;
 use std::collections::HashMap;
@@ -8,7 +9,7 @@ fn test_simple_variable_substitution() {
     vars.insert("name".to_string(), json!("test-session"));
 
     let template = "name: {{ name }}";
-    let result = render(template, &vars).unwrap();
+    let result = render(template, &vars, &[], &Secrets::default()).unwrap();
 
     assert_eq!(result, "name: test-session");
 }
@@ -18,7 +19,7 @@ fn test_variable_with_default() {
     let vars = HashMap::new(); // Empty - should use default
 
     let template = r#"name: {{ name | default(value="default-session") }}"#;
-    let result = render(template, &vars).unwrap();
+    let result = render(template, &vars, &[], &Secrets::default()).unwrap();
 
     assert_eq!(result, "name: default-session");
 }
@@ -33,7 +34,7 @@ fn test_multiple_variables() {
 name: {{ name }}
 path: {{ path }}
 "#;
-    let result = render(template, &vars).unwrap();
+    let result = render(template, &vars, &[], &Secrets::default()).unwrap();
 
     assert!(result.contains("name: my-project"));
     assert!(result.contains("path: /home/user/dev"));
@@ -44,7 +45,7 @@ fn test_missing_required_variable_fails() {
     let vars = HashMap::new();
 
     let template = "name: {{ required_var }}"; // No default
-    let result = render(template, &vars);
+    let result = render(template, &vars, &[], &Secrets::default());
 
     assert!(result.is_err());
 }
@@ -63,7 +64,7 @@ windows:
     panes:
       - flex: 1
 "#;
-    let result = render(template, &vars).unwrap();
+    let result = render(template, &vars, &[], &Secrets::default()).unwrap();
 
     assert!(result.contains("name: work"));
     assert!(result.contains("path: ~"));
@@ -80,9 +81,98 @@ fn test_array_variable_in_loop() {
   - name: {{ project }}
 {% endfor %}
 "#;
-    let result = render(template, &vars).unwrap();
+    let result = render(template, &vars, &[], &Secrets::default()).unwrap();
 
     assert!(result.contains("- name: web"));
     assert!(result.contains("- name: api"));
     assert!(result.contains("- name: cli"));
 }
+
+#[test]
+fn test_include_partial_from_dir() {
+    let dir = std::env::temp_dir().join("laio_test_template_include");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("panes")).unwrap();
+    std::fs::write(dir.join("panes/editor.yaml"), "- name: {{ editor }}").unwrap();
+
+    let mut vars = HashMap::new();
+    vars.insert("editor".to_string(), json!("nvim"));
+
+    let template = r#"{% include "panes/editor.yaml" %}"#;
+    let result = render(
+        template,
+        &vars,
+        std::slice::from_ref(&dir),
+        &Secrets::default(),
+    )
+    .unwrap();
+
+    assert_eq!(result, "- name: nvim");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_component_from_partials_dir() {
+    let dir = std::env::temp_dir().join("laio_test_template_component");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("pane.yaml"),
+        "{% component pane.server(port: integer = 3000) %}port: {{ port }}{% endcomponent pane.server %}",
+    )
+    .unwrap();
+
+    let vars = HashMap::new();
+
+    let result = render(
+        "{{<pane.server/>}}",
+        &vars,
+        std::slice::from_ref(&dir),
+        &Secrets::default(),
+    )
+    .unwrap();
+    assert_eq!(result, "port: 3000");
+
+    let result = render(
+        "{{<pane.server port={8080}/>}}",
+        &vars,
+        std::slice::from_ref(&dir),
+        &Secrets::default(),
+    )
+    .unwrap();
+    assert_eq!(result, "port: 8080");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_missing_partial_fails() {
+    let vars = HashMap::new();
+
+    let template = r#"{% include "missing.yaml" %}"#;
+    let result = render(template, &vars, &[], &Secrets::default());
+
+    assert!(result.is_err());
+    let err = result.unwrap_err().to_string();
+    assert!(
+        err.contains("missing.yaml"),
+        "Expected missing partial error, got: {err}"
+    );
+}
+
+#[test]
+fn test_missing_partials_dir_is_ignored() {
+    let dir = std::env::temp_dir().join("laio_test_template_no_such_dir");
+    let vars = HashMap::new();
+
+    let result = render(
+        "name: test",
+        &vars,
+        std::slice::from_ref(&dir),
+        &Secrets::default(),
+    )
+    .unwrap();
+
+    assert_eq!(result, "name: test");
+}
@@ -1,31 +1,45 @@
-use miette::{miette, Result};
-use serde_json::{json, Value};
-use std::collections::HashMap;
+use miette::{miette, Context, IntoDiagnostic, Result};
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, env};
+
+use super::secrets::{self, Secrets};
 
 /// Parses a vector of "key=value" strings into a HashMap.
 ///
 /// When the same key appears multiple times, an array is automatically created.
 /// Single values remain as strings for backward compatibility.
 ///
+/// Keys may carry a type suffix (`port:int=3000`, `debug:bool=false`) to produce
+/// typed values; supported types are `str`, `int`, `float`, `bool` and `json`.
+/// Untyped values that look like a JSON array or object are parsed as JSON.
+/// Dotted keys (`db.host=localhost`) build nested objects.
+///
+/// Secret variables (`token:secret=...`) are registered in `secrets` for masking;
+/// a secret whose value was withheld (`token:secret=`) is resolved again, prompting
+/// if needed.
+///
 /// # Arguments
 ///
 /// * `vars` - A slice of strings in "key=value" format
+/// * `secrets` - The secrets of this invocation
 ///
 /// # Returns
 ///
 /// Returns a HashMap with the parsed key-value pairs, where values can be
-/// strings (single occurrence) or arrays (multiple occurrences).
+/// strings (single occurrence), arrays (multiple occurrences), typed values or
+/// nested objects.
 ///
 /// # Errors
 ///
-/// Returns an error if any string is not in "key=value" format.
-pub fn parse_variables(vars: &[String]) -> Result<HashMap<String, Value>> {
-    // First pass: accumulate all values for each key
-    let mut accumulator: HashMap<String, Vec<String>> = HashMap::new();
+/// Returns an error if any string is not in "key=value" format, if a typed value
+/// cannot be converted, or if a dotted key conflicts with a plain key.
+pub fn parse_variables(vars: &[String], secrets: &Secrets) -> Result<HashMap<String, Value>> {
+    // First pass: accumulate all values for each key, keeping first-seen order
+    let mut order: Vec<String> = Vec::new();
+    let mut accumulator: HashMap<String, Vec<Value>> = HashMap::new();
 
     for var in vars {
         let parts: Vec<&str> = var.splitn(2, '=').collect();
-
         if parts.len() != 2 {
             return Err(miette!(
                 "Invalid variable format: '{}'. Expected format: key=value",
@@ -33,7 +47,7 @@ pub fn parse_variables(vars: &[String]) -> Result<HashMap<String, Value>> {
             ));
         }
 
-        let key = parts[0].trim();
+        let (key, kind) = split_type(parts[0].trim());
         let value = parts[1].trim();
 
         if key.is_empty() {
@@ -43,23 +57,178 @@ pub fn parse_variables(vars: &[String]) -> Result<HashMap<String, Value>> {
             ));
         }
 
-        accumulator
-            .entry(key.to_string())
-            .or_default()
-            .push(value.to_string());
+        if key.split('.').any(str::is_empty) {
+            return Err(miette!(
+                "Invalid variable: key '{}' contains an empty segment in '{}'",
+                key,
+                var
+            ));
+        }
+
+        let value = if is_secret_kind(kind) {
+            json!(secret_value(key, value, secrets)?)
+        } else {
+            typed_value(value, kind).map_err(|e| miette!("Invalid variable '{}': {}", var, e))?
+        };
+
+        if !accumulator.contains_key(key) {
+            order.push(key.to_string());
+        }
+        accumulator.entry(key.to_string()).or_default().push(value);
     }
 
     // Second pass: convert to serde_json::Value
-    // Single value -> string, Multiple values -> array
+    // Single value -> value, Multiple values -> array
     let mut map = HashMap::new();
-    for (key, values) in accumulator {
+    for key in order {
+        let mut values = accumulator.remove(&key).unwrap_or_default();
         let value = if values.len() == 1 {
-            json!(values[0]) // Single value stays as string
+            values.remove(0) // Single value stays as is
         } else {
             json!(values) // Multiple values become array
         };
-        map.insert(key, value);
+
+        insert_nested(&mut map, &key, value)?;
     }
 
     Ok(map)
 }
+
+/// Returns the variable name of a "key=value" string, without any type suffix.
+///
+/// `port:int=3000` yields `port`, `db.host=localhost` yields `db.host`.
+pub fn variable_name(var: &str) -> Option<&str> {
+    var.split_once('=').map(|(key, _)| split_type(key.trim()).0)
+}
+
+/// Variables for rendering config `name`, as `laio start` passes them: the user's
+/// `variables` with `session_name` set to the name without its namespace, and
+/// `path` defaulting to the current directory.
+pub fn session_variables(name: &str, variables: &[String]) -> Result<Vec<String>> {
+    // session_name always comes from the name of the config
+    let mut effective_variables: Vec<String> = variables
+        .iter()
+        .filter(|v| variable_name(v) != Some("session_name"))
+        .cloned()
+        .collect();
+
+    let session_name = name.rsplit('/').next().unwrap_or(name);
+    effective_variables.push(format!("session_name={}", session_name));
+
+    if !effective_variables
+        .iter()
+        .any(|v| variable_name(v) == Some("path"))
+    {
+        let cwd = env::current_dir()
+            .into_diagnostic()
+            .wrap_err("Failed to get current directory")?;
+        effective_variables.push(format!("path={}", cwd.display()));
+    }
+
+    Ok(effective_variables)
+}
+
+/// Returns true if a "key=value" string is a secret variable (`key:secret=value`).
+pub fn is_secret_variable(var: &str) -> bool {
+    var.split_once('=')
+        .is_some_and(|(key, _)| is_secret_kind(split_type(key.trim()).1))
+}
+
+fn is_secret_kind(kind: Option<&str>) -> bool {
+    kind.is_some_and(|kind| kind.eq_ignore_ascii_case(secrets::SECRET_TYPE))
+}
+
+fn secret_value(name: &str, value: &str, secrets: &Secrets) -> Result<String> {
+    if value.is_empty() {
+        return secrets.resolve(name, None);
+    }
+    secrets.insert(name, value);
+    Ok(value.to_string())
+}
+
+fn split_type(key: &str) -> (&str, Option<&str>) {
+    match key.rsplit_once(':') {
+        Some((name, kind)) => (name.trim(), Some(kind.trim())),
+        None => (key, None),
+    }
+}
+
+fn typed_value(value: &str, kind: Option<&str>) -> Result<Value> {
+    match kind.map(str::to_lowercase).as_deref() {
+        None => Ok(untyped_value(value)),
+        Some("str") | Some("string") => Ok(json!(value)),
+        Some("int") | Some("integer") => value
+            .parse::<i64>()
+            .map(|v| json!(v))
+            .map_err(|_| miette!("'{}' is not a valid integer", value)),
+        Some("float") | Some("number") => value
+            .parse::<f64>()
+            .map(|v| json!(v))
+            .map_err(|_| miette!("'{}' is not a valid float", value)),
+        Some("bool") | Some("boolean") => match value.to_lowercase().as_str() {
+            "true" => Ok(json!(true)),
+            "false" => Ok(json!(false)),
+            _ => Err(miette!("'{}' is not a valid bool (true/false)", value)),
+        },
+        Some("json") => {
+            serde_json::from_str(value).map_err(|e| miette!("'{}' is not valid JSON: {}", value, e))
+        }
+        Some(other) => Err(miette!(
+            "unknown type '{}', expected one of str, int, float, bool, json, secret",
+            other
+        )),
+    }
+}
+
+fn untyped_value(value: &str) -> Value {
+    if value.starts_with('[') || value.starts_with('{') {
+        if let Ok(parsed) = serde_json::from_str(value) {
+            return parsed;
+        }
+        log::debug!("Value '{value}' looks like JSON but failed to parse, keeping string");
+    }
+    json!(value)
+}
+
+fn insert_nested(map: &mut HashMap<String, Value>, key: &str, value: Value) -> Result<()> {
+    let mut segments = key.split('.');
+    let root = segments.next().unwrap_or_default();
+    let rest: Vec<&str> = segments.collect();
+
+    if rest.is_empty() {
+        if map.contains_key(root) {
+            return Err(miette!(
+                "Invalid variable: '{}' conflicts with a nested key of the same name",
+                key
+            ));
+        }
+        map.insert(root.to_string(), value);
+        return Ok(());
+    }
+
+    let mut current = map
+        .entry(root.to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+
+    for segment in &rest[..rest.len() - 1] {
+        current = current
+            .as_object_mut()
+            .ok_or_else(|| miette!("Invalid variable: '{}' conflicts with a plain value", key))?
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    let object = current
+        .as_object_mut()
+        .ok_or_else(|| miette!("Invalid variable: '{}' conflicts with a plain value", key))?;
+    let leaf = rest[rest.len() - 1];
+    if object.contains_key(leaf) {
+        return Err(miette!(
+            "Invalid variable: '{}' conflicts with a nested key of the same name",
+            key
+        ));
+    }
+    object.insert(leaf.to_string(), value);
+
+    Ok(())
+}
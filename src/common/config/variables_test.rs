@@ -1,9 +1,12 @@
-use super::variables::parse_variables;
+use super::secrets::Secrets;
+use super::template::render;
+use super::variables::{parse_variables, variable_name};
+use serde_json::json;
 
 #[test]
 fn test_parse_single_variable() {
     let vars = vec!["name=test".to_string()];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     assert_eq!(result.get("name").unwrap().as_str(), Some("test"));
 }
@@ -15,7 +18,7 @@ fn test_parse_multiple_variables() {
         "path=/home/user/dev".to_string(),
         "editor=vim".to_string(),
     ];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     assert_eq!(result.get("name").unwrap().as_str(), Some("myproject"));
     assert_eq!(result.get("path").unwrap().as_str(), Some("/home/user/dev"));
@@ -25,7 +28,7 @@ fn test_parse_multiple_variables() {
 #[test]
 fn test_parse_empty_vec() {
     let vars: Vec<String> = vec![];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     assert!(result.is_empty());
 }
@@ -33,7 +36,7 @@ fn test_parse_empty_vec() {
 #[test]
 fn test_parse_value_with_equals_sign() {
     let vars = vec!["url=https://example.com?foo=bar".to_string()];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     assert_eq!(
         result.get("url").unwrap().as_str(),
@@ -44,7 +47,7 @@ fn test_parse_value_with_equals_sign() {
 #[test]
 fn test_parse_whitespace_trimmed() {
     let vars = vec!["  name  =  test  ".to_string()];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     assert_eq!(result.get("name").unwrap().as_str(), Some("test"));
 }
@@ -52,7 +55,7 @@ fn test_parse_whitespace_trimmed() {
 #[test]
 fn test_parse_invalid_no_equals() {
     let vars = vec!["invalid".to_string()];
-    let result = parse_variables(&vars);
+    let result = parse_variables(&vars, &Secrets::default());
 
     assert!(result.is_err());
     assert!(result
@@ -64,7 +67,7 @@ fn test_parse_invalid_no_equals() {
 #[test]
 fn test_parse_invalid_empty_key() {
     let vars = vec!["=value".to_string()];
-    let result = parse_variables(&vars);
+    let result = parse_variables(&vars, &Secrets::default());
 
     assert!(result.is_err());
     assert!(result
@@ -76,7 +79,7 @@ fn test_parse_invalid_empty_key() {
 #[test]
 fn test_parse_empty_value_allowed() {
     let vars = vec!["name=".to_string()];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     assert_eq!(result.get("name").unwrap().as_str(), Some(""));
 }
@@ -84,7 +87,7 @@ fn test_parse_empty_value_allowed() {
 #[test]
 fn test_parse_duplicate_keys_creates_array() {
     let vars = vec!["project=web".to_string(), "project=api".to_string()];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     // Multiple values should create an array
     let value = result.get("project").unwrap();
@@ -102,7 +105,7 @@ fn test_parse_three_values_creates_array() {
         "item=second".to_string(),
         "item=third".to_string(),
     ];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     let value = result.get("item").unwrap();
     assert!(value.is_array());
@@ -116,7 +119,7 @@ fn test_parse_three_values_creates_array() {
 #[test]
 fn test_parse_single_value_stays_string() {
     let vars = vec!["name=single".to_string()];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     // Single value should remain a string, not an array
     let value = result.get("name").unwrap();
@@ -133,7 +136,7 @@ fn test_parse_mixed_single_and_array_values() {
         "env=prod".to_string(),
         "path=/tmp".to_string(),
     ];
-    let result = parse_variables(&vars).unwrap();
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
 
     // name and path should be strings
     assert!(result.get("name").unwrap().is_string());
@@ -150,3 +153,162 @@ fn test_parse_mixed_single_and_array_values() {
     assert_eq!(arr[1].as_str(), Some("staging"));
     assert_eq!(arr[2].as_str(), Some("prod"));
 }
+
+#[test]
+fn test_parse_typed_int() {
+    let vars = vec!["port:int=3000".to_string()];
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
+
+    assert_eq!(result.get("port").unwrap(), &json!(3000));
+}
+
+#[test]
+fn test_parse_typed_bool() {
+    let vars = vec![
+        "debug:bool=false".to_string(),
+        "verbose:bool=TRUE".to_string(),
+    ];
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
+
+    assert_eq!(result.get("debug").unwrap(), &json!(false));
+    assert_eq!(result.get("verbose").unwrap(), &json!(true));
+}
+
+#[test]
+fn test_parse_typed_float_and_str() {
+    let vars = vec!["ratio:float=0.5".to_string(), "version:str=[1]".to_string()];
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
+
+    assert_eq!(result.get("ratio").unwrap(), &json!(0.5));
+    assert_eq!(result.get("version").unwrap(), &json!("[1]"));
+}
+
+#[test]
+fn test_parse_typed_invalid_value() {
+    let vars = vec!["port:int=abc".to_string()];
+    let result = parse_variables(&vars, &Secrets::default());
+
+    assert!(result.is_err());
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("not a valid integer"));
+}
+
+#[test]
+fn test_parse_unknown_type() {
+    let vars = vec!["port:number2=1".to_string()];
+    let result = parse_variables(&vars, &Secrets::default());
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("unknown type"));
+}
+
+#[test]
+fn test_parse_json_array_value() {
+    let vars = vec![r#"services=["api","web"]"#.to_string()];
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
+
+    assert_eq!(result.get("services").unwrap(), &json!(["api", "web"]));
+}
+
+#[test]
+fn test_parse_json_object_value() {
+    let vars = vec![r#"db={"host":"localhost","port":5432}"#.to_string()];
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
+
+    assert_eq!(result.get("db").unwrap()["port"], json!(5432));
+}
+
+#[test]
+fn test_parse_invalid_untyped_json_stays_string() {
+    let vars = vec!["name=[draft".to_string()];
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
+
+    assert_eq!(result.get("name").unwrap(), &json!("[draft"));
+}
+
+#[test]
+fn test_parse_explicit_json_must_be_valid() {
+    let vars = vec!["items:json=[1,".to_string()];
+    let result = parse_variables(&vars, &Secrets::default());
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_parse_dotted_keys_build_nested_objects() {
+    let vars = vec![
+        "db.host=localhost".to_string(),
+        "db.port:int=5432".to_string(),
+        "db.replica.host=replica".to_string(),
+    ];
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
+
+    assert_eq!(
+        result.get("db").unwrap(),
+        &json!({"host": "localhost", "port": 5432, "replica": {"host": "replica"}})
+    );
+}
+
+#[test]
+fn test_parse_dotted_key_repeated_creates_array() {
+    let vars = vec!["app.env=dev".to_string(), "app.env=prod".to_string()];
+    let result = parse_variables(&vars, &Secrets::default()).unwrap();
+
+    assert_eq!(result.get("app").unwrap()["env"], json!(["dev", "prod"]));
+}
+
+#[test]
+fn test_parse_dotted_key_conflicts_with_plain_key() {
+    let vars = vec!["db=main".to_string(), "db.host=localhost".to_string()];
+    assert!(parse_variables(&vars, &Secrets::default()).is_err());
+
+    let vars = vec!["db.host=localhost".to_string(), "db=main".to_string()];
+    assert!(parse_variables(&vars, &Secrets::default()).is_err());
+}
+
+#[test]
+fn test_parse_dotted_key_empty_segment() {
+    let vars = vec!["db..host=localhost".to_string()];
+    let result = parse_variables(&vars, &Secrets::default());
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("empty segment"));
+}
+
+#[test]
+fn test_typed_bool_renders_falsy() {
+    let vars = parse_variables(&["debug:bool=false".to_string()], &Secrets::default()).unwrap();
+    let result = render(
+        "{% if debug %}on{% else %}off{% endif %}",
+        &vars,
+        &[],
+        &Secrets::default(),
+    )
+    .unwrap();
+
+    assert_eq!(result, "off");
+}
+
+#[test]
+fn test_typed_int_compares_numerically() {
+    let vars = parse_variables(&["port:int=3000".to_string()], &Secrets::default()).unwrap();
+    let result = render(
+        "{% if port > 1024 %}high{% endif %}",
+        &vars,
+        &[],
+        &Secrets::default(),
+    )
+    .unwrap();
+
+    assert_eq!(result, "high");
+}
+
+#[test]
+fn test_variable_name_strips_type() {
+    assert_eq!(variable_name("port:int=3000"), Some("port"));
+    assert_eq!(variable_name("db.host=localhost"), Some("db.host"));
+    assert_eq!(variable_name("path=/a:b"), Some("path"));
+    assert_eq!(variable_name("invalid"), None);
+}
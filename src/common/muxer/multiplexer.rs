@@ -18,9 +18,46 @@ pub(crate) trait Multiplexer {
         stop_all: bool,
         stop_other: bool,
     ) -> Result<()>;
+    /// Runs the shutdown hooks of `running`, session `name` as it was started, stops it
+    /// and starts `session` in its place.
+    fn restart(
+        &self,
+        name: &str,
+        running: &Option<Session>,
+        session: &Session,
+        env_vars: &[(&str, &str)],
+        skip_attach: bool,
+        skip_cmds: bool,
+    ) -> Result<()>;
+    /// Name of the session laio runs in, if any.
+    fn current_session_name(&self) -> Result<Option<String>>;
     fn get_session_config_path(&self, name: &str) -> Result<Option<String>>;
     fn get_session_variables(&self, name: &str) -> Result<Option<Vec<String>>>;
+    fn set_session_config_path(&self, name: &str, config_path: &str) -> Result<()>;
+    /// Running sessions with what the multiplexer can tell about them, see
+    /// [`SessionDetails`](crate::common::session_info::SessionDetails).
     fn list_sessions(&self) -> Result<Vec<SessionInfo>>;
     fn switch(&self, name: &str, skip_attach: bool) -> Result<bool>;
-    fn get_session(&self) -> Result<Session>;
+    /// Adds the windows and panes of `session` missing in the running session of the
+    /// same name and applies its layouts, leaving existing panes alone. With `prune`,
+    /// windows and panes not in `session` are closed. Returns warnings about layouts
+    /// that couldn't be applied.
+    fn apply(&self, session: &Session, prune: bool, skip_cmds: bool) -> Result<Vec<String>>;
+    /// Kills window `window` of the running session of `session` and recreates it from
+    /// `session` at the same index. With `pane`, only the pane of that name is
+    /// restarted.
+    fn respawn(
+        &self,
+        session: &Session,
+        window: &str,
+        pane: Option<&str>,
+        skip_cmds: bool,
+    ) -> Result<()>;
+    /// Whether the panes of a session can be captured for a snapshot.
+    fn supports_snapshots(&self) -> bool;
+    /// Content of the panes of session `name` including their scrollback, by window
+    /// and in the order of the layout.
+    fn capture_panes(&self, name: &str) -> Result<Vec<Vec<String>>>;
+    /// Reads the layout of `session`, or of the current session if none is given.
+    fn get_session(&self, session: Option<&str>) -> Result<Session>;
 }
@@ -25,10 +25,24 @@ mock! {
             stop_other: bool,
         ) -> Result<()>;
 
+        fn restart<'a>(
+            &self,
+            name: &str,
+            running: &Option<Session>,
+            session: &Session,
+            env_vars: &'a [(&'a str, &'a str)],
+            skip_attach: bool,
+            skip_cmds: bool,
+        ) -> Result<()>;
+
+        fn current_session_name(&self) -> Result<Option<String>>;
+
         fn get_session_config_path(&self, name: &str) -> Result<Option<String>>;
 
         fn get_session_variables(&self, name: &str) -> Result<Option<Vec<String>>>;
 
+        fn set_session_config_path(&self, name: &str, config_path: &str) -> Result<()>;
+
         fn list_sessions(&self) -> Result<Vec<SessionInfo>>;
 
         fn switch(
@@ -37,6 +51,16 @@ mock! {
             skip_attach: bool,
         ) -> Result<bool>;
 
-        fn get_session(&self) -> Result<Session>;
+        fn apply(&self, session: &Session, prune: bool, skip_cmds: bool) -> Result<Vec<String>>;
+        fn respawn<'a>(
+            &self,
+            session: &Session,
+            window: &str,
+            pane: Option<&'a str>,
+            skip_cmds: bool,
+        ) -> Result<()>;
+        fn supports_snapshots(&self) -> bool;
+        fn capture_panes(&self, name: &str) -> Result<Vec<Vec<String>>>;
+        fn get_session<'a>(&self, session: Option<&'a str>) -> Result<Session>;
     }
 }
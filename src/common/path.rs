@@ -7,6 +7,8 @@ use std::{
 
 use miette::{bail, miette, Error, IntoDiagnostic, Result};
 
+pub(crate) const LAIO_CONFIG_PATH: &str = "LAIO_CONFIG_PATH";
+
 pub(crate) fn home_dir() -> Result<String> {
     env::var("HOME").map_err(|_| miette!("Failed to get home directory"))
 }
@@ -56,6 +58,11 @@ pub(crate) fn resolve_symlink(path: &PathBuf) -> Result<PathBuf> {
     Ok(new_path)
 }
 
+/// Whether `a` and `b` are the same file, following symlinks.
+pub(crate) fn same_file(a: &Path, b: &Path) -> bool {
+    a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
 pub(crate) fn sanitize_path(path: &String, parent_path: &String) -> String {
     log::debug!("Original path: {path}");
     let path = match path {
@@ -137,3 +144,58 @@ pub(crate) fn relative_path(absolute_path: &str, base_path: &str) -> Option<Stri
         false => Some(rel_path),
     }
 }
+
+/// Default config directory: `$XDG_CONFIG_HOME/laio`, falling back to `~/.config/laio`.
+pub(crate) fn default_config_dir() -> String {
+    match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => format!("{dir}/laio"),
+        _ => "~/.config/laio".to_string(),
+    }
+}
+
+/// Directory for state such as snapshots: `$XDG_STATE_HOME/laio`, falling back to
+/// `~/.local/state/laio`.
+pub(crate) fn default_state_dir() -> String {
+    match env::var("XDG_STATE_HOME") {
+        Ok(dir) if !dir.is_empty() => format!("{dir}/laio"),
+        _ => "~/.local/state/laio".to_string(),
+    }
+}
+
+/// Config search path: the given directories if any, then `LAIO_CONFIG_PATH`,
+/// then the default config directory.
+pub(crate) fn config_search_path(config_dir: Option<&str>) -> String {
+    match config_dir {
+        Some(dir) => dir.to_string(),
+        None => match env::var(LAIO_CONFIG_PATH) {
+            Ok(path) if !path.trim_matches(':').is_empty() => path,
+            _ => default_config_dir(),
+        },
+    }
+}
+
+/// Splits a colon-separated list of config directories, expanding a leading `~`.
+/// Earlier entries take precedence, duplicates are dropped.
+pub(crate) fn split_search_path(search_path: &str) -> Vec<String> {
+    let home = home_dir().unwrap_or_default();
+    let mut dirs: Vec<String> = Vec::new();
+    for dir in search_path.split(':').filter(|dir| !dir.is_empty()) {
+        let dir = match dir.strip_prefix('~') {
+            Some("") => home.clone(),
+            Some(rest) if rest.starts_with('/') => format!("{home}{rest}"),
+            _ => dir.to_string(),
+        };
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// Finds `file_name` in the first directory of the search path containing it.
+pub(crate) fn find_in_search_path(search_path: &[String], file_name: &str) -> Option<PathBuf> {
+    search_path
+        .iter()
+        .map(|dir| PathBuf::from(dir).join(file_name))
+        .find(|path| symlink_metadata(path).is_ok())
+}
@@ -1,5 +1,7 @@
 use serde::{Serialize, Serializer};
-use std::fmt;
+use std::{cmp::Ordering, fmt, path::Path};
+
+use crate::common::path::same_file;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SessionStatus {
@@ -38,10 +40,63 @@ impl fmt::Display for SessionStatus {
     }
 }
 
+/// How a config is stored in the config directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    /// A file in the config directory.
+    Local,
+    /// A symlink, such as one to a project's `.laio.yaml` made by `laio config link`.
+    Symlink,
+}
+
+/// What the multiplexer reports about a running session. What it can't tell is left
+/// out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionDetails {
+    pub muxer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub windows: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panes: Option<usize>,
+    /// Number of attached clients.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clients: Option<usize>,
+    /// Unix timestamp in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<u64>,
+    /// Unix timestamp in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_activity: Option<u64>,
+    /// Variables the session was started with, from `LAIO_VARS`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_laio_session: Option<bool>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SessionInfo {
     pub status: SessionStatus,
     pub name: String,
+    /// Name of the running session when it isn't `name`, such as `api` for the
+    /// config `work/api`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Directory the config was found in, set when searching several directories.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_source: Option<ConfigSource>,
+    /// Set for running sessions.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub details: Option<SessionDetails>,
 }
 
 impl SessionInfo {
@@ -53,6 +108,13 @@ impl SessionInfo {
                 SessionStatus::Active
             },
             name,
+            session: None,
+            description: None,
+            tags: vec![],
+            config_dir: None,
+            config_path: None,
+            config_source: None,
+            details: None,
         }
     }
 
@@ -60,9 +122,155 @@ impl SessionInfo {
         Self {
             status: SessionStatus::Inactive,
             name,
+            session: None,
+            description: None,
+            tags: vec![],
+            config_dir: None,
+            config_path: None,
+            config_source: None,
+            details: None,
         }
     }
 
+    pub fn with_config_dir(mut self, config_dir: Option<String>) -> Self {
+        self.config_dir = config_dir;
+        self
+    }
+
+    pub fn with_description(mut self, description: Option<String>, tags: Vec<String>) -> Self {
+        self.description = description;
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_config_path(mut self, config_path: String, source: ConfigSource) -> Self {
+        self.config_path = Some(config_path);
+        self.config_source = Some(source);
+        self
+    }
+
+    /// Adds the details of a running session. `config_path` is the `LAIO_CONFIG` of
+    /// the session.
+    pub fn with_session_details(
+        mut self,
+        details: SessionDetails,
+        config_path: Option<String>,
+    ) -> Self {
+        self.details = Some(details);
+        self.config_path = config_path;
+        self
+    }
+
+    /// Name of the running session, or of the session the config starts as far as
+    /// it is known.
+    pub fn session_name(&self) -> &str {
+        self.session.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Namespace of a config name (`work` for `work/api`), if any.
+    pub fn namespace(&self) -> Option<&str> {
+        self.name.rsplit_once('/').map(|(namespace, _)| namespace)
+    }
+
+    /// Orders top-level entries first, then groups namespaced entries by folder.
+    pub fn cmp_grouped(a: &SessionInfo, b: &SessionInfo) -> Ordering {
+        a.namespace()
+            .cmp(&b.namespace())
+            .then_with(|| a.name.cmp(&b.name))
+    }
+
+    /// Orders sessions by `recent`, the names of the sessions from the most recently
+    /// used, and the others as [`Self::cmp_grouped`]. A config matches a session of
+    /// its name, with or without its namespace.
+    pub fn sort_recent(sessions: &mut [SessionInfo], recent: &[String]) {
+        let position = |info: &SessionInfo| {
+            recent.iter().position(|name| {
+                *name == info.name
+                    || info.session.as_ref() == Some(name)
+                    || info
+                        .name
+                        .rsplit_once('/')
+                        .is_some_and(|(_, config)| config == name)
+            })
+        };
+        sessions.sort_by(|a, b| match (position(a), position(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Self::cmp_grouped(a, b),
+        });
+    }
+
+    /// Merges active sessions with available configs into a single list, grouped by
+    /// namespace and sorted by name. Each active session replaces the config it was
+    /// started from and picks up its name, description and where the config is, its
+    /// own name is kept in `session` if it differs. Sessions
+    /// that know their `LAIO_CONFIG` are matched by it, others by name, or by the
+    /// name within a namespace if only one namespaced config has it.
+    pub fn merge(sessions: Vec<SessionInfo>, configs: Vec<SessionInfo>) -> Vec<SessionInfo> {
+        let mut configs: Vec<Option<SessionInfo>> = configs.into_iter().map(Some).collect();
+
+        let mut merged: Vec<SessionInfo> = sessions
+            .into_iter()
+            .map(|session| {
+                let position = match &session.config_path {
+                    Some(path) => configs.iter().position(|c| {
+                        c.as_ref().is_some_and(|c| {
+                            c.config_path
+                                .as_ref()
+                                .is_some_and(|config| same_file(Path::new(config), Path::new(path)))
+                        })
+                    }),
+                    None => configs
+                        .iter()
+                        .position(|c| c.as_ref().is_some_and(|c| c.name == session.name))
+                        .or_else(|| {
+                            let mut namespaced = configs.iter().enumerate().filter(|(_, c)| {
+                                c.as_ref().is_some_and(|c| {
+                                    c.name
+                                        .rsplit_once('/')
+                                        .is_some_and(|(_, name)| name == session.name)
+                                })
+                            });
+                            // Configs in different folders may share a session name
+                            match (namespaced.next(), namespaced.next()) {
+                                (Some((i, _)), None) => Some(i),
+                                _ => None,
+                            }
+                        }),
+                };
+                match position.and_then(|i| configs[i].take()) {
+                    Some(config) => SessionInfo {
+                        status: session.status,
+                        session: (session.name != config.name).then_some(session.name),
+                        config_path: session.config_path.or(config.config_path),
+                        details: session.details,
+                        ..config
+                    },
+                    None => session,
+                }
+            })
+            .collect();
+
+        merged.extend(configs.into_iter().flatten());
+
+        merged.sort_by(SessionInfo::cmp_grouped);
+        // Configs in different folders may share a name, only entries of the same
+        // config are the same
+        merged.dedup_by(|a, b| {
+            a.name == b.name
+                && match (&a.config_path, &b.config_path) {
+                    (Some(a), Some(b)) => same_file(Path::new(a), Path::new(b)),
+                    (a, b) => a == b,
+                }
+        });
+        merged
+    }
+
     pub fn is_active(&self) -> bool {
         matches!(self.status, SessionStatus::Active | SessionStatus::Attached)
     }
@@ -70,6 +278,16 @@ impl SessionInfo {
 
 impl fmt::Display for SessionInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {}", self.status.icon(), self.name)
+        write!(f, "{} {}", self.status.icon(), self.name)?;
+        if let Some(description) = &self.description {
+            write!(f, " - {description}")?;
+        }
+        for tag in &self.tags {
+            write!(f, " #{tag}")?;
+        }
+        if let Some(dir) = &self.config_dir {
+            write!(f, " ({dir})")?;
+        }
+        Ok(())
     }
 }
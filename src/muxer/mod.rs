@@ -1,4 +1,8 @@
-use crate::common::muxer::Multiplexer;
+use crate::common::{
+    cmd::{Runner, ShellRunner},
+    config::secrets::Secrets,
+    muxer::Multiplexer,
+};
 use clap::ValueEnum;
 use miette::{bail, Result};
 use std::env;
@@ -13,7 +17,20 @@ pub(crate) enum Muxer {
     Zellij,
 }
 
-pub(crate) fn create_muxer(muxer: &Option<Muxer>) -> Result<Box<dyn Multiplexer>> {
+pub(crate) fn create_muxer(
+    muxer: &Option<Muxer>,
+    secrets: &Secrets,
+) -> Result<Box<dyn Multiplexer>> {
+    create_muxer_with_runner(muxer, ShellRunner::new(secrets.clone()), secrets)
+}
+
+/// Creates the muxer running its commands through `runner`, such as a
+/// [`RecordingRunner`](crate::common::cmd::RecordingRunner) for dry runs.
+pub(crate) fn create_muxer_with_runner<R: Runner>(
+    muxer: &Option<Muxer>,
+    runner: R,
+    secrets: &Secrets,
+) -> Result<Box<dyn Multiplexer>> {
     let muxer = match muxer {
         Some(m) => m.clone(),
         None => match env::var("LAIO_MUXER") {
@@ -30,7 +47,11 @@ pub(crate) fn create_muxer(muxer: &Option<Muxer>) -> Result<Box<dyn Multiplexer>
     };
 
     match muxer {
-        Muxer::Tmux => Ok(Box::new(Tmux::new())),
-        Muxer::Zellij => Ok(Box::new(Zellij::new())),
+        Muxer::Tmux => Ok(Box::new(
+            Tmux::new_with_runner(runner).with_secrets(secrets.clone()),
+        )),
+        Muxer::Zellij => Ok(Box::new(
+            Zellij::new_with_runner(runner).with_secrets(secrets.clone()),
+        )),
     }
 }
@@ -11,25 +11,63 @@ use std::{
     process::{self, Command},
     sync::Arc,
 };
-use sysinfo::{Pid, ProcessesToUpdate, System};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
 
 use crate::{
-    cmd_basic,
+    cmd_basic, cmd_query,
     common::{
         cmd::{Runner, Type},
         config::Command as ConfigCommand,
         muxer::Client,
+        session_info::SessionDetails,
     },
 };
 
 use super::Target;
 
+/// Checksum tmux expects in front of a custom layout.
+pub(crate) fn layout_checksum(layout: &str) -> String {
+    let csum = layout.as_bytes().iter().fold(0u16, |csum, &c| {
+        let rotated = (csum >> 1) | ((csum & 1) << 15);
+        rotated.wrapping_add(c as u16)
+    });
+    format!("{csum:04x}")
+}
+
+/// Query `tmux <args>` for `session`, or for the current session if none is given.
+fn in_session(session: Option<&str>, args: &[&str]) -> Type {
+    let mut command = Command::new("tmux");
+    command.arg(args[0]);
+    if let Some(session) = session {
+        command.args(["-t", session]);
+    }
+    command.args(&args[1..]);
+    Type::Query(command)
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct Dimensions {
     pub width: usize,
     pub height: usize,
 }
 
+/// A window as listed by `list-windows`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct WindowInfo {
+    pub name: String,
+    pub layout: String,
+    pub active: bool,
+}
+
+/// Pane state that is not part of the window layout.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub(crate) struct PaneInfo {
+    pub focus: bool,
+    pub zoom: bool,
+    pub title: Option<String>,
+    pub style: Option<String>,
+}
+
 #[derive(Debug)]
 pub(crate) struct TmuxClient<R: Runner> {
     pub cmd_runner: Arc<R>,
@@ -89,7 +127,7 @@ impl<R: Runner> TmuxClient<R> {
 
     pub(crate) fn session_exists(&self, name: &str) -> bool {
         self.cmd_runner
-            .run(&cmd_basic!("tmux", args = ["has-session", "-t", name]))
+            .run(&cmd_query!("tmux", args = ["has-session", "-t", name]))
             .unwrap_or(false)
     }
 
@@ -105,12 +143,12 @@ impl<R: Runner> TmuxClient<R> {
 
     pub(crate) fn is_inside_session(&self) -> bool {
         self.cmd_runner
-            .run(&cmd_basic!("printenv", args = ["TMUX"]))
+            .run(&cmd_query!("printenv", args = ["TMUX"]))
             .is_ok_and(|s: String| !s.is_empty())
     }
 
     pub(crate) fn current_session_name(&self) -> Result<String> {
-        self.cmd_runner.run(&cmd_basic!(
+        self.cmd_runner.run(&cmd_query!(
             "sh",
             args = [
                 "-c",
@@ -119,6 +157,17 @@ impl<R: Runner> TmuxClient<R> {
         ))
     }
 
+    /// Name of the session of the pane laio runs in, empty outside of a pane.
+    pub(crate) fn pane_session_name(&self) -> Result<String> {
+        self.cmd_runner.run(&cmd_query!(
+            "sh",
+            args = [
+                "-c",
+                "[ -n \"$TMUX_PANE\" ] && tmux display-message -t \"$TMUX_PANE\" -p '#S' || true"
+            ]
+        ))
+    }
+
     pub(crate) fn stop_session(&self, name: &str) -> Result<()> {
         if self.session_exists(name) {
             self.cmd_runner
@@ -128,6 +177,13 @@ impl<R: Runner> TmuxClient<R> {
         }
     }
 
+    pub(crate) fn rename_session(&self, name: &str, new_name: &str) -> Result<()> {
+        self.cmd_runner.run(&cmd_basic!(
+            "tmux",
+            args = ["rename-session", "-t", name, new_name]
+        ))
+    }
+
     pub(crate) fn new_window(
         &self,
         session_name: &str,
@@ -152,7 +208,7 @@ impl<R: Runner> TmuxClient<R> {
     }
 
     pub(crate) fn get_current_window(&self, session_name: &str) -> Result<String> {
-        self.cmd_runner.run(&cmd_basic!(
+        self.cmd_runner.run(&cmd_query!(
             "tmux",
             args = ["display-message", "-t", session_name, "-p", "#I"]
         ))
@@ -175,7 +231,7 @@ impl<R: Runner> TmuxClient<R> {
     }
 
     pub(crate) fn get_current_pane(&self, target: &Target) -> Result<String> {
-        self.cmd_runner.run(&cmd_basic!(
+        self.cmd_runner.run(&cmd_query!(
             "tmux",
             args = ["display-message", "-t", target.to_string(), "-p", "#P"]
         ))
@@ -192,8 +248,17 @@ impl<R: Runner> TmuxClient<R> {
             ))
     }
 
+    /// Sets `name` in the environment of running session `target` right away,
+    /// unlike `setenv` which queues it with the pane commands.
+    pub(crate) fn set_environment(&self, target: &Target, name: &str, value: &str) -> Result<()> {
+        self.cmd_runner.run(&cmd_basic!(
+            "tmux",
+            args = ["set-environment", "-t", target.to_string(), name, value]
+        ))
+    }
+
     pub(crate) fn getenv(&self, target: &Target, name: &str) -> Result<String> {
-        let output: String = self.cmd_runner.run(&cmd_basic!(
+        let output: String = self.cmd_runner.run(&cmd_query!(
             "tmux",
             args = ["show-environment", "-t", target.to_string(), name]
         ))?;
@@ -204,6 +269,43 @@ impl<R: Runner> TmuxClient<R> {
             .ok_or_else(|| miette!("Variable not found or malformed output"))
     }
 
+    /// Variables laio set in the session's environment, read in one go.
+    pub(crate) fn laio_env(&self, target: &Target) -> Result<HashMap<String, String>> {
+        let output: String = self.cmd_runner.run(&cmd_query!(
+            "tmux",
+            args = ["show-environment", "-t", target.to_string()]
+        ))?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(name, _)| name.starts_with("LAIO_"))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect())
+    }
+
+    /// User variables of the session's environment. Variables laio sets itself,
+    /// those tmux copies in through `update-environment` and removed ones are skipped.
+    pub(crate) fn session_env(&self, target: &Target) -> Result<HashMap<String, String>> {
+        let updated: String = self.cmd_runner.run(&cmd_query!(
+            "tmux",
+            args = ["show-options", "-g", "-v", "update-environment"]
+        ))?;
+        let updated: Vec<&str> = updated.split_whitespace().collect();
+
+        let output: String = self.cmd_runner.run(&cmd_query!(
+            "tmux",
+            args = ["show-environment", "-t", target.to_string()]
+        ))?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(name, _)| !name.starts_with("LAIO_") && !updated.contains(name))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect())
+    }
+
     pub(crate) fn register_commands(&self, target: &Target, cmds: &[ConfigCommand]) {
         let cmd_strings: Vec<String> = cmds.iter().map(|cmd| cmd.to_string()).collect();
         self.register_command(target, cmd_strings);
@@ -292,22 +394,14 @@ impl<R: Runner> TmuxClient<R> {
     pub(crate) fn select_custom_layout(&self, target: &Target, layout: &str) -> Result<()> {
         self.select_layout(
             target,
-            &format!("{},{}", self.layout_checksum(layout), layout),
+            &format!("{},{}", layout_checksum(layout), layout),
         )
     }
 
-    pub(crate) fn layout_checksum(&self, layout: &str) -> String {
-        let csum = layout.as_bytes().iter().fold(0u16, |csum, &c| {
-            let rotated = (csum >> 1) | ((csum & 1) << 15);
-            rotated.wrapping_add(c as u16)
-        });
-        format!("{csum:04x}")
-    }
-
     pub(crate) fn get_dimensions(&self) -> Result<Dimensions> {
         let res: String = if self.is_inside_session() {
             log::debug!("Inside session, using tmux dimensions.");
-            self.cmd_runner.run(&cmd_basic!(
+            self.cmd_runner.run(&cmd_query!(
                 "tmux",
                 args = [
                     "display-message",
@@ -325,20 +419,33 @@ impl<R: Runner> TmuxClient<R> {
         from_str(&res).into_diagnostic()
     }
 
-    pub(crate) fn list_sessions(&self) -> Result<Vec<(String, bool)>> {
+    /// Sessions with the number of their attached clients and windows, and when they
+    /// were created and last active.
+    pub(crate) fn list_sessions(&self) -> Result<Vec<(String, SessionDetails)>> {
         self.cmd_runner
-            .run(&cmd_basic!(
+            .run(&cmd_query!(
                 "tmux",
-                args = ["ls", "-F", "#{session_name}|#{session_attached}"]
+                args = [
+                    "ls",
+                    "-F",
+                    "#{session_name}|#{session_attached}|#{session_windows}|#{session_created}|#{session_activity}"
+                ]
             ))
             .map(|res: String| {
                 res.lines()
                     .filter_map(|line| {
-                        let parts: Vec<&str> = line.split('|').collect();
-                        if parts.len() == 2 {
-                            let name = parts[0].to_string();
-                            let is_attached = parts[1] != "0";
-                            Some((name, is_attached))
+                        // The name comes first as it may contain the separator
+                        let parts: Vec<&str> = line.rsplitn(5, '|').collect();
+                        if parts.len() == 5 {
+                            let details = SessionDetails {
+                                muxer: "tmux".to_string(),
+                                clients: parts[3].parse().ok(),
+                                windows: parts[2].parse().ok(),
+                                created: parts[1].parse().ok(),
+                                last_activity: parts[0].parse().ok(),
+                                ..Default::default()
+                            };
+                            Some((parts[4].to_string(), details))
                         } else {
                             None
                         }
@@ -348,8 +455,24 @@ impl<R: Runner> TmuxClient<R> {
             .or_else(|_| Ok(vec![]))
     }
 
+    /// Number of panes of every session.
+    pub(crate) fn pane_counts(&self) -> Result<HashMap<String, usize>> {
+        self.cmd_runner
+            .run(&cmd_query!(
+                "tmux",
+                args = ["list-panes", "-a", "-F", "#{session_name}"]
+            ))
+            .map(|res: String| {
+                res.lines().fold(HashMap::new(), |mut counts, name| {
+                    *counts.entry(name.to_string()).or_insert(0) += 1;
+                    counts
+                })
+            })
+            .or_else(|_| Ok(HashMap::new()))
+    }
+
     pub(crate) fn get_base_idx(&self) -> Result<usize> {
-        let res: String = self.cmd_runner.run(&cmd_basic!(
+        let res: String = self.cmd_runner.run(&cmd_query!(
             "tmux",
             args = ["show-options", "-g", "base-index"]
         ))?;
@@ -386,16 +509,127 @@ impl<R: Runner> TmuxClient<R> {
         ))
     }
 
-    pub(crate) fn session_name(&self) -> Result<String> {
+    pub(crate) fn session_name(&self, session: Option<&str>) -> Result<String> {
         self.cmd_runner
-            .run(&cmd_basic!("tmux", args = ["display-message", "-p", "#S"]))
+            .run(&in_session(session, &["display-message", "-p", "#S"]))
     }
 
-    pub(crate) fn session_layout(&self) -> Result<String> {
-        self.cmd_runner.run(&cmd_basic!(
-            "tmux",
-            args = ["list-windows", "-F", "\"#{window_name} #{window_layout}\""]
-        ))
+    pub(crate) fn session_windows(&self, session: Option<&str>) -> Result<Vec<WindowInfo>> {
+        // The name goes last so that it may contain any character but a newline
+        let output: String = self.cmd_runner.run(&in_session(
+            session,
+            &[
+                "list-windows",
+                "-F",
+                "#{window_active}\t#{window_layout}\t#{window_name}",
+            ],
+        ))?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '\t');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(active), Some(layout), Some(name)) => Some(WindowInfo {
+                        name: name.to_string(),
+                        layout: layout.to_string(),
+                        active: active == "1",
+                    }),
+                    _ => None,
+                }
+            })
+            .collect())
+    }
+
+    /// Focus, zoom, title and style of every pane in the session, keyed by pane id
+    /// without the `%` prefix.
+    pub(crate) fn pane_info(&self, session: Option<&str>) -> Result<HashMap<String, PaneInfo>> {
+        let output: String = self.cmd_runner.run(&in_session(
+            session,
+            &[
+                "list-panes",
+                "-s",
+                "-F",
+                "#{pane_id}\t#{pane_active}\t#{window_zoomed_flag}\t#{host}\t#{pane_title}",
+            ],
+        ))?;
+
+        let panes: Vec<[&str; 5]> = output
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(5, '\t');
+                Some([
+                    parts.next()?,
+                    parts.next()?,
+                    parts.next()?,
+                    parts.next()?,
+                    parts.next()?,
+                ])
+            })
+            .collect();
+        let pane_ids: Vec<&str> = panes.iter().map(|[pane_id, ..]| *pane_id).collect();
+        let styles = self.pane_styles(&pane_ids)?;
+
+        let mut pane_map = HashMap::new();
+        for [pane_id, active, zoomed, host, title] in panes {
+
+            // tmux titles panes after the host unless something renamed them
+            let title = title.trim();
+            let focus = active == "1";
+            pane_map.insert(
+                pane_id.replace('%', ""),
+                PaneInfo {
+                    focus,
+                    zoom: focus && zoomed == "1",
+                    title: (!title.is_empty() && title != host).then(|| title.to_string()),
+                    style: styles.get(pane_id).cloned(),
+                },
+            );
+        }
+
+        log::trace!("pane-info-map: {pane_map:?}");
+
+        Ok(pane_map)
+    }
+
+    /// Styles set on panes `pane_ids`, read with a single tmux call. Each pane prints
+    /// its id, followed by its style if it has one.
+    fn pane_styles<'a>(&self, pane_ids: &[&'a str]) -> Result<HashMap<&'a str, String>> {
+        if pane_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut command = Command::new("tmux");
+        for (index, pane_id) in pane_ids.iter().enumerate() {
+            if index > 0 {
+                command.arg(";");
+            }
+            command.args(["display-message", "-p", "-t", pane_id, "#{pane_id}", ";"]);
+            command.args([
+                "show-options",
+                "-p",
+                "-q",
+                "-v",
+                "-t",
+                pane_id,
+                "window-style",
+            ]);
+        }
+        let output: String = self.cmd_runner.run(&Type::Query(command))?;
+
+        let mut styles = HashMap::new();
+        let mut pane = None;
+        for line in output.lines().map(str::trim) {
+            match pane_ids.iter().find(|pane_id| **pane_id == line) {
+                Some(pane_id) => pane = Some(*pane_id),
+                None if !line.is_empty() => {
+                    if let Some(pane) = pane {
+                        styles.insert(pane, line.to_string());
+                    }
+                }
+                None => {}
+            }
+        }
+        Ok(styles)
     }
 
     pub(crate) fn rename_window(&self, target: &Target, name: &str) -> Result<()> {
@@ -412,8 +646,122 @@ impl<R: Runner> TmuxClient<R> {
         ))
     }
 
-    pub(crate) fn session_start_path(&self) -> Result<String> {
-        let pane_map: HashMap<String, String> = self.pane_paths()?;
+    /// Id and name of every window in the session, in order
+    pub(crate) fn window_ids(&self, session_name: &str) -> Result<Vec<(String, String)>> {
+        let output: String = self.cmd_runner.run(&cmd_query!(
+            "tmux",
+            args = [
+                "list-windows",
+                "-t",
+                session_name,
+                "-F",
+                "#{window_id}\t#{window_name}"
+            ]
+        ))?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(id, name)| (id.to_string(), name.to_string()))
+            .collect())
+    }
+
+    /// Ids of the panes of a window, in the order tmux assigns them to a layout
+    pub(crate) fn window_panes(&self, target: &Target) -> Result<Vec<String>> {
+        let output: String = self.cmd_runner.run(&cmd_query!(
+            "tmux",
+            args = ["list-panes", "-t", target.to_string(), "-F", "#{pane_id}"]
+        ))?;
+
+        Ok(output.lines().map(str::to_string).collect())
+    }
+
+    pub(crate) fn kill_window(&self, target: &Target) -> Result<()> {
+        self.cmd_runner.run(&cmd_basic!(
+            "tmux",
+            args = ["kill-window", "-t", target.to_string()]
+        ))
+    }
+
+    pub(crate) fn kill_pane(&self, target: &Target) -> Result<()> {
+        self.cmd_runner.run(&cmd_basic!(
+            "tmux",
+            args = ["kill-pane", "-t", target.to_string()]
+        ))
+    }
+
+    /// Index of a window and whether it's the active window of its session
+    pub(crate) fn window_index(&self, target: &Target) -> Result<(String, bool)> {
+        let output: String = self.cmd_runner.run(&cmd_query!(
+            "tmux",
+            args = [
+                "display-message",
+                "-t",
+                target.to_string(),
+                "-p",
+                "#{window_index} #{window_active}"
+            ]
+        ))?;
+
+        match output.trim().split_once(' ') {
+            Some((index, active)) => Ok((index.to_string(), active == "1")),
+            None => bail!("Unexpected window index of '{}': '{}'", target, output),
+        }
+    }
+
+    /// Replaces the window at `target` (`session:index`) with a new window, and
+    /// returns its id
+    pub(crate) fn replace_window(
+        &self,
+        target: &Target,
+        window_name: &str,
+        path: &str,
+        select: bool,
+    ) -> Result<String> {
+        let flags = if select { "-Pk" } else { "-Pdk" };
+        self.cmd_runner.run(&cmd_basic!(
+            "tmux",
+            args = [
+                "new-window",
+                flags,
+                "-t",
+                target.to_string(),
+                "-n",
+                window_name,
+                "-c",
+                path,
+                "-F",
+                "#{window_id}"
+            ]
+        ))
+    }
+
+    /// Scrollback and visible content of a pane, with colors and wrapped lines joined
+    pub(crate) fn capture_pane(&self, target: &Target) -> Result<String> {
+        self.cmd_runner.run(&cmd_query!(
+            "tmux",
+            args = [
+                "capture-pane",
+                "-p",
+                "-e",
+                "-J",
+                "-S",
+                "-",
+                "-t",
+                target.to_string()
+            ]
+        ))
+    }
+
+    pub(crate) fn respawn_pane(&self, target: &Target, path: &str) -> Result<()> {
+        self.cmd_runner.run(&cmd_basic!(
+            "tmux",
+            args = ["respawn-pane", "-k", "-t", target.to_string(), "-c", path]
+        ))
+    }
+
+    pub(crate) fn session_start_path(&self, session: Option<&str>) -> Result<String> {
+        let pane_map: HashMap<String, String> = self.pane_paths(session)?;
         let pane_paths: Vec<PathBuf> = pane_map.values().map(PathBuf::from).collect();
 
         if pane_paths.is_empty() {
@@ -477,10 +825,10 @@ impl<R: Runner> TmuxClient<R> {
         Ok(common_prefix.to_string_lossy().into_owned())
     }
 
-    pub(crate) fn pane_paths(&self) -> Result<HashMap<String, String>> {
-        let output: String = self.cmd_runner.run(&cmd_basic!(
-            "tmux",
-            args = ["list-panes", "-s", "-F", "#{pane_id} #{pane_current_path}"]
+    pub(crate) fn pane_paths(&self, session: Option<&str>) -> Result<HashMap<String, String>> {
+        let output: String = self.cmd_runner.run(&in_session(
+            session,
+            &["list-panes", "-s", "-F", "#{pane_id} #{pane_current_path}"],
         ))?;
 
         let pane_map = output
@@ -500,12 +848,12 @@ impl<R: Runner> TmuxClient<R> {
         Ok(pane_map)
     }
 
-    pub(crate) fn pane_command(&self) -> Result<HashMap<String, String>> {
+    pub(crate) fn pane_command(&self, session: Option<&str>) -> Result<HashMap<String, String>> {
         let current_pid = process::id().to_string();
 
-        let output: String = self.cmd_runner.run(&cmd_basic!(
-            "tmux",
-            args = ["list-panes", "-s", "-F", "#{pane_id} #{pane_pid}"]
+        let output: String = self.cmd_runner.run(&in_session(
+            session,
+            &["list-panes", "-s", "-F", "#{pane_id} #{pane_pid}"],
         ))?;
 
         let mut pane_map: HashMap<String, String> = HashMap::new();
@@ -524,7 +872,7 @@ impl<R: Runner> TmuxClient<R> {
                 // Fallback to pgrep for testing - this maintains test compatibility
                 let child_pids_output = self
                     .cmd_runner
-                    .run(&cmd_basic!("pgrep", args = ["-P", pane_pid.to_string()]))
+                    .run(&cmd_query!("pgrep", args = ["-P", pane_pid.to_string()]))
                     .unwrap_or_else(|_| String::new());
 
                 for child_pid in child_pids_output
@@ -534,7 +882,7 @@ impl<R: Runner> TmuxClient<R> {
                 {
                     let cmd_output: String = self
                         .cmd_runner
-                        .run(&cmd_basic!("ps", args = ["-p", child_pid, "-o", "args="]))?;
+                        .run(&cmd_query!("ps", args = ["-p", child_pid, "-o", "args="]))?;
                     let command = cmd_output.trim();
 
                     if !command.is_empty() && !command.starts_with('-') {
@@ -592,10 +940,15 @@ impl<R: Runner> TmuxClient<R> {
     }
 
     /// Get the command line of a process using sysinfo
-    fn get_process_command(pid: i32) -> Option<String> {
+    pub(crate) fn get_process_command(pid: i32) -> Option<String> {
         let mut system = System::new();
         let pid = Pid::from_u32(pid as u32);
-        system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+        // The command line isn't read by a plain refresh
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::nothing().with_cmd(UpdateKind::Always),
+        );
 
         system.process(pid).and_then(|process| {
             let cmd_args = process.cmd();
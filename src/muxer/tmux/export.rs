@@ -0,0 +1,443 @@
+//! Export of laio sessions for tmux users without laio: a standalone shell script
+//! and tmuxinator projects.
+//!
+//! Both use the custom layouts `start` applies, computed for [`DIMENSIONS`]. tmux
+//! scales them to the terminal once the session is attached.
+
+use std::{collections::BTreeMap, env};
+
+use miette::{IntoDiagnostic, Result};
+use noyalib::compat::serde_yaml::{Mapping, Value};
+
+use crate::common::{
+    cmd::quote,
+    config::{Command, Pane, Script, Session},
+    path::sanitize_path,
+};
+
+use super::{
+    client::layout_checksum,
+    mux::{first_pane_path, generate_layout, LayoutInfo},
+    Dimensions,
+};
+
+/// Size of the window the layouts are computed for.
+const DIMENSIONS: Dimensions = Dimensions {
+    width: 200,
+    height: 50,
+};
+
+/// A shell script that starts `session` with plain tmux commands, or attaches to it
+/// if it's running already.
+pub(crate) fn script(session: &Session, warnings: &mut Vec<String>) -> Result<String> {
+    let mut writer = ScriptWriter::default();
+    writer.lines.extend([
+        "#!/bin/sh".to_string(),
+        format!(
+            "# Starts the tmux session '{}', exported from laio.",
+            session.name
+        ),
+        "set -e".to_string(),
+        String::new(),
+        format!("session={}", quote(&session.name)),
+        String::new(),
+        "if tmux has-session -t \"=$session\" 2>/dev/null; then".to_string(),
+        "    [ -n \"$TMUX\" ] && exec tmux switch-client -t \"=$session\"".to_string(),
+        "    exec tmux attach-session -t \"=$session\"".to_string(),
+        "fi".to_string(),
+        String::new(),
+    ]);
+
+    if !session.startup.is_empty() || session.startup_script.is_some() {
+        writer
+            .lines
+            .push(format!("cd {}", shell_path(&session.path)));
+        for command in &session.startup {
+            let mut words = vec![quote(&command.command)];
+            words.extend(
+                command
+                    .to_process_command()
+                    .get_args()
+                    .map(|arg| quote(&arg.to_string_lossy())),
+            );
+            writer.lines.push(words.join(" "));
+        }
+        if let Some(script) = &session.startup_script {
+            let file = writer.script_file(script);
+            writer.lines.push(format!("\"${file}\""));
+        }
+        writer.lines.push(String::new());
+    }
+    if !session.shutdown.is_empty() || session.shutdown_script.is_some() {
+        warnings.push("Shutdown commands are not exported".to_string());
+    }
+
+    let first_path = session
+        .windows
+        .first()
+        .map(|window| first_pane_path(window, &window.effective_path(&session.path)))
+        .unwrap_or_else(|| session.path.clone());
+    let mut new_session = format!(
+        "tmux new-session -d -s \"$session\" -c {} -x {} -y {}",
+        shell_path(&first_path),
+        DIMENSIONS.width,
+        DIMENSIONS.height
+    );
+    for (key, value) in session.env.iter().collect::<BTreeMap<_, _>>() {
+        new_session.push_str(&format!(" -e {}", quote(&format!("{key}={value}"))));
+    }
+    writer.lines.push(new_session);
+    if let Some(shell) = &session.shell {
+        writer.lines.push(format!(
+            "tmux set-option -t \"$session\" default-shell {}",
+            quote(shell)
+        ));
+    }
+
+    let mut focused_window = None;
+    for (index, window) in session.windows.iter().enumerate() {
+        let window_var = format!("w{}", index + 1);
+        let window_path = window.effective_path(&session.path);
+        writer.lines.push(String::new());
+        if index == 0 {
+            writer.lines.push(format!(
+                "{window_var}=$(tmux display-message -p -t \"$session\" '#{{window_id}}')"
+            ));
+            writer.lines.push(format!(
+                "tmux rename-window -t \"${window_var}\" {}",
+                quote(&window.name)
+            ));
+        } else {
+            writer.lines.push(format!(
+                "{window_var}=$(tmux new-window -d -P -F '#{{window_id}}' -t \"$session\" -n {} -c {})",
+                quote(&window.name),
+                shell_path(&first_pane_path(window, &window_path))
+            ));
+        }
+        if window.focus {
+            focused_window = Some(window_var.clone());
+        }
+        if window.panes.is_empty() {
+            continue;
+        }
+
+        writer.panes += 1;
+        writer.current = writer.panes;
+        writer.lines.push(format!(
+            "p{}=$(tmux display-message -p -t \"${window_var}\" '#{{pane_id}}')",
+            writer.current
+        ));
+        let layout = generate_layout(
+            &LayoutInfo {
+                dimensions: &DIMENSIONS,
+                direction: &window.flex_direction,
+                xy: (0, 0),
+            },
+            &window.panes,
+            0,
+            &mut |pane, index| writer.pane(pane, index, &window_var, &window_path),
+        )?;
+        writer.lines.push(format!(
+            "tmux select-layout -t \"${window_var}\" {}",
+            quote(&format!("{},{layout}", layout_checksum(&layout)))
+        ));
+    }
+
+    if !writer.deferred.is_empty() {
+        writer.lines.push(String::new());
+        if let Some(delay) = session.pane_cmd_delay {
+            writer
+                .lines
+                .push(format!("sleep {}", delay as f64 / 1000.0));
+        }
+        let deferred = std::mem::take(&mut writer.deferred);
+        writer.lines.extend(deferred);
+    }
+
+    writer.lines.push(String::new());
+    if let Some(window_var) = focused_window {
+        writer
+            .lines
+            .push(format!("tmux select-window -t \"${window_var}\""));
+    }
+    writer.lines.extend([
+        "[ -n \"$TMUX\" ] && exec tmux switch-client -t \"=$session\"".to_string(),
+        "exec tmux attach-session -t \"=$session\"".to_string(),
+    ]);
+
+    Ok(writer.lines.join("\n") + "\n")
+}
+
+#[derive(Default)]
+struct ScriptWriter {
+    lines: Vec<String>,
+    /// Commands run once all windows are laid out, as `start` does.
+    deferred: Vec<String>,
+    /// Number of pane variables, `p1` to `pN`.
+    panes: usize,
+    /// Pane variable of the pane tmux made active last.
+    current: usize,
+    /// Number of script file variables, `s1` to `sN`.
+    scripts: usize,
+}
+
+impl ScriptWriter {
+    /// Creates `pane` in the window of `window_var` the way `start` does, and
+    /// returns the number of its pane variable as its id in the layout.
+    fn pane(
+        &mut self,
+        pane: &Pane,
+        index: usize,
+        window_var: &str,
+        window_path: &str,
+    ) -> Result<String> {
+        if index > 0 {
+            let path = sanitize_path(
+                pane.first_leaf_path().unwrap_or(&".".to_string()),
+                &window_path.to_string(),
+            );
+            self.panes += 1;
+            self.current = self.panes;
+            self.lines.push(format!(
+                "p{}=$(tmux split-window -t \"${window_var}\" -c {} -P -F '#{{pane_id}}')",
+                self.current,
+                shell_path(&path)
+            ));
+        }
+        let target = format!("\"$p{}\"", self.current);
+
+        if let Some(name) = &pane.name {
+            self.deferred
+                .push(format!("tmux select-pane -t {target} -T {}", quote(name)));
+        }
+        if pane.zoom {
+            self.deferred
+                .push(format!("tmux resize-pane -Z -t {target}"));
+        }
+        if pane.focus {
+            self.deferred
+                .push(format!("tmux select-pane -Z -t {target}"));
+        }
+        if let Some(style) = &pane.style {
+            self.lines
+                .push(format!("tmux select-pane -t {target} -P {}", quote(style)));
+        }
+        self.lines
+            .push(format!("tmux select-layout -t \"${window_var}\" tiled"));
+
+        let mut keys: Vec<String> = pane
+            .commands
+            .iter()
+            .flat_map(|command| [quote(&command.to_string()), "C-m".to_string()])
+            .collect();
+        if let Some(script) = &pane.script {
+            let file = self.script_file(script);
+            keys.extend([format!("\"${file}\""), "C-m".to_string()]);
+        }
+        if !keys.is_empty() {
+            self.deferred
+                .push(format!("tmux send-keys -t {target} {}", keys.join(" ")));
+        }
+
+        Ok(self.current.to_string())
+    }
+
+    /// Writes `script` to a temporary file, returning the variable holding its path.
+    fn script_file(&mut self, script: &Script) -> String {
+        self.scripts += 1;
+        let var = format!("s{}", self.scripts);
+        self.lines.extend([
+            format!("{var}=$(mktemp)"),
+            format!("cat > \"${var}\" <<'LAIO_SCRIPT'"),
+            script.to_string().trim_end().to_string(),
+            "LAIO_SCRIPT".to_string(),
+            format!("chmod +x \"${var}\""),
+        ]);
+        var
+    }
+}
+
+/// A tmuxinator project for `session`. Panes are listed in the order tmux numbers
+/// them, and nested splits become a custom layout.
+pub(crate) fn tmuxinator(session: &Session, warnings: &mut Vec<String>) -> Result<String> {
+    let mut project = Mapping::new();
+    project.insert("name", Value::String(session.name.clone()));
+    project.insert("root", Value::String(home_path(&session.path)));
+
+    let commands = |commands: &[Command]| -> Value {
+        Value::Sequence(
+            commands
+                .iter()
+                .map(|command| Value::String(command.to_string()))
+                .collect(),
+        )
+    };
+    if !session.startup.is_empty() {
+        project.insert("on_project_start", commands(&session.startup));
+    }
+    if !session.shutdown.is_empty() {
+        project.insert("on_project_stop", commands(&session.shutdown));
+    }
+    if session.startup_script.is_some() || session.shutdown_script.is_some() {
+        warnings.push("Startup and shutdown scripts are not exported".to_string());
+    }
+    // tmuxinator has no environment, so panes export it before their commands
+    if !session.env.is_empty() {
+        let exports = session
+            .env
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(key, value)| Value::String(format!("export {key}={}", quote(value))))
+            .collect();
+        project.insert("pre_window", Value::Sequence(exports));
+    }
+    if let Some(shell) = &session.shell {
+        warnings.push(format!("Shell '{shell}' is not exported"));
+    }
+    if session.pane_cmd_delay.is_some() {
+        warnings.push("Pane command delay is not exported".to_string());
+    }
+
+    let has_titles = session
+        .windows
+        .iter()
+        .any(|window| window.leaf_panes().any(|pane| pane.name.is_some()));
+    if has_titles {
+        project.insert("enable_pane_titles", Value::Bool(true));
+    }
+
+    if let Some(window) = session.windows.iter().find(|window| window.focus) {
+        project.insert("startup_window", Value::String(window.name.clone()));
+        if let Some(index) = window.leaf_panes().position(|pane| pane.focus) {
+            project.insert("startup_pane", Value::from(index as u64));
+        }
+    }
+
+    let mut windows = vec![];
+    for window in &session.windows {
+        let window_path = window.effective_path(&session.path);
+        let mut settings = Mapping::new();
+        if window.path.is_some() {
+            settings.insert("root", Value::String(home_path(&window_path)));
+        }
+
+        let panes: Vec<&Pane> = window.leaf_panes().collect();
+        if split_panes(&window.panes)
+            .iter()
+            .any(|pane| !pane.commands.is_empty() || pane.script.is_some())
+        {
+            warnings.push(format!(
+                "Commands of split panes in '{}' are not exported",
+                window.name
+            ));
+        }
+        if panes.len() > 1 {
+            let mut id = 0;
+            let layout = generate_layout(
+                &LayoutInfo {
+                    dimensions: &DIMENSIONS,
+                    direction: &window.flex_direction,
+                    xy: (0, 0),
+                },
+                &window.panes,
+                0,
+                &mut |_, _| {
+                    id += 1;
+                    Ok(id.to_string())
+                },
+            )?;
+            settings.insert(
+                "layout",
+                Value::String(format!("{},{layout}", layout_checksum(&layout))),
+            );
+        }
+
+        if !panes.is_empty() {
+            let panes = panes
+                .iter()
+                .map(|pane| tmuxinator_pane(pane, &window.name, &window_path, warnings))
+                .collect();
+            settings.insert("panes", Value::Sequence(panes));
+        }
+
+        let mut entry = Mapping::new();
+        entry.insert(
+            window.name.clone(),
+            match settings.len() {
+                0 => Value::Null,
+                _ => Value::Mapping(settings),
+            },
+        );
+        windows.push(Value::Mapping(entry));
+    }
+    project.insert("windows", Value::Sequence(windows));
+
+    noyalib::compat::serde_yaml::to_string(&Value::Mapping(project)).into_diagnostic()
+}
+
+fn tmuxinator_pane(
+    pane: &Pane,
+    window: &str,
+    window_path: &str,
+    warnings: &mut Vec<String>,
+) -> Value {
+    if pane.zoom || pane.style.is_some() {
+        warnings.push(format!(
+            "Pane zoom and style in '{window}' are not exported"
+        ));
+    }
+    if pane.script.is_some() {
+        warnings.push(format!("Pane script in '{window}' is not exported"));
+    }
+
+    // tmuxinator opens all panes in the window root
+    let mut commands = vec![];
+    if pane.path != "." {
+        let path = sanitize_path(&pane.path, &window_path.to_string());
+        commands.push(format!("cd {}", shell_path(&home_path(&path))));
+    }
+    commands.extend(pane.commands.iter().map(|command| command.to_string()));
+
+    let value = match commands.len() {
+        0 => Value::Null,
+        1 => Value::String(commands.remove(0)),
+        _ => Value::Sequence(commands.into_iter().map(Value::String).collect()),
+    };
+    match &pane.name {
+        Some(name) => {
+            let mut titled = Mapping::new();
+            titled.insert(name.clone(), value);
+            Value::Mapping(titled)
+        }
+        None => value,
+    }
+}
+
+/// The panes holding other panes, which tmux does not create.
+fn split_panes(panes: &[Pane]) -> Vec<&Pane> {
+    panes
+        .iter()
+        .filter(|pane| !pane.panes.is_empty())
+        .flat_map(|pane| std::iter::once(pane).chain(split_panes(&pane.panes)))
+        .collect()
+}
+
+/// `path` with the home directory as `~`.
+fn home_path(path: &str) -> String {
+    match env::var("HOME") {
+        Ok(home) if !home.is_empty() && (path == home || path.starts_with(&format!("{home}/"))) => {
+            path.replacen(&home, "~", 1)
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// `path` quoted for the shell, keeping the home directory expandable.
+fn shell_path(path: &str) -> String {
+    match home_path(path).strip_prefix('~') {
+        Some("") => "\"$HOME\"".to_string(),
+        Some(rest) => format!("\"$HOME\"{}", quote(rest)),
+        None => quote(path),
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::VecDeque, sync::Arc};
 
 use miette::{Result, bail};
 
@@ -6,10 +6,13 @@ use crate::{
     app::manager::session::manager::LAIO_CONFIG,
     common::{
         cmd::{Runner, ShellRunner},
-        config::{FlexDirection, Pane, Session, Window},
+        config::{FlexDirection, Pane, Session, Window, secrets::Secrets},
         muxer::{Client, Multiplexer},
-        path::{home_dir, resolve_symlink, sanitize_path, to_absolute_path},
-        session_info::SessionInfo,
+        path::{
+            config_search_path, home_dir, resolve_symlink, sanitize_path, split_search_path,
+            to_absolute_path,
+        },
+        session_info::{SessionDetails, SessionInfo},
     },
     muxer::tmux::parser::parse,
     tmux_target,
@@ -18,7 +21,7 @@ use crate::{
 /// Path for the first pane of `window`, resolved against the window's
 /// effective working directory. If the window declares no panes the
 /// window path is used directly — tmux auto-creates one pane there.
-fn first_pane_path(window: &Window, window_path: &str) -> String {
+pub(crate) fn first_pane_path(window: &Window, window_path: &str) -> String {
     match window.first_leaf_path() {
         Some(p) => sanitize_path(p, &window_path.to_string()),
         None => window_path.to_string(),
@@ -27,10 +30,10 @@ fn first_pane_path(window: &Window, window_path: &str) -> String {
 
 use super::{Dimensions, Target, client::TmuxClient};
 
-struct LayoutInfo<'a> {
-    dimensions: &'a Dimensions,
-    direction: &'a FlexDirection,
-    xy: (usize, usize),
+pub(crate) struct LayoutInfo<'a> {
+    pub(crate) dimensions: &'a Dimensions,
+    pub(crate) direction: &'a FlexDirection,
+    pub(crate) xy: (usize, usize),
 }
 
 struct LayoutMeta<'a> {
@@ -49,21 +52,25 @@ struct CalculateInfo {
 
 pub(crate) struct Tmux<R: Runner = ShellRunner> {
     client: TmuxClient<R>,
-}
-
-impl Tmux {
-    pub fn new() -> Self {
-        Self::new_with_runner(ShellRunner::new())
-    }
+    /// Used to render the configs of running sessions.
+    secrets: Secrets,
 }
 
 impl<R: Runner> Tmux<R> {
     pub fn new_with_runner(runner: R) -> Self {
         Self {
             client: TmuxClient::new(Arc::new(runner)),
+            secrets: Secrets::default(),
         }
     }
 
+    /// Uses `secrets` to render the configs of running sessions, e.g. for their
+    /// shutdown hooks.
+    pub(crate) fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
     fn process_windows(
         &self,
         session: &Session,
@@ -93,272 +100,272 @@ impl<R: Runner> Tmux<R> {
                 };
                 log::trace!("window-id: {window_id}");
 
-                if window.panes.is_empty() {
-                    return Ok(());
-                }
-
-                self.client.select_custom_layout(
-                    &tmux_target!(&session.name, &window_id),
-                    &self.generate_layout(
-                        &LayoutMeta {
-                            name: session.name.as_str(),
-                            id: window_id.as_str(),
-                            path: window_path.as_str(),
-                        },
-                        &LayoutInfo {
-                            dimensions,
-                            direction: &window.flex_direction,
-                            xy: (0, 0),
-                        },
-                        &window.panes,
-                        skip_cmds,
-                        0,
-                    )?,
-                )?;
-
-                Ok(())
-            })
-    }
-
-    fn calculate_pane_dimensions(
-        &self,
-        layout_info: &LayoutInfo,
-        calculate_info: &CalculateInfo,
-        panes: &[Pane],
-    ) -> Option<(usize, usize, usize, usize)> {
-        let (current_x, current_y) = layout_info.xy;
-        let index = calculate_info.index;
-        let (pane_width, pane_height, next_x, next_y) = match layout_info.direction {
-            FlexDirection::Column => {
-                let h = self.calculate_dimension(
-                    calculate_info,
-                    index == panes.len() - 1,
-                    current_y,
-                    layout_info.dimensions.height,
-                )?;
-                (
-                    layout_info.dimensions.width,
-                    h,
-                    layout_info.xy.0,
-                    layout_info.xy.1 + h + 1,
-                )
-            }
-            _ => {
-                let w = self.calculate_dimension(
-                    calculate_info,
-                    index == panes.len() - 1,
-                    current_x,
-                    layout_info.dimensions.width,
-                )?;
-                (
-                    w,
-                    layout_info.dimensions.height,
-                    current_x + w + 1,
-                    current_y,
+                self.layout_window(
+                    &LayoutMeta {
+                        name: session.name.as_str(),
+                        id: window_id.as_str(),
+                        path: window_path.as_str(),
+                    },
+                    window,
+                    dimensions,
+                    skip_cmds,
                 )
-            }
-        };
-        Some((pane_width, pane_height, next_x, next_y))
-    }
-
-    fn calculate_dimension(
-        &self,
-        calculate_info: &CalculateInfo,
-        is_last_pane: bool,
-        current_value: usize,
-        total_value: usize,
-    ) -> Option<usize> {
-        let (flex, flex_total, dividers, depth, index) = (
-            calculate_info.flex,
-            calculate_info.flex_total,
-            calculate_info.dividers,
-            calculate_info.depth,
-            calculate_info.index,
-        );
-        if is_last_pane {
-            log::trace!("current_value: {current_value}, total_value: {total_value}");
-            if current_value >= total_value {
-                log::warn!(
-                    "skipping pane: total_value: {total_value}, current_value: {current_value}"
-                );
-                return None;
-            }
-            Some(total_value - current_value)
-        } else {
-            Some(if depth > 0 || index > 0 {
-                total_value * flex / flex_total - dividers
-            } else {
-                total_value * flex / flex_total
             })
-        }
     }
 
-    fn generate_pane_string(
+    /// Splits the new window of `layout_meta` into the panes of `window` and applies
+    /// their layout.
+    fn layout_window(
         &self,
         layout_meta: &LayoutMeta,
-        layout_info: &LayoutInfo,
-        pane: &Pane,
-        depth: usize,
-        pane_id: &str,
+        window: &Window,
+        dimensions: &Dimensions,
         skip_cmds: bool,
-    ) -> Result<String> {
-        let pane_string = if !pane.panes.is_empty() {
-            self.generate_layout(
+    ) -> Result<()> {
+        if window.panes.is_empty() {
+            return Ok(());
+        }
+
+        self.client.select_custom_layout(
+            &tmux_target!(layout_meta.name, layout_meta.id),
+            &self.generate_layout(
                 layout_meta,
                 &LayoutInfo {
-                    dimensions: layout_info.dimensions,
-                    direction: &pane.flex_direction,
-                    xy: layout_info.xy,
+                    dimensions,
+                    direction: &window.flex_direction,
+                    xy: (0, 0),
                 },
-                &pane.panes,
+                &window.panes,
                 skip_cmds,
-                depth + 1,
-            )?
+            )?,
+        )
+    }
+
+    /// Splits the existing window of `layout_meta` into the panes of `window` that are
+    /// missing and applies their layout. Panes already there are kept in order, with
+    /// `prune` the ones left over are closed. Returns a warning if the layout can't be
+    /// applied because of left over panes.
+    fn apply_window(
+        &self,
+        layout_meta: &LayoutMeta,
+        window: &Window,
+        dimensions: &Dimensions,
+        prune: bool,
+        skip_cmds: bool,
+    ) -> Result<Option<String>> {
+        let (session_name, window_id) = (layout_meta.name, layout_meta.id);
+        let mut existing: VecDeque<String> = self
+            .client
+            .window_panes(&tmux_target!(session_name, window_id))?
+            .into();
+        let mut last = existing.back().cloned().unwrap_or_default();
+
+        let layout = if window.panes.is_empty() {
+            existing.pop_front();
+            None
         } else {
-            let (current_x, current_y) = layout_info.xy;
-            format!(
-                "{0}x{1},{2},{3},{4}",
-                layout_info.dimensions.width,
-                layout_info.dimensions.height,
-                current_x,
-                current_y,
-                pane_id.replace('%', "")
-            )
+            let mut current: Option<String> = None;
+            let layout = generate_layout(
+                &LayoutInfo {
+                    dimensions,
+                    direction: &window.flex_direction,
+                    xy: (0, 0),
+                },
+                &window.panes,
+                0,
+                &mut |pane, index| {
+                    // The first pane of a split is the pane that was split
+                    if let (0, Some(id)) = (index, &current) {
+                        return Ok(id.clone());
+                    }
+                    let id = match existing.pop_front() {
+                        Some(id) => id,
+                        None => {
+                            // Splitting the last pane keeps the new one last in the layout
+                            let path = sanitize_path(
+                                pane.first_leaf_path().unwrap_or(&".".to_string()),
+                                &layout_meta.path.to_string(),
+                            );
+                            let id = self.client.split_window(
+                                &tmux_target!(session_name, window_id, &last),
+                                &path,
+                            )?;
+                            self.setup_pane(layout_meta, pane, &id, skip_cmds)?;
+                            last = id.clone();
+                            id
+                        }
+                    };
+                    current = Some(id.clone());
+                    Ok(id)
+                },
+            )?;
+            // A single pane fills the window
+            (window.panes.len() > 1).then_some(layout)
         };
-        Ok(pane_string)
+
+        if !existing.is_empty() {
+            if !prune {
+                return Ok(Some(format!(
+                    "Window '{}' has {} more pane(s) than its config, its layout was kept",
+                    window.name,
+                    existing.len()
+                )));
+            }
+            for pane_id in existing {
+                self.client
+                    .kill_pane(&tmux_target!(session_name, window_id, &pane_id))?;
+            }
+        }
+
+        if let Some(layout) = layout {
+            self.client
+                .select_custom_layout(&tmux_target!(session_name, window_id), &layout)?;
+        }
+        Ok(None)
     }
 
+    /// Splits the window of `layout_meta` into `panes` and returns their layout.
     fn generate_layout(
         &self,
         layout_meta: &LayoutMeta,
         layout_info: &LayoutInfo,
         panes: &[Pane],
         skip_cmds: bool,
-        depth: usize,
     ) -> Result<String> {
-        let flex_total = panes.iter().map(|p| p.flex).sum();
+        generate_layout(layout_info, panes, 0, &mut |pane, index| {
+            self.create_pane(layout_meta, pane, index, skip_cmds)
+        })
+    }
 
-        let (mut current_x, mut current_y) = layout_info.xy;
+    /// Creates `pane` by splitting the window, or in the current pane if it's the
+    /// first of its siblings. Returns the id of the pane.
+    fn create_pane(
+        &self,
+        layout_meta: &LayoutMeta,
+        pane: &Pane,
+        index: usize,
+        skip_cmds: bool,
+    ) -> Result<String> {
+        let window_path = layout_meta.path;
 
-        let mut pane_strings: Vec<String> = Vec::new();
-        let mut num_dividers = 0;
+        let pane_id = if index > 0 {
+            let path = sanitize_path(
+                pane.first_leaf_path().unwrap_or(&".".to_string()),
+                &window_path.to_string(),
+            );
+            self.client
+                .split_window(&tmux_target!(layout_meta.name, layout_meta.id), &path)?
+        } else {
+            self.client
+                .get_current_pane(&tmux_target!(layout_meta.name, layout_meta.id))?
+        };
 
+        self.setup_pane(layout_meta, pane, &pane_id, skip_cmds)?;
+        Ok(pane_id)
+    }
+
+    /// Sets up the new pane `pane_id` of the window of `layout_meta` and registers its
+    /// commands.
+    fn setup_pane(
+        &self,
+        layout_meta: &LayoutMeta,
+        pane: &Pane,
+        pane_id: &str,
+        skip_cmds: bool,
+    ) -> Result<()> {
         let session_name = layout_meta.name;
-        let window_path = layout_meta.path;
         let window_id = layout_meta.id;
 
-        for (index, pane) in panes.iter().enumerate() {
-            let (pane_width, pane_height, next_x, next_y) = match self.calculate_pane_dimensions(
-                &LayoutInfo {
-                    dimensions: layout_info.dimensions,
-                    direction: layout_info.direction,
-                    xy: (current_x, current_y),
-                },
-                &CalculateInfo {
-                    depth,
-                    dividers: num_dividers,
-                    flex: pane.flex,
-                    index,
-                    flex_total,
-                },
-                panes,
-            ) {
-                Some(value) => value,
-                None => continue,
-            };
-
-            if depth > 0 || index > 0 {
-                num_dividers += 1;
-            }
+        if let Some(name) = &pane.name {
+            self.client.set_pane_title(
+                &tmux_target!(session_name, window_id, pane_id),
+                name.as_str(),
+            );
+        };
 
-            let pane_id = if index > 0 {
-                let path = sanitize_path(
-                    pane.first_leaf_path().unwrap_or(&".".to_string()),
-                    &window_path.to_string(),
-                );
-                self.client
-                    .split_window(&tmux_target!(session_name, window_id), &path)?
-            } else {
-                self.client
-                    .get_current_pane(&tmux_target!(session_name, window_id))?
-            };
+        if pane.zoom {
+            self.client
+                .zoom_pane(&tmux_target!(session_name, window_id, pane_id));
+        };
 
-            if let Some(name) = &pane.name {
-                self.client.set_pane_title(
-                    &tmux_target!(session_name, window_id, pane_id.as_str()),
-                    name.as_str(),
-                );
-            };
+        if pane.focus {
+            self.client
+                .focus_pane(&tmux_target!(session_name, window_id, pane_id));
+        };
 
-            if pane.zoom {
-                self.client
-                    .zoom_pane(&tmux_target!(session_name, window_id, pane_id.as_str()));
-            };
+        if let Some(style) = &pane.style {
+            self.client
+                .set_pane_style(&tmux_target!(session_name, window_id, pane_id), style)?;
+        }
 
-            if pane.focus {
-                self.client
-                    .focus_pane(&tmux_target!(session_name, window_id, pane_id.as_str()));
-            };
+        self.client
+            .select_layout(&tmux_target!(session_name, window_id), "tiled")?;
 
-            if let Some(style) = &pane.style {
-                self.client.set_pane_style(
-                    &tmux_target!(session_name, window_id, pane_id.as_str()),
-                    style,
-                )?;
+        if !skip_cmds {
+            let mut commands = pane.commands.clone();
+            if let Some(script) = &pane.script {
+                commands.push(script.to_cmd(self.client.get_runner().is_dry_run())?);
             }
-
             self.client
-                .select_layout(&tmux_target!(session_name, window_id), "tiled")?;
+                .register_commands(&tmux_target!(session_name, window_id, pane_id), &commands);
+        };
 
-            pane_strings.push(self.generate_pane_string(
-                layout_meta,
-                &LayoutInfo {
-                    dimensions: &Dimensions {
-                        width: pane_width,
-                        height: pane_height,
-                    },
-                    direction: layout_info.direction,
-                    xy: (current_x, current_y),
-                },
-                pane,
-                depth,
-                &pane_id,
-                skip_cmds,
-            )?);
+        Ok(())
+    }
 
-            (current_x, current_y) = (next_x, next_y);
-            if !skip_cmds {
-                let mut commands = pane.commands.clone();
-                if let Some(script) = &pane.script {
-                    commands.push(script.to_cmd()?);
-                }
-                self.client.register_commands(
-                    &tmux_target!(session_name, window_id, pane_id.as_str()),
-                    &commands,
-                );
-            };
-        }
+    /// Restarts the pane named `pane` of the window of `layout_meta` in its path and
+    /// registers its commands. Panes are matched with the config by position.
+    fn respawn_pane(
+        &self,
+        layout_meta: &LayoutMeta,
+        window: &Window,
+        pane: &str,
+        skip_cmds: bool,
+    ) -> Result<()> {
+        let leaves: Vec<&Pane> = window.leaf_panes().collect();
+        let Some(position) = leaves.iter().position(|p| p.name.as_deref() == Some(pane)) else {
+            bail!("Window '{}' has no pane named '{pane}'", window.name);
+        };
+        let config_pane = leaves[position];
+
+        let pane_ids = self
+            .client
+            .window_panes(&tmux_target!(layout_meta.name, layout_meta.id))?;
+        let Some(pane_id) = pane_ids.get(position) else {
+            bail!(
+                "Window '{}' has {} pane(s), pane '{pane}' is pane {} of its config",
+                window.name,
+                pane_ids.len(),
+                position + 1
+            );
+        };
+        let target = tmux_target!(layout_meta.name, layout_meta.id, pane_id);
 
-        if pane_strings.len() > 1 {
-            let (open_delimiter, close_delimiter) = match layout_info.direction {
-                FlexDirection::Column => ('[', ']'),
-                FlexDirection::Row => ('{', '}'),
-            };
+        self.client.respawn_pane(
+            &target,
+            &sanitize_path(&config_pane.path, &layout_meta.path.to_string()),
+        )?;
+        // The layout, zoom and focus of the window are left as they are
+        self.client.set_pane_title(&target, pane);
+        if let Some(style) = &config_pane.style {
+            self.client.set_pane_style(&target, style)?;
+        }
+        if !skip_cmds {
+            let mut commands = config_pane.commands.clone();
+            if let Some(script) = &config_pane.script {
+                commands.push(script.to_cmd(self.client.get_runner().is_dry_run())?);
+            }
+            self.client.register_commands(&target, &commands);
+        }
+        Ok(())
+    }
 
-            Ok(format!(
-                "{}x{},0,0{}{}{}",
-                layout_info.dimensions.width,
-                layout_info.dimensions.height,
-                open_delimiter,
-                pane_strings.join(","),
-                close_delimiter
-            ))
-        } else {
-            Ok(format!(
-                "{}x{},0,0",
-                layout_info.dimensions.width, layout_info.dimensions.height
-            ))
+    fn run_shutdown(&self, session: &Session) -> Result<()> {
+        let mut commands = session.shutdown.clone();
+        if let Some(script) = &session.shutdown_script {
+            commands.push(script.to_cmd(self.client.get_runner().is_dry_run())?);
         }
+        self.client.run_commands(&commands, &session.path)
     }
 
     fn is_laio_session(&self, name: &str) -> Result<bool> {
@@ -383,7 +390,7 @@ impl<R: Runner> Multiplexer for Tmux<R> {
         if !skip_cmds {
             let mut commands = session.startup.clone();
             if let Some(script) = &session.startup_script {
-                commands.push(script.to_cmd()?);
+                commands.push(script.to_cmd(self.client.get_runner().is_dry_run())?);
             }
 
             self.client.run_commands(&commands, &session.path)?;
@@ -459,13 +466,14 @@ impl<R: Runner> Multiplexer for Tmux<R> {
 
         if stop_all || (stop_other && self.client.is_inside_session()) {
             log::trace!("Closing all/other laio sessions.");
-            self.list_sessions()?
+            self.client
+                .list_sessions()?
                 .into_iter()
-                .filter(|info| info.name != current_session_name)
-                .try_for_each(|info| -> Result<()> {
-                    if self.is_laio_session(&info.name)? {
-                        log::trace!("Closing session: {:?}", info.name);
-                        self.stop(&Some(info.name.to_string()), &None, skip_cmds, false, false)?;
+                .filter(|(name, _)| *name != current_session_name)
+                .try_for_each(|(name, _)| -> Result<()> {
+                    if self.is_laio_session(&name)? {
+                        log::trace!("Closing session: {:?}", name);
+                        self.stop(&Some(name.to_string()), &None, skip_cmds, false, false)?;
                     }
                     Ok(())
                 })?;
@@ -488,11 +496,7 @@ impl<R: Runner> Multiplexer for Tmux<R> {
             if !skip_cmds && !stop_other {
                 // If session was provided (with variables), use it directly
                 if let Some(sess) = session {
-                    let mut commands = sess.shutdown.clone();
-                    if let Some(script) = &sess.shutdown_script {
-                        commands.push(script.to_cmd()?);
-                    }
-                    return self.client.run_commands(&commands, &sess.path);
+                    return self.run_shutdown(sess);
                 }
 
                 // Otherwise, try to load from LAIO_CONFIG (backward compatibility)
@@ -506,14 +510,11 @@ impl<R: Runner> Multiplexer for Tmux<R> {
                         let sess = Session::from_config(
                             &resolve_symlink(&to_absolute_path(&config)?)?,
                             variables.as_deref(),
+                            &split_search_path(&config_search_path(None)),
+                            &self.secrets,
                         )?;
 
-                        let mut commands = sess.shutdown.clone();
-                        if let Some(script) = &sess.shutdown_script {
-                            commands.push(script.to_cmd()?);
-                        }
-
-                        self.client.run_commands(&commands, &sess.path)
+                        self.run_shutdown(&sess)
                     }
                     Err(e) => {
                         log::warn!("LAIO_CONFIG environment variable not found: {e:?}");
@@ -535,6 +536,55 @@ impl<R: Runner> Multiplexer for Tmux<R> {
         result.and(stop_result)
     }
 
+    fn restart(
+        &self,
+        name: &str,
+        running: &Option<Session>,
+        session: &Session,
+        env_vars: &[(&str, &str)],
+        skip_attach: bool,
+        skip_cmds: bool,
+    ) -> Result<()> {
+        // laio runs in the session as it was started, `name` may only be a prefix of it
+        let current = self.client.pane_session_name()?;
+        let inside = current == name || running.as_ref().is_some_and(|r| r.name == current);
+        if !inside {
+            // Without the running session there are no shutdown hooks to run
+            let skip_shutdown = skip_cmds || running.is_none();
+            self.stop(
+                &Some(name.to_string()),
+                running,
+                skip_shutdown,
+                false,
+                false,
+            )?;
+            return self.start(session, env_vars, skip_attach, skip_cmds);
+        }
+
+        // laio runs in a pane of the session, so it's only closed once the new one is up
+        if !skip_cmds && let Some(running) = running {
+            self.run_shutdown(running)?;
+        }
+        let name = current.as_str();
+        // tmux matches targets by prefix, so `name` must not be one of the old name
+        let old_name = format!("laio-restarting-{name}");
+        self.client.rename_session(name, &old_name)?;
+        if let Err(e) = self.start(session, env_vars, skip_attach, skip_cmds) {
+            // Don't leave the session renamed, nor what was started of the new one
+            let _ = self.client.stop_session(&format!("={}", session.name));
+            if let Err(rename) = self.client.rename_session(&old_name, name) {
+                log::warn!("Could not rename '{old_name}' back to '{name}': {rename:?}");
+            }
+            return Err(e);
+        }
+        self.client.stop_session(&old_name)
+    }
+
+    fn current_session_name(&self) -> Result<Option<String>> {
+        let name = self.client.pane_session_name()?;
+        Ok((!name.is_empty()).then_some(name))
+    }
+
     fn get_session_config_path(&self, name: &str) -> Result<Option<String>> {
         match self.client.getenv(&tmux_target!(name), LAIO_CONFIG) {
             Ok(config_path) => Ok(Some(config_path)),
@@ -542,6 +592,11 @@ impl<R: Runner> Multiplexer for Tmux<R> {
         }
     }
 
+    fn set_session_config_path(&self, name: &str, config_path: &str) -> Result<()> {
+        self.client
+            .set_environment(&tmux_target!(name), LAIO_CONFIG, config_path)
+    }
+
     fn get_session_variables(&self, name: &str) -> Result<Option<Vec<String>>> {
         use crate::app::manager::session::manager::{LAIO_VARS, decode_variables};
 
@@ -568,12 +623,46 @@ impl<R: Runner> Multiplexer for Tmux<R> {
     }
 
     fn list_sessions(&self) -> Result<Vec<SessionInfo>> {
-        self.client.list_sessions().map(|sessions| {
-            sessions
-                .into_iter()
-                .map(|(name, is_attached)| SessionInfo::active(name, is_attached))
-                .collect()
-        })
+        use crate::app::manager::session::manager::{LAIO_VARS, decode_variables};
+
+        let panes = self.client.pane_counts()?;
+        Ok(self
+            .client
+            .list_sessions()?
+            .into_iter()
+            .map(|(name, details)| {
+                let is_attached = details.clients.is_some_and(|clients| clients > 0);
+                let details = SessionDetails {
+                    panes: panes.get(&name).copied(),
+                    ..details
+                };
+
+                // A session laio can't read is still listed, without laio's details
+                let env = match self.client.laio_env(&tmux_target!(&name)) {
+                    Ok(env) => env,
+                    Err(e) => {
+                        log::warn!("Could not read the environment of session '{name}': {e:?}");
+                        return SessionInfo::active(name, is_attached)
+                            .with_session_details(details, None);
+                    }
+                };
+                let variables = match env.get(LAIO_VARS).map(|vars| decode_variables(vars)) {
+                    Some(Ok(variables)) => variables,
+                    Some(Err(e)) => {
+                        log::warn!("Failed to decode LAIO_VARS for session '{name}': {e:?}");
+                        vec![]
+                    }
+                    None => vec![],
+                };
+                let config_path = env.get(LAIO_CONFIG).cloned();
+                let details = SessionDetails {
+                    variables,
+                    is_laio_session: Some(config_path.is_some()),
+                    ..details
+                };
+                SessionInfo::active(name, is_attached).with_session_details(details, config_path)
+            })
+            .collect())
     }
 
     fn switch(&self, name: &str, skip_attach: bool) -> Result<bool> {
@@ -592,20 +681,364 @@ impl<R: Runner> Multiplexer for Tmux<R> {
         Ok(false)
     }
 
-    fn get_session(&self) -> Result<Session> {
+    fn apply(&self, session: &Session, prune: bool, skip_cmds: bool) -> Result<Vec<String>> {
+        if !self.client.session_exists(&session.name) {
+            bail!("Session '{}' does not exist", session.name);
+        }
+
+        let dimensions = self.client.get_dimensions()?;
+        let mut warnings = vec![];
+
+        let mut unmatched = self.client.window_ids(&session.name)?;
+        for window in &session.windows {
+            let window_path = window.effective_path(&session.path);
+            let existing = unmatched
+                .iter()
+                .position(|(_, name)| *name == window.name)
+                .map(|index| unmatched.remove(index).0);
+            let window_id = match &existing {
+                Some(id) => id.clone(),
+                None => {
+                    let path = first_pane_path(window, &window_path);
+                    self.client.new_window(&session.name, &window.name, &path)?
+                }
+            };
+            let layout_meta = LayoutMeta {
+                name: session.name.as_str(),
+                id: window_id.as_str(),
+                path: window_path.as_str(),
+            };
+
+            if existing.is_some() {
+                warnings.extend(self.apply_window(
+                    &layout_meta,
+                    window,
+                    &dimensions,
+                    prune,
+                    skip_cmds,
+                )?);
+            } else {
+                self.layout_window(&layout_meta, window, &dimensions, skip_cmds)?;
+            }
+        }
+
+        if prune {
+            for (window_id, name) in unmatched {
+                log::info!("Closing window '{name}'");
+                self.client
+                    .kill_window(&tmux_target!(&session.name, &window_id))?;
+            }
+        }
+
+        if let Some(delay_ms) = session.pane_cmd_delay {
+            std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+        }
+        self.client.flush_commands();
+
+        Ok(warnings)
+    }
+
+    fn respawn(
+        &self,
+        session: &Session,
+        window: &str,
+        pane: Option<&str>,
+        skip_cmds: bool,
+    ) -> Result<()> {
+        let Some(config_window) = session.windows.iter().find(|w| w.name == window) else {
+            bail!(
+                "Window '{window}' is not in the config of session '{}'",
+                session.name
+            );
+        };
+        let Some((window_id, _)) = self
+            .client
+            .window_ids(&session.name)?
+            .into_iter()
+            .find(|(_, name)| name == window)
+        else {
+            bail!(
+                "Window '{window}' does not exist in session '{}'",
+                session.name
+            );
+        };
+        let window_path = config_window.effective_path(&session.path);
+
+        match pane {
+            Some(pane) => self.respawn_pane(
+                &LayoutMeta {
+                    name: session.name.as_str(),
+                    id: window_id.as_str(),
+                    path: window_path.as_str(),
+                },
+                config_window,
+                pane,
+                skip_cmds,
+            )?,
+            None => {
+                let dimensions = self.client.get_dimensions()?;
+                let (index, active) = self
+                    .client
+                    .window_index(&tmux_target!(&session.name, &window_id))?;
+                let window_id = self.client.replace_window(
+                    &tmux_target!(&session.name, &index),
+                    &config_window.name,
+                    &first_pane_path(config_window, &window_path),
+                    active,
+                )?;
+                log::trace!("window-id: {window_id}");
+
+                self.layout_window(
+                    &LayoutMeta {
+                        name: session.name.as_str(),
+                        id: window_id.as_str(),
+                        path: window_path.as_str(),
+                    },
+                    config_window,
+                    &dimensions,
+                    skip_cmds,
+                )?;
+            }
+        }
+
+        if let Some(delay_ms) = session.pane_cmd_delay {
+            std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+        }
+        self.client.flush_commands();
+        Ok(())
+    }
+
+    fn supports_snapshots(&self) -> bool {
+        true
+    }
+
+    fn capture_panes(&self, name: &str) -> Result<Vec<Vec<String>>> {
+        self.client
+            .window_ids(name)?
+            .iter()
+            .map(|(window_id, _)| {
+                self.client
+                    .window_panes(&tmux_target!(name, window_id))?
+                    .iter()
+                    .map(|pane_id| {
+                        self.client
+                            .capture_pane(&tmux_target!(name, window_id, pane_id))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn get_session(&self, session: Option<&str>) -> Result<Session> {
+        if let Some(session) = session
+            && !self.client.session_exists(session)
+        {
+            bail!("Session '{session}' does not exist");
+        }
+
         let home_dir = home_dir()?;
-        let layout = self.client.session_layout()?;
-        let name = self.client.session_name()?;
-        let path = self.client.session_start_path()?.replace(&home_dir, "~");
-        let pane_paths = self.client.pane_paths()?;
+        let windows = self.client.session_windows(session)?;
+        let name = self.client.session_name(session)?;
+        let path = self
+            .client
+            .session_start_path(session)?
+            .replace(&home_dir, "~");
+        let pane_paths = self.client.pane_paths(session)?;
 
-        let cmd_dict = self.client.pane_command()?;
+        let cmd_dict = self.client.pane_command(session)?;
+        let pane_info = self.client.pane_info(session)?;
 
-        log::trace!("session_layout: {layout}");
+        log::trace!("session_windows: {windows:?}");
 
-        let tokens = parse(&layout, &pane_paths, &path, &cmd_dict);
+        let tokens = parse(&windows, &pane_paths, &path, &cmd_dict, &pane_info);
         log::trace!("tokens: {tokens:#?}");
 
-        Ok(Session::from_tokens(&name, &path, &tokens))
+        let mut session = Session::from_tokens(&name, &path, &tokens);
+        session.env = self.client.session_env(&tmux_target!(&name))?;
+        Ok(session)
+    }
+}
+
+/// Custom tmux layout of `panes`, as taken by `select-layout` after the checksum.
+///
+/// `create_pane` is called for every pane in the order tmux has to create them, with
+/// its index among its siblings, and returns the id of the pane.
+pub(crate) fn generate_layout(
+    layout_info: &LayoutInfo,
+    panes: &[Pane],
+    depth: usize,
+    create_pane: &mut dyn FnMut(&Pane, usize) -> Result<String>,
+) -> Result<String> {
+    let flex_total = panes.iter().map(|p| p.flex).sum();
+
+    let (mut current_x, mut current_y) = layout_info.xy;
+
+    let mut pane_strings: Vec<String> = Vec::new();
+    let mut num_dividers = 0;
+
+    for (index, pane) in panes.iter().enumerate() {
+        let (pane_width, pane_height, next_x, next_y) = match calculate_pane_dimensions(
+            &LayoutInfo {
+                dimensions: layout_info.dimensions,
+                direction: layout_info.direction,
+                xy: (current_x, current_y),
+            },
+            &CalculateInfo {
+                depth,
+                dividers: num_dividers,
+                flex: pane.flex,
+                index,
+                flex_total,
+            },
+            panes,
+        ) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        if depth > 0 || index > 0 {
+            num_dividers += 1;
+        }
+
+        let pane_id = create_pane(pane, index)?;
+
+        pane_strings.push(generate_pane_string(
+            &LayoutInfo {
+                dimensions: &Dimensions {
+                    width: pane_width,
+                    height: pane_height,
+                },
+                direction: layout_info.direction,
+                xy: (current_x, current_y),
+            },
+            pane,
+            depth,
+            &pane_id,
+            create_pane,
+        )?);
+
+        (current_x, current_y) = (next_x, next_y);
+    }
+
+    if pane_strings.len() > 1 {
+        let (open_delimiter, close_delimiter) = match layout_info.direction {
+            FlexDirection::Column => ('[', ']'),
+            FlexDirection::Row => ('{', '}'),
+        };
+
+        Ok(format!(
+            "{}x{},0,0{}{}{}",
+            layout_info.dimensions.width,
+            layout_info.dimensions.height,
+            open_delimiter,
+            pane_strings.join(","),
+            close_delimiter
+        ))
+    } else {
+        Ok(format!(
+            "{}x{},0,0",
+            layout_info.dimensions.width, layout_info.dimensions.height
+        ))
+    }
+}
+
+fn generate_pane_string(
+    layout_info: &LayoutInfo,
+    pane: &Pane,
+    depth: usize,
+    pane_id: &str,
+    create_pane: &mut dyn FnMut(&Pane, usize) -> Result<String>,
+) -> Result<String> {
+    let pane_string = if !pane.panes.is_empty() {
+        generate_layout(
+            &LayoutInfo {
+                dimensions: layout_info.dimensions,
+                direction: &pane.flex_direction,
+                xy: layout_info.xy,
+            },
+            &pane.panes,
+            depth + 1,
+            create_pane,
+        )?
+    } else {
+        let (current_x, current_y) = layout_info.xy;
+        format!(
+            "{0}x{1},{2},{3},{4}",
+            layout_info.dimensions.width,
+            layout_info.dimensions.height,
+            current_x,
+            current_y,
+            pane_id.replace('%', "")
+        )
+    };
+    Ok(pane_string)
+}
+
+fn calculate_pane_dimensions(
+    layout_info: &LayoutInfo,
+    calculate_info: &CalculateInfo,
+    panes: &[Pane],
+) -> Option<(usize, usize, usize, usize)> {
+    let (current_x, current_y) = layout_info.xy;
+    let index = calculate_info.index;
+    let (pane_width, pane_height, next_x, next_y) = match layout_info.direction {
+        FlexDirection::Column => {
+            let h = calculate_dimension(
+                calculate_info,
+                index == panes.len() - 1,
+                current_y,
+                layout_info.dimensions.height,
+            )?;
+            (
+                layout_info.dimensions.width,
+                h,
+                layout_info.xy.0,
+                layout_info.xy.1 + h + 1,
+            )
+        }
+        _ => {
+            let w = calculate_dimension(
+                calculate_info,
+                index == panes.len() - 1,
+                current_x,
+                layout_info.dimensions.width,
+            )?;
+            (
+                w,
+                layout_info.dimensions.height,
+                current_x + w + 1,
+                current_y,
+            )
+        }
+    };
+    Some((pane_width, pane_height, next_x, next_y))
+}
+
+fn calculate_dimension(
+    calculate_info: &CalculateInfo,
+    is_last_pane: bool,
+    current_value: usize,
+    total_value: usize,
+) -> Option<usize> {
+    let (flex, flex_total, dividers, depth, index) = (
+        calculate_info.flex,
+        calculate_info.flex_total,
+        calculate_info.dividers,
+        calculate_info.depth,
+        calculate_info.index,
+    );
+    if is_last_pane {
+        log::trace!("current_value: {current_value}, total_value: {total_value}");
+        if current_value >= total_value {
+            log::warn!("skipping pane: total_value: {total_value}, current_value: {current_value}");
+            return None;
+        }
+        Some(total_value - current_value)
+    } else {
+        Some(if depth > 0 || index > 0 {
+            total_value * flex / flex_total - dividers
+        } else {
+            total_value * flex / flex_total
+        })
     }
 }
@@ -11,6 +11,8 @@ use crate::common::{
     path::relative_path,
 };
 
+use super::client::{PaneInfo, WindowInfo};
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Dimensions {
     pub width: i32,
@@ -26,6 +28,9 @@ pub struct Token {
     pub split_type: Option<SplitType>,
     pub children: Vec<Token>,
     pub commands: Vec<Command>,
+    pub style: Option<String>,
+    pub zoom: bool,
+    pub focus: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -67,11 +72,11 @@ impl Window {
             .as_ref()
             .map(FlexDirection::from_split_type);
         Self {
-            name: token.name.clone().unwrap_or_else(|| "foo".to_string()),
+            name: token.name.clone().unwrap_or_default(),
             path: None,
             flex_direction: pane_flex_direction.clone().unwrap_or_default(),
             panes: Pane::from_tokens(&token.children, pane_flex_direction.unwrap_or_default()),
-            focus: false,
+            focus: token.focus,
         }
     }
 }
@@ -80,6 +85,8 @@ impl Session {
     pub(crate) fn from_tokens(name: &str, path: &str, tokens: &[Token]) -> Self {
         Self {
             name: name.to_string(),
+            description: None,
+            tags: vec![],
             startup: vec![],
             shutdown: vec![],
             startup_script: None,
@@ -139,8 +146,8 @@ impl Pane {
                 Pane {
                     flex_direction: pane_flex_direction.clone(),
                     flex: normalized_flex_value,
-                    name: None,
-                    style: None,
+                    name: token.name.clone(),
+                    style: token.style.clone(),
                     path: match token.path {
                         Some(ref p) => p.clone(),
                         None => ".".to_string(),
@@ -148,8 +155,8 @@ impl Pane {
                     commands: token.commands.clone(),
                     script: None,
                     panes: Pane::from_tokens(&token.children, pane_flex_direction),
-                    zoom: false,
-                    focus: false,
+                    zoom: token.zoom,
+                    focus: token.focus,
                 }
             })
             .inspect(|pane| log::trace!("pane: {pane:?}"))
@@ -160,10 +167,11 @@ impl Pane {
 }
 
 pub fn parse(
-    tmux_layout: &str,
+    windows: &[WindowInfo],
     pane_paths: &HashMap<String, String>,
     session_path_str: &str,
     cmd_dict: &HashMap<String, String>,
+    pane_info: &HashMap<String, PaneInfo>,
 ) -> Vec<Token> {
     let session_path = Path::new(&session_path_str);
     log::trace!("session_path: {session_path:?}");
@@ -175,9 +183,19 @@ pub fn parse(
         adjusted_pane_paths.insert(pane_id.clone(), path_opt);
     }
 
-    tmux_layout
-        .lines()
-        .filter_map(|line| parse_window(line.trim(), &adjusted_pane_paths, cmd_dict))
+    windows
+        .iter()
+        .filter_map(|window| {
+            let mut token = parse_window(
+                window.layout.trim(),
+                &adjusted_pane_paths,
+                cmd_dict,
+                pane_info,
+            )?;
+            token.name = Some(window.name.clone());
+            token.focus = window.active;
+            Some(token)
+        })
         .collect()
 }
 
@@ -185,22 +203,12 @@ fn parse_window(
     input: &str,
     pane_paths: &HashMap<String, Option<String>>,
     cmd_dict: &HashMap<String, String>,
+    pane_info: &HashMap<String, PaneInfo>,
 ) -> Option<Token> {
     let mut rest = input.trim_start();
-    trace!("line: {rest:?}");
     trace!("parse_window: {rest:?}");
     trace!("pane_paths: {pane_paths:?}");
 
-    let name_re = Regex::new(r"(?P<name>[\w\-]+)\s").unwrap();
-    let name = if let Some(captures) = name_re.captures(rest) {
-        rest = &rest[captures.get(0).unwrap().end()..];
-        trace!("rest-name {rest:?}");
-        Some(captures["name"].to_string())
-    } else {
-        None
-    };
-    trace!("name: {name:?}");
-
     let dim_re = Regex::new(r"(?P<width>\d+)x(?P<height>\d+)(,\d){2}").unwrap();
     let dimensions = if let Some(captures) = dim_re.captures(rest) {
         rest = &rest[captures.get(0).unwrap().end()..];
@@ -215,7 +223,7 @@ fn parse_window(
 
     trace!("dimensions: {dimensions:?}");
 
-    let (mut children, split_type, _) = parse_children(rest, pane_paths, cmd_dict);
+    let (mut children, split_type, _) = parse_children(rest, pane_paths, cmd_dict, pane_info);
 
     if children.is_empty() {
         let id_re = Regex::new(r"[,]{1}(?P<id>\d+)").unwrap();
@@ -234,16 +242,25 @@ fn parse_window(
                 .get(&id)
                 .map(|cmd| cmd.to_string())
                 .map_or(vec![], |cmd| vec![Command::from_string(cmd.as_str())]);
-
-            if path.is_some() || !commands.is_empty() {
+            // Focus and zoom mean nothing for the only pane of a window
+            let info = pane_info.get(&id).cloned().unwrap_or_default();
+
+            if path.is_some()
+                || !commands.is_empty()
+                || info.title.is_some()
+                || info.style.is_some()
+            {
                 children.push(Token {
                     id: Some(id),
-                    name: None,
+                    name: info.title,
                     dimensions,
                     path,
                     split_type: None,
                     children: vec![],
                     commands,
+                    style: info.style,
+                    zoom: false,
+                    focus: false,
                 });
             }
         }
@@ -251,12 +268,15 @@ fn parse_window(
 
     Some(Token {
         id: None,
-        name,
+        name: None,
         dimensions,
         path: None,
         split_type,
         children,
         commands: vec![],
+        style: None,
+        zoom: false,
+        focus: false,
     })
 }
 
@@ -264,6 +284,7 @@ fn parse_children<'a>(
     input: &'a str,
     pane_paths: &HashMap<String, Option<String>>,
     cmd_dict: &HashMap<String, String>,
+    pane_info: &HashMap<String, PaneInfo>,
 ) -> (Vec<Token>, Option<SplitType>, &'a str) {
     let mut rest = input.trim_start();
     trace!("parse_children: {rest:?}");
@@ -294,7 +315,7 @@ fn parse_children<'a>(
             split_type,
             split_type.as_ref().map(|st| st.closing_char())
         );
-        if let Some((child, next_rest)) = parse_single(rest, pane_paths, cmd_dict) {
+        if let Some((child, next_rest)) = parse_single(rest, pane_paths, cmd_dict, pane_info) {
             children.push(child);
             rest = next_rest;
         }
@@ -315,6 +336,7 @@ fn parse_single<'a>(
     input: &'a str,
     pane_paths: &HashMap<String, Option<String>>,
     cmd_dict: &HashMap<String, String>,
+    pane_info: &HashMap<String, PaneInfo>,
 ) -> Option<(Token, &'a str)> {
     let mut rest = input.trim_start();
     trace!("parse_single: {rest:?}");
@@ -334,9 +356,9 @@ fn parse_single<'a>(
     }?;
     trace!("dimensions and pane id {dimensions_pane_id:?}");
 
-    let (children, split_type, rest) = parse_children(rest, pane_paths, cmd_dict);
+    let (children, split_type, rest) = parse_children(rest, pane_paths, cmd_dict, pane_info);
 
-    let (path, commands) = if children.is_empty() {
+    let (path, commands, info) = if children.is_empty() {
         let path = match pane_paths.get(&dimensions_pane_id.1) {
             Some(Some(path)) => {
                 trace!("path: {path:?}");
@@ -355,19 +377,27 @@ fn parse_single<'a>(
             None => vec![],
         };
 
-        (path, cmds)
+        let info = pane_info
+            .get(&dimensions_pane_id.1)
+            .cloned()
+            .unwrap_or_default();
+
+        (path, cmds, info)
     } else {
-        (None, vec![])
+        (None, vec![], PaneInfo::default())
     };
 
     let token = Token {
         id: Some(dimensions_pane_id.1),
         split_type,
-        name: None,
+        name: info.title,
         path,
         dimensions: dimensions_pane_id.0,
         children,
         commands,
+        style: info.style,
+        zoom: info.zoom,
+        focus: info.focus,
     };
 
     Some((token, rest))
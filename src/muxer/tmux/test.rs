@@ -6,7 +6,11 @@ use crate::{
     tmux_target,
 };
 use crate::{
-    common::{config::Session, muxer::multiplexer::Multiplexer, session_info::SessionStatus},
+    common::{
+        config::{secrets::Secrets, Session},
+        muxer::multiplexer::Multiplexer,
+        session_info::{SessionDetails, SessionStatus},
+    },
     muxer::{tmux::Target, Tmux},
 };
 use lazy_static::lazy_static;
@@ -169,7 +173,7 @@ fn mux_start_session() {
         .withf(|cmd| {
             matches!(
                 cmd,
-                Type::Basic(_)
+                Type::Query(_)
                 if cmd.to_string() == "tmux has-session -t valid"
             )
         })
@@ -178,7 +182,7 @@ fn mux_start_session() {
 
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "printenv TMUX"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "printenv TMUX"))
         .times(2)
         .returning(|_| Ok("something".to_string()));
 
@@ -186,7 +190,7 @@ fn mux_start_session() {
         .expect_run()
         .times(1)
         .withf(
-            |cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux display-message -p width: #{window_width}\nheight: #{window_height}")
+            |cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux display-message -p width: #{window_width}\nheight: #{window_height}")
         ).returning(|_| Ok("width: 160\nheight: 90".to_string()));
 
     cmd_string
@@ -227,13 +231,13 @@ fn mux_start_session() {
     cmd_string
         .expect_run()
         .times(1)
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux show-options -g base-index" ))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux show-options -g base-index" ))
         .returning(|_| Ok("base-index 1".to_string()));
 
     cmd_string
         .expect_run()
         .times(1)
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux display-message -t valid -p #I" ))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux display-message -t valid -p #I" ))
         .returning(|_| {
                 let value = WIN_NUM.fetch_add(1, Ordering::SeqCst) + 1;
                 Ok(format!("@{value}"))
@@ -249,7 +253,7 @@ fn mux_start_session() {
     cmd_string
         .expect_run()
         .times(2)
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux display-message -t valid:@1 -p #P" ))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux display-message -t valid:@1 -p #P" ))
         .returning(|_| {
                 let value = PANE_NUM.fetch_add(1, Ordering::SeqCst) + 1;
                 Ok(format!("%{value}"))
@@ -331,7 +335,7 @@ fn mux_start_session() {
     cmd_string
         .expect_run()
         .times(1)
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux display-message -t valid:@2 -p #P" ))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux display-message -t valid:@2 -p #P" ))
         .returning(|_| {
                 let value = PANE_NUM.fetch_add(1, Ordering::SeqCst) + 1;
                 Ok(format!("%{value}"))
@@ -391,18 +395,18 @@ fn mux_start_session() {
     // Mock pane readiness checks (capture-pane for stability check)
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string().contains("capture-pane") && cmd.to_string().contains("-p")))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string().contains("capture-pane") && cmd.to_string().contains("-p")))
         .returning(|_| Ok("ready".to_string()));
 
     // Mock PID checks for command completion
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string().contains("display-message") && cmd.to_string().contains("pane_pid")))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string().contains("display-message") && cmd.to_string().contains("pane_pid")))
         .returning(|_| Ok("12345".to_string()));
 
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string().starts_with("pgrep -P")))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string().starts_with("pgrep -P")))
         .returning(|_| Err(miette::miette!("No child processes")));
 
     cmd_unit
@@ -495,27 +499,27 @@ fn mux_stop_session() -> Result<()> {
     cmd_bool
         .expect_run()
         .withf(
-            |cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux has-session -t valid"),
+            |cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux has-session -t valid"),
         )
         .times(2)
         .returning(|_| Ok(true));
 
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "sh -c [ -n \"$TMUX\" ] && tmux display-message -p '#S' || true"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "sh -c [ -n \"$TMUX\" ] && tmux display-message -p '#S' || true"))
         .times(1)
         .returning(|_| Ok("valid".to_string()));
 
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux show-environment -t valid LAIO_CONFIG"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux show-environment -t valid LAIO_CONFIG"))
         .times(2)
         .returning(|_| Ok("LAIO_CONFIG=./src/common/config/test/valid.yaml".to_string()));
 
     // Mock LAIO_VARS retrieval (returns empty for backward compatibility test)
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux show-environment -t valid LAIO_VARS"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux show-environment -t valid LAIO_VARS"))
         .times(1)
         .returning(|_| Err(miette::miette!("Not found"))); // Simulate no LAIO_VARS (old session)
 
@@ -563,11 +567,34 @@ fn mux_list_sessions() -> Result<()> {
     cmd_string
         .expect_run()
         .times(1)
-        .withf(
-            |cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux ls -F #{session_name}|#{session_attached}"),
-        )
+        .withf(|cmd| {
+            matches!(cmd, Type::Query(_) if cmd.to_string()
+                == "tmux ls -F #{session_name}|#{session_attached}|#{session_windows}|#{session_created}|#{session_activity}")
+        })
+        .returning(|_| Ok("foo|2|2|100|200\nbar|0|1|300|400\nb|az|1|1|500|600\n".to_string()));
+    cmd_string
+        .expect_run()
+        .times(1)
+        .withf(|cmd| cmd.to_string() == "tmux list-panes -a -F #{session_name}")
+        .returning(|_| Ok("foo\nfoo\nfoo\nbar\nb|az\n".to_string()));
+    // One read of the environment per session, a broken one doesn't fail the list
+    cmd_string
+        .expect_run()
         .times(1)
-        .returning(|_| Ok("foo|1\nbar|0\nbaz|1\n".to_string()));
+        .withf(|cmd| cmd.to_string() == "tmux show-environment -t foo")
+        .returning(|_| {
+            Ok("HOME=/root\nLAIO_CONFIG=/configs/foo.yaml\nLAIO_VARS=env=dev\n".to_string())
+        });
+    cmd_string
+        .expect_run()
+        .times(1)
+        .withf(|cmd| cmd.to_string() == "tmux show-environment -t bar")
+        .returning(|_| Ok("LAIO_CONFIG=/configs/bar.yaml\nLAIO_VARS=broken\n".to_string()));
+    cmd_string
+        .expect_run()
+        .times(1)
+        .withf(|cmd| cmd.to_string().starts_with("tmux show-environment -t "))
+        .returning(|_| Err(miette::miette!("no such session")));
 
     let runner = RunnerMock {
         cmd_unit,
@@ -577,16 +604,39 @@ fn mux_list_sessions() -> Result<()> {
 
     let tmux = Tmux::new_with_runner(runner);
 
-    let result = tmux.list_sessions()?;
-
-    let sessions = result;
+    let sessions = tmux.list_sessions()?;
     assert_eq!(sessions.len(), 3);
     assert_eq!(sessions[0].name, "foo");
     assert_eq!(sessions[0].status, SessionStatus::Attached);
+    assert_eq!(
+        sessions[0].config_path.as_deref(),
+        Some("/configs/foo.yaml")
+    );
+    assert_eq!(
+        sessions[0].details,
+        Some(SessionDetails {
+            muxer: "tmux".to_string(),
+            windows: Some(2),
+            panes: Some(3),
+            clients: Some(2),
+            created: Some(100),
+            last_activity: Some(200),
+            variables: vec!["env=dev".to_string()],
+            is_laio_session: Some(true),
+        })
+    );
     assert_eq!(sessions[1].name, "bar");
     assert_eq!(sessions[1].status, SessionStatus::Active);
-    assert_eq!(sessions[2].name, "baz");
+    let details = sessions[1].details.as_ref().unwrap();
+    assert_eq!(details.panes, Some(1));
+    assert_eq!(details.is_laio_session, Some(true));
+    assert!(details.variables.is_empty());
+    assert_eq!(sessions[2].name, "b|az");
     assert_eq!(sessions[2].status, SessionStatus::Attached);
+    let details = sessions[2].details.as_ref().unwrap();
+    assert_eq!(details.windows, Some(1));
+    assert_eq!(details.is_laio_session, None);
+    assert_eq!(sessions[2].config_path, None);
 
     Ok(())
 }
@@ -610,19 +660,19 @@ windows:
 
     cmd_bool
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux has-session -t test"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux has-session -t test"))
         .times(1)
         .returning(|_| Ok(false));
 
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "printenv TMUX"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "printenv TMUX"))
         .times(2)
         .returning(|_| Ok("something".to_string()));
 
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string().contains("window_width")))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string().contains("window_width")))
         .times(1)
         .returning(|_| Ok("width: 160\nheight: 90".to_string()));
 
@@ -634,13 +684,13 @@ windows:
 
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux show-options -g base-index"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux show-options -g base-index"))
         .times(1)
         .returning(|_| Ok("base-index 1".to_string()));
 
     cmd_string
         .expect_run()
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "tmux display-message -t test -p #I"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "tmux display-message -t test -p #I"))
         .times(1)
         .returning(|_| Ok("@1".to_string()));
 
@@ -672,3 +722,581 @@ windows:
     let tmux = Tmux::new_with_runner(runner);
     assert!(tmux.start(&session, &[], true, true).is_ok());
 }
+
+#[test]
+fn mux_get_session() -> Result<()> {
+    let cmd_unit = MockCmdUnitMock::new();
+    let mut cmd_string = MockCmdStringMock::new();
+    let cmd_bool = MockCmdBoolMock::new();
+
+    let outputs: Vec<(&str, &str)> = vec![
+        (
+            "tmux list-windows -F #{window_active}\t#{window_layout}\t#{window_name}",
+            "1\tc3d1,160x40,0,0{80x40,0,0,1,79x40,81,0,2}\tcode & docs\n0\ta1b2,160x40,0,0,3\tlogs",
+        ),
+        ("tmux display-message -p #S", "proj"),
+        (
+            "tmux list-panes -s -F #{pane_id} #{pane_current_path}",
+            "%1 /work/proj\n%2 /work/proj/docs\n%3 /work/proj",
+        ),
+        (
+            "tmux list-panes -s -F #{pane_id} #{pane_pid}",
+            "%1 999999991\n%2 999999992\n%3 999999993",
+        ),
+        ("pgrep -P 999999993", "4242"),
+        ("ps -p 4242 -o args=", "tail -f app.log"),
+        (
+            "tmux list-panes -s -F #{pane_id}\t#{pane_active}\t#{window_zoomed_flag}\t#{host}\t#{pane_title}",
+            "%1\t1\t1\tbox\teditor\n%2\t0\t1\tbox\tbox\n%3\t1\t0\tbox\tbox",
+        ),
+        (
+            "tmux display-message -p -t %1 #{pane_id} ; show-options -p -q -v -t %1 window-style ; display-message -p -t %2 #{pane_id} ; show-options -p -q -v -t %2 window-style ; display-message -p -t %3 #{pane_id} ; show-options -p -q -v -t %3 window-style",
+            "%1\nbg=colour235\n%2\n%3\n",
+        ),
+        (
+            "tmux show-options -g -v update-environment",
+            "DISPLAY\nSSH_AUTH_SOCK",
+        ),
+        (
+            "tmux show-environment -t proj",
+            "-DISPLAY\nSSH_AUTH_SOCK=/tmp/agent\nLAIO_CONFIG=/work/proj/.laio.yaml\nFOO=bar",
+        ),
+    ];
+
+    for (command, output) in outputs {
+        cmd_string
+            .expect_run()
+            .withf(move |cmd| cmd.to_string() == command)
+            .returning(move |_| Ok(output.to_string()));
+    }
+    cmd_string
+        .expect_run()
+        .withf(|cmd| cmd.to_string().starts_with("pgrep -P"))
+        .returning(|_| Ok(String::new()));
+
+    let runner = RunnerMock {
+        cmd_unit,
+        cmd_string,
+        cmd_bool,
+    };
+
+    let tmux = Tmux::new_with_runner(runner);
+    let session = tmux.get_session(None)?;
+
+    assert_eq!(session.name, "proj");
+    assert_eq!(session.path, "/work/proj");
+    assert_eq!(
+        session.env,
+        HashMap::from([("FOO".to_string(), "bar".to_string())])
+    );
+
+    assert_eq!(session.windows.len(), 2);
+    let code = &session.windows[0];
+    assert_eq!(code.name, "code & docs");
+    assert!(code.focus);
+    assert_eq!(code.panes.len(), 2);
+    assert_eq!(code.panes[0].name.as_deref(), Some("editor"));
+    assert_eq!(code.panes[0].style.as_deref(), Some("bg=colour235"));
+    assert!(code.panes[0].focus);
+    assert!(code.panes[0].zoom);
+    assert_eq!(code.panes[1].name, None);
+    assert_eq!(code.panes[1].path, "docs");
+    assert!(!code.panes[1].focus);
+    assert!(!code.panes[1].zoom);
+
+    let logs = &session.windows[1];
+    assert_eq!(logs.name, "logs");
+    assert!(!logs.focus);
+    assert_eq!(logs.panes.len(), 1);
+    assert!(!logs.panes[0].focus);
+    assert_eq!(logs.panes[0].commands[0].to_string(), "tail -f app.log");
+
+    Ok(())
+}
+
+#[test]
+fn export_script() -> Result<()> {
+    let cwd = std::env::current_dir().unwrap();
+    let session = Session::from_config(
+        &cwd.join("src/common/config/test/valid.yaml"),
+        None,
+        &[],
+        &Secrets::default(),
+    )?;
+
+    let mut warnings = vec![];
+    let script = super::export::script(&session, &mut warnings)?;
+    let lines: Vec<&str> = script.lines().collect();
+
+    assert_eq!(lines[0], "#!/bin/sh");
+    assert!(lines.contains(&"tmux new-session -d -s \"$session\" -c /tmp -x 200 -y 50 -e FOO=bar"));
+    assert!(lines.contains(&"p3=$(tmux split-window -t \"$w1\" -c /tmp/src -P -F '#{pane_id}')"));
+    assert!(lines.contains(
+        &"tmux select-layout -t \"$w1\" 'adfe,200x50,0,0[200x25,0,0{66x25,0,0,1,133x25,67,0,2},200x24,0,26,3]'"
+    ));
+    assert!(lines.contains(
+        &"w2=$(tmux new-window -d -P -F '#{window_id}' -t \"$session\" -n infrastructure -c /tmp/one)"
+    ));
+    assert!(lines.contains(&"tmux send-keys -t \"$p6\" clear C-m 'echo \"hello again 3\"' C-m"));
+    assert!(lines.contains(&"tmux select-pane -t \"$p1\" -T foo"));
+
+    // Panes are created before any command is sent to them
+    let last_split = lines.iter().rposition(|l| l.contains("split-window"));
+    let first_keys = lines.iter().position(|l| l.contains("send-keys"));
+    assert!(last_split < first_keys);
+    assert_eq!(
+        lines.last(),
+        Some(&"exec tmux attach-session -t \"=$session\"")
+    );
+    assert_eq!(warnings, vec!["Shutdown commands are not exported"]);
+    Ok(())
+}
+
+#[test]
+fn export_tmuxinator() -> Result<()> {
+    use noyalib::compat::serde_yaml::{from_str, Value};
+
+    let cwd = std::env::current_dir().unwrap();
+    let session = Session::from_config(
+        &cwd.join("src/common/config/test/valid.yaml"),
+        None,
+        &[],
+        &Secrets::default(),
+    )?;
+
+    let mut warnings = vec![];
+    let yaml = super::export::tmuxinator(&session, &mut warnings)?;
+    let project: Value = from_str(&yaml).unwrap();
+
+    assert_eq!(project["name"].as_str(), Some("valid"));
+    assert_eq!(project["root"].as_str(), Some("/tmp"));
+    assert_eq!(project["on_project_start"][1].as_str(), Some("echo Hi"));
+    assert_eq!(project["pre_window"][0].as_str(), Some("export FOO=bar"));
+
+    let infrastructure = &project["windows"][1]["infrastructure"];
+    assert_eq!(
+        infrastructure["layout"].as_str(),
+        Some("2332,200x50,0,0[200x12,0,0,1,200x25,0,13,2,200x11,0,39,3]")
+    );
+    assert_eq!(
+        infrastructure["panes"][2][0].as_str(),
+        Some("cd /tmp/three")
+    );
+    assert_eq!(
+        infrastructure["panes"][2][2].as_str(),
+        Some("echo \"hello again 3\"")
+    );
+    assert_eq!(
+        project["windows"][0]["code"]["panes"]
+            .as_sequence()
+            .map(Vec::len),
+        Some(3)
+    );
+
+    assert!(warnings.contains(&"Startup and shutdown scripts are not exported".to_string()));
+    assert!(warnings.contains(&"Commands of split panes in 'code' are not exported".to_string()));
+    Ok(())
+}
+
+#[test]
+fn mux_start_session_dry_run() -> Result<()> {
+    use crate::common::cmd::RecordingRunner;
+
+    let cwd = std::env::current_dir().unwrap();
+    let session = Session::from_config(
+        &cwd.join("src/common/config/test/valid.yaml"),
+        None,
+        &[],
+        &Secrets::default(),
+    )?;
+
+    let mut cmd_string = MockCmdStringMock::new();
+    let mut cmd_bool = MockCmdBoolMock::new();
+    cmd_bool
+        .expect_run()
+        .withf(|cmd| cmd.to_string() == "tmux has-session -t valid")
+        .returning(|_| Err(miette::miette!("can't find session: valid")));
+    cmd_string
+        .expect_run()
+        .returning(|cmd| match cmd.to_string().as_str() {
+            "printenv TMUX" => Ok("/tmp/tmux-1000/default,1,0".to_string()),
+            "tmux show-options -g base-index" => Ok("base-index 1".to_string()),
+            line if line.contains("#{window_width}") => Ok("width: 160\nheight: 90".to_string()),
+            _ => Ok(String::new()),
+        });
+
+    let runner = RecordingRunner::new_with_runner(
+        RunnerMock {
+            cmd_unit: MockCmdUnitMock::new(),
+            cmd_string,
+            cmd_bool,
+        },
+        Secrets::default(),
+    );
+    let tmux = Tmux::new_with_runner(runner.clone());
+    tmux.start(&session, &[("LAIO_CONFIG", "valid.yaml")], false, true)?;
+
+    let recorded: Vec<String> = runner.recorded().iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        recorded[0],
+        "tmux new-session -d -s valid -c /tmp -e FOO=bar"
+    );
+    assert!(recorded.contains(&"tmux set-environment -t valid LAIO_CONFIG valid.yaml".to_string()));
+    assert!(recorded.contains(&"tmux rename-window -t valid:@1 code".to_string()));
+    assert!(recorded.contains(
+        &"tmux select-layout -t valid:@1 '03f3,160x90,0,0[160x45,0,0{53x45,0,0,3,106x45,54,0,4},160x44,0,46,5]'"
+            .to_string()
+    ));
+    assert!(recorded.contains(
+        &"tmux new-window -Pd -t valid -n infrastructure -c /tmp/one -F '#{window_id}'".to_string()
+    ));
+    assert!(!recorded.iter().any(|c| c.contains("hello again")));
+    assert_eq!(recorded.last().unwrap(), "tmux switch-client -t valid");
+    Ok(())
+}
+
+#[test]
+fn mux_apply_session() -> Result<()> {
+    use crate::common::cmd::RecordingRunner;
+
+    let cwd = std::env::current_dir().unwrap();
+    let session = Session::from_config(
+        &cwd.join("src/common/config/test/valid.yaml"),
+        None,
+        &[],
+        &Secrets::default(),
+    )?;
+
+    let runner = |panes: &'static str| {
+        let mut cmd_string = MockCmdStringMock::new();
+        let mut cmd_bool = MockCmdBoolMock::new();
+        cmd_bool
+            .expect_run()
+            .withf(|cmd| cmd.to_string() == "tmux has-session -t valid")
+            .returning(|_| Ok(true));
+        cmd_string
+            .expect_run()
+            .returning(move |cmd| match cmd.to_string().as_str() {
+                "printenv TMUX" => Ok("/tmp/tmux-1000/default,1,0".to_string()),
+                line if line.starts_with("tmux list-windows -t valid ") => {
+                    Ok("@1\tcode\n@9\tscratch".to_string())
+                }
+                line if line.starts_with("tmux list-panes -t valid:@1 ") => Ok(panes.to_string()),
+                line if line.contains("#{window_width}") => {
+                    Ok("width: 160\nheight: 90".to_string())
+                }
+                _ => Ok(String::new()),
+            });
+        RecordingRunner::new_with_runner(
+            RunnerMock {
+                cmd_unit: MockCmdUnitMock::new(),
+                cmd_string,
+                cmd_bool,
+            },
+            Secrets::default(),
+        )
+    };
+
+    // The missing pane is split off the last one, the missing window is created
+    let recording = runner("%3\n%4");
+    let warnings = Tmux::new_with_runner(recording.clone()).apply(&session, true, false)?;
+    assert!(warnings.is_empty());
+
+    let recorded: Vec<String> = recording.recorded().iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        recorded[0],
+        "tmux split-window -t valid:@1.%4 -c /tmp/src -P -F '#{pane_id}'"
+    );
+    assert!(recorded.contains(
+        &"tmux select-layout -t valid:@1 '03f1,160x90,0,0[160x45,0,0{53x45,0,0,3,106x45,54,0,4},160x44,0,46,1]'"
+            .to_string()
+    ));
+    assert!(recorded.contains(
+        &"tmux new-window -Pd -t valid -n infrastructure -c /tmp/one -F '#{window_id}'".to_string()
+    ));
+    assert!(recorded.contains(&"tmux kill-window -t valid:@9".to_string()));
+    // Existing panes are left alone
+    assert!(!recorded
+        .iter()
+        .any(|c| c.contains("valid:@1.%3") || c.contains("rename-window")));
+    assert!(recorded
+        .iter()
+        .any(|c| c.starts_with("tmux send-keys -t valid:@1.%1")));
+
+    // Without pruning, left over panes keep the layout as it is
+    let recording = runner("%3\n%4\n%5\n%6");
+    let warnings = Tmux::new_with_runner(recording.clone()).apply(&session, false, true)?;
+    assert_eq!(
+        warnings,
+        vec!["Window 'code' has 1 more pane(s) than its config, its layout was kept"]
+    );
+    let recorded: Vec<String> = recording.recorded().iter().map(|c| c.to_string()).collect();
+    let layouts = recorded
+        .iter()
+        .filter(|c| c.starts_with("tmux select-layout") && !c.ends_with("tiled"));
+    // Only the new window is laid out
+    assert_eq!(layouts.count(), 1);
+    assert!(!recorded.iter().any(|c| c.contains("kill-")));
+    Ok(())
+}
+
+#[test]
+fn mux_restart_current_session() -> Result<()> {
+    use crate::common::cmd::RecordingRunner;
+
+    let cwd = std::env::current_dir().unwrap();
+    let session = Session::from_config(
+        &cwd.join("src/common/config/test/valid.yaml"),
+        None,
+        &[],
+        &Secrets::default(),
+    )?;
+
+    let runner = || {
+        let mut cmd_string = MockCmdStringMock::new();
+        let mut cmd_bool = MockCmdBoolMock::new();
+        cmd_bool
+            .expect_run()
+            .returning(|cmd| Ok(cmd.to_string() != "tmux has-session -t valid"));
+        cmd_string
+            .expect_run()
+            .returning(|cmd| match cmd.to_string().as_str() {
+                "printenv TMUX" => Ok("/tmp/tmux-1000/default,1,0".to_string()),
+                line if line.starts_with("sh -c") => Ok("valid".to_string()),
+                line if line.contains("#{window_width}") => {
+                    Ok("width: 160\nheight: 90".to_string())
+                }
+                _ => Ok(String::new()),
+            });
+        RecordingRunner::new_with_runner(
+            RunnerMock {
+                cmd_unit: MockCmdUnitMock::new(),
+                cmd_string,
+                cmd_bool,
+            },
+            Secrets::default(),
+        )
+    };
+
+    let recording = runner();
+    Tmux::new_with_runner(recording.clone()).restart(
+        "valid",
+        &Some(session.clone()),
+        &session,
+        &[("LAIO_CONFIG", "valid.yaml")],
+        false,
+        true,
+    )?;
+
+    // The session laio runs in is closed only after switching to the new one
+    let recorded: Vec<String> = recording.recorded().iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        recorded[0],
+        "tmux rename-session -t valid laio-restarting-valid"
+    );
+    assert_eq!(
+        recorded[1],
+        "tmux new-session -d -s valid -c /tmp -e FOO=bar"
+    );
+    let switch = recorded
+        .iter()
+        .position(|c| c == "tmux switch-client -t valid")
+        .unwrap();
+    assert_eq!(switch, recorded.len() - 2);
+    assert_eq!(
+        recorded.last().unwrap(),
+        "tmux kill-session -t laio-restarting-valid"
+    );
+
+    // A prefix of the name is the session laio runs in too
+    let prefix = runner();
+    Tmux::new_with_runner(prefix.clone()).restart(
+        "val",
+        &Some(session.clone()),
+        &session,
+        &[],
+        false,
+        true,
+    )?;
+    assert_eq!(
+        prefix.recorded()[0].to_string(),
+        "tmux rename-session -t valid laio-restarting-valid"
+    );
+    Ok(())
+}
+
+#[test]
+fn mux_restart_current_session_renames_back_on_failure() -> Result<()> {
+    use std::sync::Mutex;
+
+    let cwd = std::env::current_dir().unwrap();
+    let session = Session::from_config(
+        &cwd.join("src/common/config/test/valid.yaml"),
+        None,
+        &[],
+        &Secrets::default(),
+    )?;
+
+    let run: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    let mut cmd_unit = MockCmdUnitMock::new();
+    let log = run.clone();
+    cmd_unit.expect_run().returning(move |cmd| {
+        let cmd = cmd.to_string();
+        log.lock().unwrap().push(cmd.clone());
+        match cmd.starts_with("tmux new-session ") {
+            true => Err(miette::miette!("new-session failed")),
+            false => Ok(()),
+        }
+    });
+    let mut cmd_string = MockCmdStringMock::new();
+    cmd_string
+        .expect_run()
+        .returning(|cmd| match cmd.to_string().as_str() {
+            "printenv TMUX" => Ok("/tmp/tmux-1000/default,1,0".to_string()),
+            line if line.starts_with("sh -c") => Ok("valid".to_string()),
+            line if line.contains("#{window_width}") => Ok("width: 160\nheight: 90".to_string()),
+            line if line.starts_with("tmux new-session ") => {
+                Err(miette::miette!("new-session failed"))
+            }
+            _ => Ok(String::new()),
+        });
+    let mut cmd_bool = MockCmdBoolMock::new();
+    cmd_bool.expect_run().returning(|_| Ok(false));
+
+    let result = Tmux::new_with_runner(RunnerMock {
+        cmd_unit,
+        cmd_string,
+        cmd_bool,
+    })
+    .restart("valid", &None, &session, &[], false, true);
+    assert!(result.is_err());
+
+    // The session laio runs in gets its name back
+    let run = run.lock().unwrap();
+    assert_eq!(run[0], "tmux rename-session -t valid laio-restarting-valid");
+    assert_eq!(
+        run.last().unwrap(),
+        "tmux rename-session -t laio-restarting-valid valid"
+    );
+    Ok(())
+}
+
+#[test]
+fn mux_respawn_window() -> Result<()> {
+    use crate::common::cmd::RecordingRunner;
+
+    let cwd = std::env::current_dir().unwrap();
+    let mut session = Session::from_config(
+        &cwd.join("src/common/config/test/valid.yaml"),
+        None,
+        &[],
+        &Secrets::default(),
+    )?;
+    session.windows[1].panes[1].name = Some("two".to_string());
+
+    let runner = || {
+        let mut cmd_string = MockCmdStringMock::new();
+        cmd_string
+            .expect_run()
+            .returning(|cmd| match cmd.to_string().as_str() {
+                line if line.starts_with("tmux list-windows -t valid ") => {
+                    Ok("@1\tcode\n@2\tinfrastructure".to_string())
+                }
+                line if line.starts_with("tmux list-panes -t valid:@2 ") => {
+                    Ok("%4\n%5\n%6".to_string())
+                }
+                line if line.contains("#{window_index}") => Ok("1 0".to_string()),
+                line if line.contains("#{window_width}") => {
+                    Ok("width: 160\nheight: 90".to_string())
+                }
+                _ => Ok(String::new()),
+            });
+        RecordingRunner::new_with_runner(
+            RunnerMock {
+                cmd_unit: MockCmdUnitMock::new(),
+                cmd_string,
+                cmd_bool: MockCmdBoolMock::new(),
+            },
+            Secrets::default(),
+        )
+    };
+
+    // The window is replaced at its index and laid out again
+    let recording = runner();
+    Tmux::new_with_runner(recording.clone()).respawn(&session, "infrastructure", None, false)?;
+    let recorded: Vec<String> = recording.recorded().iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        recorded[0],
+        "tmux new-window -Pdk -t valid:1 -n infrastructure -c /tmp/one -F '#{window_id}'"
+    );
+    assert!(recorded
+        .iter()
+        .any(|c| c.starts_with("tmux select-layout -t valid:@1 '") && !c.ends_with("tiled")));
+    assert!(!recorded.iter().any(|c| c.contains("code")));
+
+    // Only the pane is restarted, the layout is kept
+    let recording = runner();
+    Tmux::new_with_runner(recording.clone()).respawn(
+        &session,
+        "infrastructure",
+        Some("two"),
+        false,
+    )?;
+    let recorded: Vec<String> = recording.recorded().iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        recorded[0],
+        "tmux respawn-pane -k -t valid:@2.%5 -c /tmp/two"
+    );
+    assert!(recorded
+        .iter()
+        .any(|c| c.starts_with("tmux send-keys -t valid:@2.%5 'echo \"hello again 2\"'")));
+    assert!(!recorded.iter().any(|c| c.contains("select-layout")));
+
+    let error = Tmux::new_with_runner(runner())
+        .respawn(&session, "infrastructure", Some("one"), false)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Window 'infrastructure' has no pane named 'one'"
+    );
+    Ok(())
+}
+
+#[test]
+fn client_process_command_reads_command_line() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .expect("sleep to start");
+
+    // The command line shows up once the child has finished exec'ing
+    let command = (0..50).find_map(|_| {
+        TmuxClient::<RunnerMock>::get_process_command(child.id() as i32).or_else(|| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            None
+        })
+    });
+    let _ = child.kill();
+    let _ = child.wait();
+
+    assert_eq!(command.as_deref(), Some("sleep 30"));
+}
+
+#[test]
+fn mux_set_session_config_path_reports_errors() {
+    let mut cmd_unit = MockCmdUnitMock::new();
+    cmd_unit
+        .expect_run()
+        .withf(|cmd| cmd.to_string() == "tmux set-environment -t valid LAIO_CONFIG /tmp/valid.yaml")
+        .times(1)
+        .returning(|_| Err(miette::miette!("no server running")));
+
+    let tmux = Tmux::new_with_runner(RunnerMock {
+        cmd_unit,
+        cmd_string: MockCmdStringMock::new(),
+        cmd_bool: MockCmdBoolMock::new(),
+    });
+
+    let result = tmux.set_session_config_path("valid", "/tmp/valid.yaml");
+    assert!(result.is_err());
+}
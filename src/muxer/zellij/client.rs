@@ -2,11 +2,12 @@ use std::{
     env::temp_dir,
     fs::{remove_file, File},
     rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use crate::common::{muxer::client::Client, path::sanitize_filename};
+use crate::common::{muxer::client::Client, path::sanitize_filename, session_info::SessionDetails};
 use crate::{
-    cmd_basic, cmd_forget,
+    cmd_basic, cmd_forget, cmd_query,
     common::cmd::{Runner, Type},
 };
 use kdl::{KdlDocument, KdlNode};
@@ -75,7 +76,7 @@ impl<R: Runner> ZellijClient<R> {
 
     pub(crate) fn session_exists(&self, name: &str) -> bool {
         self.cmd_runner
-            .run(&cmd_basic!(
+            .run(&cmd_query!(
                 "sh",
                 args = [
                     "-c",
@@ -87,12 +88,12 @@ impl<R: Runner> ZellijClient<R> {
 
     pub(crate) fn is_inside_session(&self) -> bool {
         self.cmd_runner
-            .run(&cmd_basic!("printenv", args = ["ZELLIJ"]))
+            .run(&cmd_query!("printenv", args = ["ZELLIJ"]))
             .is_ok_and(|s: String| !s.is_empty())
     }
 
     pub(crate) fn current_session_name(&self) -> Result<String> {
-        self.cmd_runner.run(&cmd_basic!(
+        self.cmd_runner.run(&cmd_query!(
             "sh",
             args = ["-c", "printenv ZELLIJ_SESSION_NAME || true"]
         ))
@@ -100,7 +101,7 @@ impl<R: Runner> ZellijClient<R> {
 
     pub(crate) fn getenv(&self, name: &str, key: &str) -> Result<String> {
         if self.is_inside_session() {
-            self.cmd_runner.run(&cmd_basic!(
+            self.cmd_runner.run(&cmd_query!(
                 "sh",
                 args = ["-c", format!("printenv {} || true", key)]
             ))
@@ -126,26 +127,80 @@ impl<R: Runner> ZellijClient<R> {
             ))?;
             let result = self
                 .cmd_runner
-                .run(&cmd_basic!("cat", args = [&temp_path_str]))?;
+                .run(&cmd_query!("cat", args = [&temp_path_str]))?;
             remove_file(&temp_path_str).into_diagnostic()?;
 
             Ok(result)
         }
     }
 
-    pub(crate) fn list_sessions(&self) -> Result<Vec<(String, bool)>> {
+    /// Sessions with when they were created, from lines such as
+    /// `name [Created 1h 2m 3s ago] (current)`.
+    pub(crate) fn list_sessions(&self) -> Result<Vec<(String, SessionDetails)>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
         self.cmd_runner
-            .run(&cmd_basic!("zellij", args = ["list-sessions", "--short"]))
-            .map(|res: String| res.lines().map(|name| (name.to_string(), false)).collect())
+            .run(&cmd_query!(
+                "zellij",
+                args = ["list-sessions", "--no-formatting"]
+            ))
+            .map(|res: String| {
+                res.lines()
+                    // Exited sessions are kept to be resurrected, but aren't running
+                    .filter(|line| !line.trim().is_empty() && !line.contains("(EXITED"))
+                    .map(|line| {
+                        let (name, rest) = line.split_once(" [Created ").unwrap_or((line, ""));
+                        let created = rest
+                            .split_once(" ago]")
+                            .and_then(|(ago, _)| parse_duration(ago))
+                            .and_then(|ago| now.checked_sub(ago));
+                        let details = SessionDetails {
+                            muxer: "zellij".to_string(),
+                            created,
+                            ..Default::default()
+                        };
+                        (name.trim().to_string(), details)
+                    })
+                    .collect()
+            })
             .or_else(|_| Ok(vec![]))
     }
 
+    /// Names of the tabs of the current session.
+    pub(crate) fn tab_names(&self) -> Result<Vec<String>> {
+        self.cmd_runner
+            .run(&cmd_query!("zellij", args = ["action", "query-tab-names"]))
+            .map(|res: String| res.lines().map(str::to_string).collect())
+    }
+
     pub(crate) fn get_layout(&self) -> Result<KdlNode> {
         let res: String = self
             .cmd_runner
-            .run(&cmd_basic!("zellij", args = ["action", "dump-layout"]))?;
+            .run(&cmd_query!("zellij", args = ["action", "dump-layout"]))?;
         let kdl_doc = KdlDocument::parse_v1(res.as_str())?;
         let layout_node = kdl_doc.get("layout").expect("Missing layout node.").clone();
         Ok(layout_node)
     }
 }
+
+/// Seconds of a duration as zellij prints it, such as `1day 2h 3m 4s`.
+fn parse_duration(text: &str) -> Option<u64> {
+    text.split_whitespace()
+        .map(|part| {
+            let (value, unit) = part.split_at(part.find(|c: char| !c.is_ascii_digit())?);
+            let seconds = match unit {
+                "s" => 1,
+                "m" => 60,
+                "h" => 60 * 60,
+                "day" | "days" => 24 * 60 * 60,
+                "month" | "months" => 2_630_016,
+                "year" | "years" => 31_557_600,
+                "ms" | "us" | "ns" => 0,
+                _ => return None,
+            };
+            value.parse::<u64>().ok().map(|value| value * seconds)
+        })
+        .sum()
+}
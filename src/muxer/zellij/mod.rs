@@ -1,8 +1,8 @@
 mod client;
-mod model;
+pub(crate) mod model;
 mod mux;
 
-pub(crate) use mux::Zellij;
+pub(crate) use mux::{session_layout, Zellij};
 
 #[cfg(test)]
 mod test;
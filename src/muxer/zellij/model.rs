@@ -1,12 +1,13 @@
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 
 use kdl::{KdlDocument, KdlEntry, KdlNode, KdlValue};
 use miette::{bail, Result};
 use noyalib::compat::serde_yaml::Value;
 
 use crate::common::config::{Command, FlexDirection, Pane, Session, Window};
-use crate::common::path::relative_path;
+use crate::common::path::{relative_path, sanitize_path};
 
 impl Display for FlexDirection {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -30,20 +31,37 @@ impl FlexDirection {
 impl Command {
     pub fn from_kdl(cmd: &KdlValue, args: &[&KdlNode]) -> Command {
         Self {
-            command: cmd.to_string(),
+            command: cmd
+                .as_string()
+                .map_or_else(|| cmd.to_string(), |s| s.to_string()),
             args: args
                 .iter()
                 .flat_map(|node| {
-                    node.entries().iter().filter_map(|entry| {
-                        entry
-                            .value()
-                            .as_string()
-                            .map(|s| Value::String(s.to_string()))
-                    })
+                    node.entries()
+                        .iter()
+                        .filter_map(|entry| match entry.value() {
+                            KdlValue::String(s) => Some(Value::String(s.clone())),
+                            KdlValue::Integer(i) => Some(Value::from(*i as i64)),
+                            KdlValue::Float(f) => Some(Value::from(*f)),
+                            KdlValue::Bool(b) => Some(Value::Bool(*b)),
+                            KdlValue::Null => None,
+                        })
                 })
                 .collect::<Vec<Value>>(),
         }
     }
+
+    fn arg_as_kdl(arg: &Value) -> KdlValue {
+        match arg {
+            Value::String(s) => KdlValue::String(s.clone()),
+            _ => KdlValue::String(
+                noyalib::compat::serde_yaml::to_string(arg)
+                    .unwrap_or_default()
+                    .trim_end()
+                    .to_string(),
+            ),
+        }
+    }
 }
 
 impl Session {
@@ -66,6 +84,7 @@ impl Session {
     }
 
     pub(crate) fn from_kdl(name: &str, layout_node: &kdl::KdlNode) -> Self {
+        // `dump-layout` writes the cwd as a child node, `as_kdl` as a property
         let path = layout_node
             .children()
             .and_then(|children| {
@@ -73,14 +92,18 @@ impl Session {
                     .get("cwd")
                     .and_then(|cwd_node| cwd_node.entries().first())
                     .and_then(|e| e.value().as_string())
-                    .map(|s| s.to_string())
             })
+            .or_else(|| find_entry_value(layout_node, "cwd"))
+            .map(|s| s.to_string())
+            .or_else(|| common_cwd(layout_node))
             .unwrap_or(".".to_string());
 
         let window_nodes = extract_child_nodes(layout_node, "tab");
 
         Self {
             name: name.to_string(),
+            description: None,
+            tags: vec![],
             path: path.clone(),
             startup: vec![],
             shutdown: vec![],
@@ -105,6 +128,16 @@ impl Window {
             "split_direction",
             KdlValue::from(self.flex_direction.to_string()),
         ));
+        if let Some(path) = &self.path {
+            tab_node
+                .entries_mut()
+                .push(KdlEntry::new_prop("cwd", KdlValue::String(path.clone())));
+        }
+        if self.focus {
+            tab_node
+                .entries_mut()
+                .push(KdlEntry::new_prop("focus", KdlValue::Bool(true)));
+        }
 
         if !self.panes.is_empty() {
             let mut panes_doc = KdlDocument::new();
@@ -126,22 +159,56 @@ impl Window {
                     .unwrap_or("nameless")
                     .to_string();
 
+                if !extract_child_nodes(window_node, "floating_panes").is_empty() {
+                    log::warn!("Floating panes of tab '{name}' can't be exported and are skipped");
+                }
+
+                let path = find_entry_value(window_node, "cwd")
+                    .map(|cwd| relative_path(cwd, session_path).unwrap_or(".".to_string()));
+                let window_path = match &path {
+                    Some(path) => sanitize_path(path, session_path),
+                    None => session_path.clone(),
+                };
+
                 let pane_nodes = extract_child_nodes(window_node, "pane");
+                let mut panes = Pane::from_kdl(&pane_nodes, &window_path);
+                let mut split_direction = window_node.get("split_direction");
+
+                // Without the tab and status bar plugins, the default layout leaves a
+                // single pane holding the actual split
+                if let [pane] = panes.as_slice()
+                    && !pane.panes.is_empty()
+                    && pane.name.is_none()
+                    && pane.path == "."
+                    && let [pane_node] = pane_nodes
+                        .iter()
+                        .filter(|node| !is_plugin(node))
+                        .collect::<Vec<_>>()
+                        .as_slice()
+                {
+                    split_direction = pane_node.get("split_direction");
+                    panes = panes.remove(0).panes;
+                }
 
-                let panes = Pane::from_kdl(&pane_nodes, session_path);
-                let flex_direction = if panes.is_empty() {
-                    FlexDirection::Row
-                } else {
-                    FlexDirection::from_kdl(window_node.get("split_direction"))
+                let flex_direction = match split_direction {
+                    Some(direction) => FlexDirection::from_kdl(Some(direction)),
+                    None if panes.is_empty() => FlexDirection::Row,
+                    None => FlexDirection::Column,
                 };
-                let path = None;
+
+                // The only pane of a window always has focus
+                if let [pane] = panes.as_mut_slice()
+                    && pane.panes.is_empty()
+                {
+                    pane.focus = false;
+                }
 
                 Window {
                     name,
                     path,
                     flex_direction,
                     panes,
-                    focus: false,
+                    focus: window_node.get("focus").and_then(|v| v.as_bool()) == Some(true),
                 }
             })
             .collect()
@@ -156,6 +223,11 @@ impl Pane {
         pane_node
             .entries_mut()
             .push(KdlEntry::new_prop("size", KdlValue::String(percentage)));
+        if let Some(name) = &self.name {
+            pane_node
+                .entries_mut()
+                .push(KdlEntry::new_prop("name", KdlValue::String(name.clone())));
+        }
         if !self.panes.is_empty() {
             let mut children_doc = KdlDocument::new();
             pane_node.entries_mut().push(KdlEntry::new_prop(
@@ -169,12 +241,6 @@ impl Pane {
             }
             pane_node.set_children(children_doc);
         } else {
-            if self.name.is_some() {
-                pane_node.entries_mut().push(KdlEntry::new_prop(
-                    "name",
-                    KdlValue::String(self.name.clone().unwrap()),
-                ));
-            };
             if self.path != "." {
                 pane_node.entries_mut().push(KdlEntry::new_prop(
                     "cwd",
@@ -194,12 +260,9 @@ impl Pane {
                 if !command.args.is_empty() {
                     let mut args_node = KdlNode::new("args");
                     command.args.iter().for_each(|arg| {
-                        args_node.entries_mut().push(KdlEntry::new(KdlValue::String(
-                            noyalib::compat::serde_yaml::to_string(arg)
-                                .unwrap_or_default()
-                                .trim_end()
-                                .to_string(),
-                        )));
+                        args_node
+                            .entries_mut()
+                            .push(KdlEntry::new(Command::arg_as_kdl(arg)));
                     });
                     pane_node
                         .children_mut()
@@ -214,15 +277,16 @@ impl Pane {
     }
 
     pub(crate) fn from_kdl(pane_nodes: &[&KdlNode], session_path: &String) -> Vec<Pane> {
-        let size_strings: Vec<&str> = pane_nodes
+        // Tab and status bars are plugin panes, which have no place in a laio config
+        let pane_nodes: Vec<&KdlNode> = pane_nodes
             .iter()
-            .map(|n| {
-                n.get("size")
-                    .and_then(|value| value.as_string())
-                    .unwrap_or("100%")
-            })
+            .filter(|node| !is_plugin(node))
+            .copied()
             .collect();
 
+        let size_strings = pane_sizes(&pane_nodes);
+        let size_strings: Vec<&str> = size_strings.iter().map(String::as_str).collect();
+
         let ratios = calculate_ratios(&size_strings);
 
         pane_nodes
@@ -254,10 +318,9 @@ impl Pane {
                 let pane_nodes = extract_child_nodes(node, "pane");
                 let panes = Pane::from_kdl(&pane_nodes, session_path);
 
-                let flex_direction = if panes.is_empty() {
-                    FlexDirection::Row
-                } else {
-                    FlexDirection::from_kdl(node.get("split_direction"))
+                let flex_direction = match node.get("split_direction") {
+                    _ if panes.is_empty() => FlexDirection::Row,
+                    direction => FlexDirection::from_kdl(direction),
                 };
 
                 Pane {
@@ -270,7 +333,7 @@ impl Pane {
                     script: None,
                     panes,
                     zoom: false,
-                    focus: false,
+                    focus: node.get("focus").and_then(|v| v.as_bool()) == Some(true),
                 }
             })
             .collect()
@@ -302,6 +365,63 @@ pub(crate) fn calculate_ratios(percentages: &[&str]) -> Vec<usize> {
     values.iter().map(|&value| value / min_value).collect()
 }
 
+/// Percentage sizes of sibling panes. Panes without a percentage, such as those
+/// with a fixed number of rows, share what the others leave.
+fn pane_sizes(pane_nodes: &[&KdlNode]) -> Vec<String> {
+    let sizes: Vec<Option<usize>> = pane_nodes
+        .iter()
+        .map(|node| {
+            node.get("size")
+                .and_then(|value| value.as_string())
+                .and_then(|size| size.strip_suffix('%'))
+                .and_then(|size| size.parse().ok())
+        })
+        .collect();
+
+    let unsized_count = sizes.iter().filter(|size| size.is_none()).count();
+    let remaining = 100usize.saturating_sub(sizes.iter().flatten().sum());
+    let share = match unsized_count {
+        0 => 0,
+        count => (remaining / count).max(1),
+    };
+
+    sizes
+        .iter()
+        .map(|size| format!("{}%", size.unwrap_or(share)))
+        .collect()
+}
+
+fn is_plugin(node: &KdlNode) -> bool {
+    !extract_child_nodes(node, "plugin").is_empty()
+}
+
+/// Longest common directory of the absolute `cwd`s in a layout, for layouts that
+/// don't state one.
+fn common_cwd(layout_node: &KdlNode) -> Option<String> {
+    fn collect<'a>(node: &'a KdlNode, cwds: &mut Vec<&'a Path>) {
+        if let Some(cwd) = find_entry_value(node, "cwd").filter(|cwd| cwd.starts_with('/')) {
+            cwds.push(Path::new(cwd));
+        }
+        node.iter_children().for_each(|child| collect(child, cwds));
+    }
+
+    let mut cwds = vec![];
+    collect(layout_node, &mut cwds);
+
+    let (first, rest) = cwds.split_first()?;
+    let common = rest.iter().fold(first.to_path_buf(), |common, cwd| {
+        common
+            .components()
+            .zip(cwd.components())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a)
+            .collect::<PathBuf>()
+    });
+
+    (common != Path::new("/") && !common.as_os_str().is_empty())
+        .then(|| common.to_string_lossy().into_owned())
+}
+
 pub(crate) fn extract_child_nodes<'a>(node: &'a KdlNode, name: &str) -> Vec<&'a KdlNode> {
     node.iter_children()
         .filter(|child| child.name().value() == name)
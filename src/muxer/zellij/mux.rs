@@ -6,36 +6,61 @@ use crate::{
     app::manager::session::manager::LAIO_CONFIG,
     common::{
         cmd::{Runner, ShellRunner},
-        config::Session,
+        config::{secrets::Secrets, Session},
         muxer::{Client, Multiplexer},
-        path::{resolve_symlink, sanitize_filename, sanitize_path, to_absolute_path},
-        session_info::SessionInfo,
+        path::{
+            config_search_path, resolve_symlink, sanitize_filename, sanitize_path,
+            split_search_path, to_absolute_path,
+        },
+        session_info::{SessionDetails, SessionInfo},
     },
 };
 
 use super::client::ZellijClient;
-pub(crate) struct Zellij<R: Runner = ShellRunner> {
-    client: ZellijClient<R>,
+
+/// The KDL layout a session is started with, in the path of its first pane.
+pub(crate) fn session_layout(session: &Session) -> Result<String> {
+    let cwd = session
+        .windows
+        .first()
+        .and_then(|window| window.first_leaf_path())
+        .map(|path| sanitize_path(path, &session.path))
+        .unwrap_or(session.path.clone());
+
+    Ok(session.as_kdl(&cwd)?.to_string())
 }
 
-impl Zellij {
-    pub fn new() -> Self {
-        Self::new_with_runner(ShellRunner::new())
-    }
+pub(crate) struct Zellij<R: Runner = ShellRunner> {
+    client: ZellijClient<R>,
+    /// Used to render the configs of running sessions.
+    secrets: Secrets,
 }
 
 impl<R: Runner> Zellij<R> {
     pub fn new_with_runner(runner: R) -> Self {
         Self {
             client: ZellijClient::new(Rc::new(runner)),
+            secrets: Secrets::default(),
         }
     }
 
-    fn session_to_layout(&self, cwd: &str, session: &Session, _skip_cmds: bool) -> Result<String> {
+    /// Uses `secrets` to render the configs of running sessions, e.g. for their
+    /// shutdown hooks.
+    pub(crate) fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    fn session_to_layout(&self, session: &Session, _skip_cmds: bool) -> Result<String> {
         let mut layout_location = temp_dir();
         layout_location.push(format!("{}.kdl", sanitize_filename(&session.name)));
         let layout_location = layout_location.to_str().unwrap().to_string();
-        let session_kld = session.as_kdl(cwd)?.to_string();
+        let session_kld = session_layout(session)?;
+        // A dry run only shows where the layout would be written
+        if self.client.get_runner().is_dry_run() {
+            log::info!("Layout of '{}':\n{session_kld}", session.name);
+            return Ok(layout_location);
+        }
 
         let mut file = OpenOptions::new()
             .write(true)
@@ -68,7 +93,11 @@ impl<R: Runner> Multiplexer for Zellij<R> {
 
         if !skip_cmds {
             let commands = if session.startup_script.is_some() {
-                let cmd = session.startup_script.clone().unwrap().to_cmd()?;
+                let cmd = session
+                    .startup_script
+                    .clone()
+                    .unwrap()
+                    .to_cmd(self.client.get_runner().is_dry_run())?;
                 &session
                     .startup
                     .clone()
@@ -82,14 +111,7 @@ impl<R: Runner> Multiplexer for Zellij<R> {
             self.client.run_commands(commands, &session.path)?;
         }
 
-        let cwd = session
-            .windows
-            .first()
-            .and_then(|window| window.first_leaf_path())
-            .map(|path| sanitize_path(path, &session.path))
-            .unwrap_or(session.path.clone());
-
-        let layout: String = self.session_to_layout(cwd.as_str(), session, skip_cmds)?;
+        let layout: String = self.session_to_layout(session, skip_cmds)?;
         let _res: () = self.client.create_session_with_layout(
             &session.name,
             env_vars,
@@ -121,15 +143,15 @@ impl<R: Runner> Multiplexer for Zellij<R> {
 
         if stop_all || (stop_other && self.client.is_inside_session()) {
             log::trace!("Closing all/other laio sessions.");
-            for info in self.list_sessions()?.into_iter() {
-                if info.name == current_session_name {
+            for (name, _) in self.client.list_sessions()?.into_iter() {
+                if name == current_session_name {
                     log::debug!("Skipping current session: {current_session_name:?}");
                     continue;
                 };
 
-                if self.is_laio_session(&info.name)? {
-                    log::debug!("Closing session: {:?}", info.name);
-                    self.stop(&Some(info.name.to_string()), &None, skip_cmds, false, false)?;
+                if self.is_laio_session(&name)? {
+                    log::debug!("Closing session: {:?}", name);
+                    self.stop(&Some(name.to_string()), &None, skip_cmds, false, false)?;
                 }
             }
             if !self.client.is_inside_session() {
@@ -152,7 +174,11 @@ impl<R: Runner> Multiplexer for Zellij<R> {
                 // If session was provided (with variables), use it directly
                 if let Some(sess) = session {
                     let commands = if sess.shutdown_script.is_some() {
-                        let cmd = sess.shutdown_script.clone().unwrap().to_cmd()?;
+                        let cmd = sess
+                            .shutdown_script
+                            .clone()
+                            .unwrap()
+                            .to_cmd(self.client.get_runner().is_dry_run())?;
                         &sess
                             .shutdown
                             .clone()
@@ -176,10 +202,16 @@ impl<R: Runner> Multiplexer for Zellij<R> {
                         let sess = Session::from_config(
                             &resolve_symlink(&to_absolute_path(&config)?)?,
                             variables.as_deref(),
+                            &split_search_path(&config_search_path(None)),
+                            &self.secrets,
                         )?;
 
                         let commands = if sess.shutdown_script.is_some() {
-                            let cmd = sess.shutdown_script.clone().unwrap().to_cmd()?;
+                            let cmd = sess
+                                .shutdown_script
+                                .clone()
+                                .unwrap()
+                                .to_cmd(self.client.get_runner().is_dry_run())?;
                             &sess
                                 .shutdown
                                 .clone()
@@ -212,6 +244,36 @@ impl<R: Runner> Multiplexer for Zellij<R> {
         result.and(stop_result)
     }
 
+    fn restart(
+        &self,
+        name: &str,
+        running: &Option<Session>,
+        session: &Session,
+        env_vars: &[(&str, &str)],
+        skip_attach: bool,
+        skip_cmds: bool,
+    ) -> Result<()> {
+        let current = self.client.current_session_name()?;
+        if current == name || running.as_ref().is_some_and(|r| r.name == current) {
+            bail!("Zellij can't restart the session laio runs in, run it outside of '{name}'");
+        }
+        // Without the running session there are no shutdown hooks to run
+        let skip_shutdown = skip_cmds || running.is_none();
+        self.stop(
+            &Some(name.to_string()),
+            running,
+            skip_shutdown,
+            false,
+            false,
+        )?;
+        self.start(session, env_vars, skip_attach, skip_cmds)
+    }
+
+    fn current_session_name(&self) -> Result<Option<String>> {
+        let name = self.client.current_session_name()?;
+        Ok((!name.is_empty()).then_some(name))
+    }
+
     fn get_session_config_path(&self, name: &str) -> Result<Option<String>> {
         match self.client.getenv(name, LAIO_CONFIG) {
             Ok(config_path) => Ok(Some(config_path)),
@@ -219,6 +281,13 @@ impl<R: Runner> Multiplexer for Zellij<R> {
         }
     }
 
+    fn set_session_config_path(&self, name: &str, _config_path: &str) -> Result<()> {
+        bail!(
+            "Zellij can't change the environment of running session '{}'",
+            name
+        )
+    }
+
     fn get_session_variables(&self, name: &str) -> Result<Option<Vec<String>>> {
         use crate::app::manager::session::manager::{decode_variables, LAIO_VARS};
 
@@ -245,12 +314,27 @@ impl<R: Runner> Multiplexer for Zellij<R> {
     }
 
     fn list_sessions(&self) -> Result<Vec<SessionInfo>> {
-        self.client.list_sessions().map(|sessions| {
-            sessions
-                .into_iter()
-                .map(|(name, is_attached)| SessionInfo::active(name, is_attached))
-                .collect()
-        })
+        let current = self.client.current_session_name()?;
+        self.client
+            .list_sessions()?
+            .into_iter()
+            .map(|(name, details)| {
+                // Reading the environment of another session would open a pane in it
+                if name != current {
+                    return Ok(SessionInfo::active(name, false).with_session_details(details, None));
+                }
+                let config_path = self
+                    .get_session_config_path(&name)?
+                    .filter(|path| !path.is_empty());
+                let details = SessionDetails {
+                    windows: self.client.tab_names().ok().map(|tabs| tabs.len()),
+                    variables: self.get_session_variables(&name)?.unwrap_or_default(),
+                    is_laio_session: Some(config_path.is_some()),
+                    ..details
+                };
+                Ok(SessionInfo::active(name, false).with_session_details(details, config_path))
+            })
+            .collect()
     }
 
     fn switch(&self, name: &str, skip_attach: bool) -> Result<bool> {
@@ -265,11 +349,45 @@ impl<R: Runner> Multiplexer for Zellij<R> {
         Ok(false)
     }
 
-    fn get_session(&self) -> Result<Session> {
+    fn apply(&self, session: &Session, _prune: bool, _skip_cmds: bool) -> Result<Vec<String>> {
+        bail!(
+            "Zellij can't add tabs or panes to running session '{}'",
+            session.name
+        )
+    }
+
+    fn respawn(
+        &self,
+        session: &Session,
+        window: &str,
+        _pane: Option<&str>,
+        _skip_cmds: bool,
+    ) -> Result<()> {
+        bail!(
+            "Zellij can't respawn tab '{window}' of running session '{}'",
+            session.name
+        )
+    }
+
+    fn supports_snapshots(&self) -> bool {
+        false
+    }
+
+    fn capture_panes(&self, name: &str) -> Result<Vec<Vec<String>>> {
+        bail!("Zellij can't capture the panes of session '{name}'")
+    }
+
+    fn get_session(&self, session: Option<&str>) -> Result<Session> {
         if !self.client.is_inside_session() {
             bail!("You do not seem to be inside a Zellij session.")
         }
         let name = self.client.current_session_name()?;
+        // Zellij only dumps the layout of the session it's run in
+        if let Some(session) = session
+            && session != name
+        {
+            bail!("Zellij can only read the layout of the current session '{name}'");
+        }
 
         let layout_node = self.client.get_layout()?;
         let session = Session::from_kdl(name.as_str(), &layout_node);
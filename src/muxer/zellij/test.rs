@@ -3,9 +3,10 @@ use crate::common::{
         test::{MockCmdBoolMock, MockCmdStringMock, MockCmdUnitMock, RunnerMock},
         Type,
     },
-    config::Session,
+    config::{FlexDirection, Session},
     muxer::Multiplexer,
 };
+use kdl::KdlDocument;
 use miette::{IntoDiagnostic, Result};
 
 use super::Zellij;
@@ -41,7 +42,7 @@ fn mux_start_session() -> Result<()> {
     cmd_bool
         .expect_run()
         .times(1)
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "sh -c zellij list-sessions --short | grep \"valid\""))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "sh -c zellij list-sessions --short | grep \"valid\""))
         .returning(|_| Ok(false));
 
     cmd_string
@@ -71,6 +72,37 @@ fn mux_start_session() -> Result<()> {
 
     Ok(())
 }
+#[test]
+fn mux_start_dry_run_writes_no_layout() -> Result<()> {
+    use crate::common::{cmd::RecordingRunner, config::secrets::Secrets};
+
+    let mut session: Session =
+        noyalib::compat::serde_yaml::from_str(include_str!("../../common/config/test/valid.yaml"))
+            .unwrap();
+    session.name = format!("laio_dry_run_{}", std::process::id());
+    let layout = std::env::temp_dir().join(format!("{}.kdl", session.name));
+
+    let mut cmd_bool = MockCmdBoolMock::new();
+    cmd_bool.expect_run().returning(|_| Ok(false));
+    let recording = RecordingRunner::new_with_runner(
+        RunnerMock {
+            cmd_unit: MockCmdUnitMock::new(),
+            cmd_string: MockCmdStringMock::new(),
+            cmd_bool,
+        },
+        Secrets::default(),
+    );
+
+    Zellij::new_with_runner(recording.clone()).start(&session, &[], false, true)?;
+
+    let recorded: Vec<String> = recording.recorded().iter().map(|c| c.to_string()).collect();
+    assert!(recorded
+        .iter()
+        .any(|c| c.contains(&format!("--new-session-with-layout {}", layout.display()))));
+    assert!(!layout.exists());
+    Ok(())
+}
+
 #[test]
 #[ignore = "flaky on GitHub Actions due to runner environment issues"]
 fn mux_stop_session() -> Result<()> {
@@ -84,7 +116,7 @@ fn mux_stop_session() -> Result<()> {
         .expect_run()
         .times(1)
         .withf(
-            |cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "sh -c printenv ZELLIJ_SESSION_NAME || true"),
+            |cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "sh -c printenv ZELLIJ_SESSION_NAME || true"),
         )
         .returning(|_| Ok("valid".to_string()));
 
@@ -92,7 +124,7 @@ fn mux_stop_session() -> Result<()> {
         .expect_run()
         .times(2)
         .withf(
-            |cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "sh -c printenv LAIO_CONFIG || true"),
+            |cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "sh -c printenv LAIO_CONFIG || true"),
         )
         .returning({
             move |_| Ok(path_str.to_string())
@@ -101,13 +133,13 @@ fn mux_stop_session() -> Result<()> {
     cmd_string
         .expect_run()
         .times(2)
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "printenv ZELLIJ"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "printenv ZELLIJ"))
         .returning(|_| Ok("0".to_string()));
 
     cmd_bool
         .expect_run()
         .times(2)
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "sh -c zellij list-sessions --short | grep \"valid\""))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "sh -c zellij list-sessions --short | grep \"valid\""))
         .returning(|_| Ok(true));
 
     cmd_unit
@@ -154,14 +186,14 @@ fn mux_get_session() -> Result<()> {
     cmd_string
         .expect_run()
         .times(1)
-        .withf(|cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "printenv ZELLIJ"))
+        .withf(|cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "printenv ZELLIJ"))
         .returning(|_| Ok("0".to_string()));
 
     cmd_string
         .expect_run()
         .times(1)
         .withf(
-            |cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "sh -c printenv ZELLIJ_SESSION_NAME || true"),
+            |cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "sh -c printenv ZELLIJ_SESSION_NAME || true"),
         )
         .returning(|_| Ok("valid".to_string()));
 
@@ -169,7 +201,7 @@ fn mux_get_session() -> Result<()> {
         .expect_run()
         .times(1)
         .withf(
-            |cmd| matches!(cmd, Type::Basic(_) if cmd.to_string() == "zellij action dump-layout"),
+            |cmd| matches!(cmd, Type::Query(_) if cmd.to_string() == "zellij action dump-layout"),
         )
         .returning(move |_| Ok(valid_kdl.to_string()));
 
@@ -180,9 +212,192 @@ fn mux_get_session() -> Result<()> {
     };
 
     let zellij = Zellij::new_with_runner(runner);
-    let result = zellij.get_session()?;
+    let result = zellij.get_session(None)?;
 
     let expected_session_yaml = to_yaml(noyalib::compat::serde_yaml::to_string(&result).into_diagnostic()?)?;
     assert_eq!(valid_yaml, expected_session_yaml);
     Ok(())
 }
+
+/// Converts a session to a layout and back, as `start` and `get_session` do.
+fn round_trip(session: &Session) -> Result<Session> {
+    let kdl = session.as_kdl(&session.path)?.to_string();
+    let doc = KdlDocument::parse_v1(&kdl)?;
+    let layout_node = doc.get("layout").expect("Missing layout node.");
+    Ok(Session::from_kdl(&session.name, layout_node))
+}
+
+#[test]
+fn kdl_round_trip() -> Result<()> {
+    let configs = [
+        include_str!("../../common/config/test/to_yaml.yaml"),
+        include_str!("../../common/config/test/window_path.yaml"),
+        "
+name: round-trip
+path: /work
+windows:
+  - name: editor
+    path: app
+    flex_direction: column
+    panes:
+      - flex: 3
+        name: main
+        commands:
+          - command: /usr/bin/nvim
+            args:
+              - -c
+              - set number
+      - flex: 1
+        name: tools
+        flex_direction: row
+        panes:
+          - flex: 1
+            path: logs
+            focus: true
+            commands:
+              - command: tail
+                args:
+                  - -n
+                  - '100'
+                  - app.log
+          - flex: 1
+            name: shell
+  - name: scratch
+    focus: true
+",
+    ];
+
+    for yaml in configs {
+        let session: Session = noyalib::compat::serde_yaml::from_str(yaml).into_diagnostic()?;
+        let result = round_trip(&session)?;
+
+        assert_eq!(
+            noyalib::compat::serde_yaml::to_string(&result).into_diagnostic()?,
+            noyalib::compat::serde_yaml::to_string(&session).into_diagnostic()?,
+            "round trip of {}",
+            session.name
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn kdl_from_dump_layout() -> Result<()> {
+    let dump = r#"
+layout {
+    cwd "/work"
+    tab name="code" hide_floating_panes=true {
+        pane size=1 borderless=true {
+            plugin location="zellij:tab-bar"
+        }
+        pane split_direction="vertical" {
+            pane command="/usr/bin/nvim" name="editor" size="70%" {
+                args "src/main.rs"
+                start_suspended true
+            }
+            pane cwd="/work/docs" focus=true
+        }
+        pane size=2 borderless=true {
+            plugin location="zellij:status-bar"
+        }
+        floating_panes {
+            pane command="htop"
+        }
+    }
+    tab name="server" cwd="/work/api" focus=true {
+        pane command="cargo" focus=true {
+            args "run"
+        }
+    }
+}
+"#;
+    let doc = KdlDocument::parse_v1(dump)?;
+    let session = Session::from_kdl("dump", doc.get("layout").unwrap());
+
+    assert_eq!(session.path, "/work");
+
+    let code = &session.windows[0];
+    assert!(!code.focus);
+    assert_eq!(code.flex_direction, FlexDirection::Row);
+    assert_eq!(code.panes.len(), 2);
+    assert_eq!(code.panes[0].name.as_deref(), Some("editor"));
+    assert_eq!(
+        code.panes[0].commands[0].to_string(),
+        "/usr/bin/nvim src/main.rs"
+    );
+    assert_eq!(code.panes[1].path, "docs");
+    assert!(code.panes[1].focus);
+    // The pane without a size takes the remaining 30%
+    assert_eq!(
+        code.panes.iter().map(|p| p.flex).collect::<Vec<_>>(),
+        vec![2, 1]
+    );
+
+    let server = &session.windows[1];
+    assert!(server.focus);
+    assert_eq!(server.path.as_deref(), Some("api"));
+    assert_eq!(server.panes.len(), 1);
+    assert!(!server.panes[0].focus);
+    assert_eq!(server.panes[0].commands[0].to_string(), "cargo run");
+    Ok(())
+}
+
+#[test]
+fn mux_list_sessions() -> Result<()> {
+    let cmd_unit = MockCmdUnitMock::new();
+    let mut cmd_string = MockCmdStringMock::new();
+    let cmd_bool = MockCmdBoolMock::new();
+
+    let outputs = [
+        ("sh -c printenv ZELLIJ_SESSION_NAME || true", "work"),
+        (
+            "zellij list-sessions --no-formatting",
+            "old [Created 2days ago] (EXITED - attach to resurrect)\nother [Created 1day 2h ago]\nwork [Created 3m 20s ago] (current)\n",
+        ),
+        ("printenv ZELLIJ", "0"),
+        ("sh -c printenv LAIO_CONFIG || true", "/configs/work.yaml"),
+        ("sh -c printenv LAIO_VARS || true", "env=dev"),
+        ("zellij action query-tab-names", "code\nlogs\n"),
+    ];
+    for (command, output) in outputs {
+        cmd_string
+            .expect_run()
+            .withf(move |cmd| cmd.to_string() == command)
+            .returning(move |_| Ok(output.to_string()));
+    }
+
+    let runner = RunnerMock {
+        cmd_unit,
+        cmd_string,
+        cmd_bool,
+    };
+    let zellij = Zellij::new_with_runner(runner);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .into_diagnostic()?
+        .as_secs();
+
+    // Exited sessions aren't running
+    let sessions = zellij.list_sessions()?;
+    assert_eq!(sessions.len(), 2);
+
+    // Only the environment and tabs of the current session can be read
+    let other = sessions[0].details.as_ref().unwrap();
+    assert_eq!(sessions[0].name, "other");
+    assert_eq!(other.muxer, "zellij");
+    assert!(other.created.unwrap().abs_diff(now - 26 * 60 * 60) <= 5);
+    assert_eq!(other.windows, None);
+    assert_eq!(other.is_laio_session, None);
+
+    let work = sessions[1].details.as_ref().unwrap();
+    assert_eq!(sessions[1].name, "work");
+    assert_eq!(
+        sessions[1].config_path.as_deref(),
+        Some("/configs/work.yaml")
+    );
+    assert!(work.created.unwrap().abs_diff(now - 200) <= 5);
+    assert_eq!(work.windows, Some(2));
+    assert_eq!(work.variables, vec!["env=dev"]);
+    assert_eq!(work.is_laio_session, Some(true));
+    Ok(())
+}